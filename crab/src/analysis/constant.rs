@@ -0,0 +1,115 @@
+use std::rc::Rc;
+
+use crate::{
+    binding::bound_tree::{
+        BoundBinaryOperationKind, BoundExpression, BoundExpressionId, BoundProgram,
+        BoundUnaryOperationKind,
+    },
+    common::{
+        arithmetic::{add, divide, multiply, negate, subtract, ArithmeticError},
+        types::Object,
+    },
+};
+
+/// Why an operation on constants has no constant result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FoldError {
+    Overflow,
+    DivisionByZero,
+    Mismatch,
+}
+
+impl From<ArithmeticError> for FoldError {
+    fn from(error: ArithmeticError) -> Self {
+        match error {
+            ArithmeticError::Overflow => FoldError::Overflow,
+            ArithmeticError::DivisionByZero => FoldError::DivisionByZero,
+        }
+    }
+}
+
+pub(crate) fn evaluate_constant(
+    program: &BoundProgram,
+    expression: BoundExpressionId,
+) -> Option<Object> {
+    match program.expression(expression) {
+        BoundExpression::Literal(expression) => Some(expression.value.clone()),
+        BoundExpression::Variable(_) | BoundExpression::Assignment(_) => None,
+        BoundExpression::Unary(expression) => {
+            let right = evaluate_constant(program, expression.right)?;
+            fold_unary(&expression.operator.operation_kind, right).ok()
+        }
+        BoundExpression::Binary(expression) => {
+            let left = evaluate_constant(program, expression.left)?;
+            let right = evaluate_constant(program, expression.right)?;
+            fold_binary(&expression.operator.operation_kind, left, right).ok()
+        }
+    }
+}
+
+pub(crate) fn evaluate_condition(
+    program: &BoundProgram,
+    expression: BoundExpressionId,
+) -> Option<bool> {
+    match evaluate_constant(program, expression)? {
+        Object::Boolean(value) => Some(value),
+        _ => None,
+    }
+}
+
+pub(crate) fn fold_unary(
+    operation: &BoundUnaryOperationKind,
+    right: Object,
+) -> Result<Object, FoldError> {
+    match (operation, right) {
+        (BoundUnaryOperationKind::Identity, Object::Number(n)) => Ok(Object::Number(n)),
+        (BoundUnaryOperationKind::Negation, Object::Number(n)) => Ok(Object::Number(negate(n)?)),
+        (BoundUnaryOperationKind::LogicalNegation, Object::Boolean(b)) => Ok(Object::Boolean(!b)),
+        _ => Err(FoldError::Mismatch),
+    }
+}
+
+pub(crate) fn fold_binary(
+    operation: &BoundBinaryOperationKind,
+    left: Object,
+    right: Object,
+) -> Result<Object, FoldError> {
+    match (operation, left, right) {
+        (BoundBinaryOperationKind::Addition, Object::Number(l), Object::Number(r)) => {
+            Ok(Object::Number(add(l, r)?))
+        }
+        (BoundBinaryOperationKind::Addition, Object::String(l), Object::String(r)) => {
+            Ok(Object::String(Rc::from([&*l, &*r].concat())))
+        }
+        (BoundBinaryOperationKind::Subtraction, Object::Number(l), Object::Number(r)) => {
+            Ok(Object::Number(subtract(l, r)?))
+        }
+        (BoundBinaryOperationKind::Multiplication, Object::Number(l), Object::Number(r)) => {
+            Ok(Object::Number(multiply(l, r)?))
+        }
+        (BoundBinaryOperationKind::Division, Object::Number(l), Object::Number(r)) => {
+            Ok(Object::Number(divide(l, r)?))
+        }
+        (BoundBinaryOperationKind::Greater, Object::Number(l), Object::Number(r)) => {
+            Ok(Object::Boolean(l > r))
+        }
+        (BoundBinaryOperationKind::Lesser, Object::Number(l), Object::Number(r)) => {
+            Ok(Object::Boolean(l < r))
+        }
+        (BoundBinaryOperationKind::GreaterEqual, Object::Number(l), Object::Number(r)) => {
+            Ok(Object::Boolean(l >= r))
+        }
+        (BoundBinaryOperationKind::LesserEqual, Object::Number(l), Object::Number(r)) => {
+            Ok(Object::Boolean(l <= r))
+        }
+        (BoundBinaryOperationKind::LogicalAnd, Object::Boolean(l), Object::Boolean(r)) => {
+            Ok(Object::Boolean(l && r))
+        }
+        (BoundBinaryOperationKind::LogicalOr, Object::Boolean(l), Object::Boolean(r)) => {
+            Ok(Object::Boolean(l || r))
+        }
+        (BoundBinaryOperationKind::NotEqual, l, r) => Ok(Object::Boolean(l != r)),
+        (BoundBinaryOperationKind::Equal, l, r) => Ok(Object::Boolean(l == r)),
+        _ => Err(FoldError::Mismatch),
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{
+    binding::bound_tree::{BoundExpressionId, BoundProgram, BoundStatement, BoundStatementId},
+    common::arena::SideTable,
+};
+
+use super::constant::evaluate_condition;
+
+pub type BlockId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    Always,
+    WhenTrue,
+    WhenFalse,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Edge {
+    pub to: BlockId,
+    pub kind: EdgeKind,
+}
+
+/// A straight-line run of statements. Blocks ending in a branch carry the condition that selects
+/// between their `WhenTrue` and `WhenFalse` edges; the header of a `for` loop has no condition
+/// expression since its test against the upper bound is implicit.
+#[derive(Debug, Default)]
+pub struct BasicBlock {
+    pub statements: Vec<BoundStatementId>,
+    pub condition: Option<BoundExpressionId>,
+    pub successors: Vec<Edge>,
+}
+
+/// Control-flow graph of a bound program. Edges that a constant condition can never take are
+/// left out, so reachability follows directly from the graph.
+pub struct ControlFlowGraph {
+    blocks: Vec<BasicBlock>,
+    entry: BlockId,
+    exit: BlockId,
+    starts: SideTable<BoundStatementId, BlockId>,
+}
+
+impl ControlFlowGraph {
+    pub fn build(program: &BoundProgram) -> Self {
+        let mut graph = Self {
+            blocks: vec![BasicBlock::default()],
+            entry: 0,
+            exit: 0,
+            starts: SideTable::default(),
+        };
+        let mut current = graph.entry;
+        for &statement in program.root() {
+            current = graph.build_statement(program, statement, current);
+        }
+        graph.exit = graph.new_block();
+        graph.connect(current, graph.exit, EdgeKind::Always);
+        graph
+    }
+
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    pub fn entry(&self) -> BlockId {
+        self.entry
+    }
+
+    pub fn exit(&self) -> BlockId {
+        self.exit
+    }
+
+    pub fn predecessors(&self, block: BlockId) -> Vec<BlockId> {
+        (0..self.blocks.len())
+            .filter(|id| {
+                self.blocks[*id]
+                    .successors
+                    .iter()
+                    .any(|edge| edge.to == block)
+            })
+            .collect()
+    }
+
+    pub fn reachable_blocks(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut pending = vec![self.entry];
+        while let Some(block) = pending.pop() {
+            if !reachable[block] {
+                reachable[block] = true;
+                pending.extend(self.blocks[block].successors.iter().map(|edge| edge.to));
+            }
+        }
+        reachable
+    }
+
+    /// The block in which `statement` starts executing, if it is part of this graph.
+    pub fn block_of(&self, statement: BoundStatementId) -> Option<BlockId> {
+        self.starts.get(statement).copied()
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(BasicBlock::default());
+        self.blocks.len() - 1
+    }
+
+    fn connect(&mut self, from: BlockId, to: BlockId, kind: EdgeKind) {
+        self.blocks[from].successors.push(Edge { to, kind });
+    }
+
+    fn build_statement(
+        &mut self,
+        program: &BoundProgram,
+        id: BoundStatementId,
+        current: BlockId,
+    ) -> BlockId {
+        match program.statement(id) {
+            BoundStatement::Expression(_) | BoundStatement::Print(_) | BoundStatement::Var(_) => {
+                self.starts.insert(id, current);
+                self.blocks[current].statements.push(id);
+                current
+            }
+            BoundStatement::Block(block) => {
+                self.starts.insert(id, current);
+                let mut current = current;
+                for &statement in &block.statements {
+                    current = self.build_statement(program, statement, current);
+                }
+                current
+            }
+            BoundStatement::If(if_statement) => {
+                self.starts.insert(id, current);
+                self.blocks[current].condition = Some(if_statement.condition);
+                let constant = evaluate_condition(program, if_statement.condition);
+                let join = self.new_block();
+
+                let consequence = self.new_block();
+                if constant != Some(false) {
+                    self.connect(current, consequence, EdgeKind::WhenTrue);
+                }
+                let end = self.build_statement(program, if_statement.consequence, consequence);
+                self.connect(end, join, EdgeKind::Always);
+
+                let alternative = match if_statement.else_clause {
+                    Some(else_clause) => {
+                        let alternative = self.new_block();
+                        let end = self.build_statement(program, else_clause, alternative);
+                        self.connect(end, join, EdgeKind::Always);
+                        alternative
+                    }
+                    None => join,
+                };
+                if constant != Some(true) {
+                    self.connect(current, alternative, EdgeKind::WhenFalse);
+                }
+                join
+            }
+            BoundStatement::While(while_statement) => {
+                let header = self.new_block();
+                self.connect(current, header, EdgeKind::Always);
+                self.starts.insert(id, header);
+                self.blocks[header].condition = Some(while_statement.condition);
+                let constant = evaluate_condition(program, while_statement.condition);
+                self.build_loop(program, header, while_statement.body, constant)
+            }
+            BoundStatement::For(for_statement) => {
+                self.starts.insert(id, current);
+                let header = self.new_block();
+                self.connect(current, header, EdgeKind::Always);
+                self.build_loop(program, header, for_statement.body, None)
+            }
+        }
+    }
+
+    fn build_loop(
+        &mut self,
+        program: &BoundProgram,
+        header: BlockId,
+        body: BoundStatementId,
+        constant: Option<bool>,
+    ) -> BlockId {
+        let body_start = self.new_block();
+        if constant != Some(false) {
+            self.connect(header, body_start, EdgeKind::WhenTrue);
+        }
+        let body_end = self.build_statement(program, body, body_start);
+        self.connect(body_end, header, EdgeKind::Always);
+        let exit = self.new_block();
+        if constant != Some(true) {
+            self.connect(header, exit, EdgeKind::WhenFalse);
+        }
+        exit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        binding::{bindings::Bindings, bound_tree::BoundProgram},
+        common::intern::Interner,
+        compilation::Compilation,
+    };
+
+    use super::{ControlFlowGraph, EdgeKind};
+
+    fn bind(source: &str) -> BoundProgram {
+        Compilation::compile(
+            source,
+            Rc::new(RefCell::new(Bindings::default())),
+            Rc::new(RefCell::new(Interner::default())),
+        )
+        .bound_program
+    }
+
+    /// Whether each statement at the top of the program can run.
+    fn reachable_roots(source: &str) -> Vec<bool> {
+        let program = bind(source);
+        let graph = ControlFlowGraph::build(&program);
+        let reachable = graph.reachable_blocks();
+        program
+            .root()
+            .iter()
+            .map(|&statement| reachable[graph.block_of(statement).unwrap()])
+            .collect()
+    }
+
+    #[test]
+    fn straight_line_code_is_one_block() {
+        let program = bind("var a = 1\nprint a\na = 2\n");
+        let graph = ControlFlowGraph::build(&program);
+        assert_eq!(graph.blocks()[graph.entry()].statements.len(), 3);
+        assert_eq!(graph.predecessors(graph.exit()), [graph.entry()]);
+    }
+
+    #[test]
+    fn both_branches_of_an_if_join_again() {
+        let program = bind("var c = true\nif c print 1 else print 2\nprint 3\n");
+        let graph = ControlFlowGraph::build(&program);
+        let entry = &graph.blocks()[graph.entry()];
+        assert!(entry.condition.is_some());
+        let kinds: Vec<EdgeKind> = entry.successors.iter().map(|edge| edge.kind).collect();
+        assert_eq!(kinds, [EdgeKind::WhenTrue, EdgeKind::WhenFalse]);
+        let join = graph.block_of(program.root()[2]).unwrap();
+        assert_eq!(graph.predecessors(join).len(), 2);
+    }
+
+    #[test]
+    fn loops_lead_back_to_their_header() {
+        let program = bind("var i = 0\nwhile i < 3 i = i + 1\n");
+        let graph = ControlFlowGraph::build(&program);
+        let header = graph.block_of(program.root()[1]).unwrap();
+        assert_eq!(graph.predecessors(header).len(), 2);
+        assert_eq!(graph.blocks()[header].successors.len(), 2);
+    }
+
+    #[test]
+    fn edges_a_constant_condition_never_takes_are_left_out() {
+        assert_eq!(
+            reachable_roots("while true print 1\nprint 2\n"),
+            [true, false]
+        );
+        assert_eq!(
+            reachable_roots("while false print 1\nprint 2\n"),
+            [true, true]
+        );
+        assert_eq!(
+            reachable_roots("var c = true\nwhile c print 1\nprint 2\n"),
+            [true, true, true]
+        );
+    }
+
+    #[test]
+    fn for_loops_may_always_end() {
+        assert_eq!(
+            reachable_roots("for i = 0 to 2147483647 print i\nprint 1\n"),
+            [true, true]
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    binding::bound_tree::{BoundExpression, BoundExpressionId, BoundProgram},
+    common::arithmetic::CheckedOperation,
+};
+
+/// Whether an expression assigns the variable with the given id, or any variable.
+pub fn writes(
+    program: &BoundProgram,
+    expression: BoundExpressionId,
+    variable: Option<usize>,
+) -> bool {
+    match program.expression(expression) {
+        BoundExpression::Literal(_) | BoundExpression::Variable(_) => false,
+        BoundExpression::Unary(unary) => writes(program, unary.right, variable),
+        BoundExpression::Binary(binary) => {
+            writes(program, binary.left, variable) || writes(program, binary.right, variable)
+        }
+        BoundExpression::Assignment(assignment) => {
+            variable.is_none_or(|id| id == assignment.variable.id)
+                || writes(program, assignment.expression, variable)
+        }
+    }
+}
+
+/// Whether an expression reads a variable.
+pub fn reads(program: &BoundProgram, expression: BoundExpressionId) -> bool {
+    match program.expression(expression) {
+        BoundExpression::Literal(_) => false,
+        BoundExpression::Variable(_) => true,
+        BoundExpression::Unary(unary) => reads(program, unary.right),
+        BoundExpression::Binary(binary) => {
+            reads(program, binary.left) || reads(program, binary.right)
+        }
+        BoundExpression::Assignment(assignment) => reads(program, assignment.expression),
+    }
+}
+
+/// Whether an expression does checked arithmetic, which can stop the program.
+pub fn can_fail(program: &BoundProgram, expression: BoundExpressionId) -> bool {
+    CheckedOperation::of(program, expression).is_some()
+        || match program.expression(expression) {
+            BoundExpression::Literal(_) | BoundExpression::Variable(_) => false,
+            BoundExpression::Unary(unary) => can_fail(program, unary.right),
+            BoundExpression::Binary(binary) => {
+                can_fail(program, binary.left) || can_fail(program, binary.right)
+            }
+            BoundExpression::Assignment(assignment) => can_fail(program, assignment.expression),
+        }
+}
+
+/// Whether evaluating an expression can neither assign a variable nor stop the program, so that
+/// skipping it or moving it would go unnoticed.
+pub fn is_inert(program: &BoundProgram, expression: BoundExpressionId) -> bool {
+    !writes(program, expression, None) && !can_fail(program, expression)
+}
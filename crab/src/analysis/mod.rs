@@ -0,0 +1,5 @@
+pub(crate) mod constant;
+pub mod control_flow;
+pub mod effects;
+pub(crate) mod reachability;
+pub(crate) mod warnings;
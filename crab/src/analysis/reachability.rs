@@ -0,0 +1,174 @@
+use crate::{
+    binding::bound_tree::{BoundExpressionId, BoundProgram, BoundStatement, BoundStatementId},
+    common::diagnostic::DiagnosticBag,
+};
+
+use super::{constant::evaluate_condition, control_flow::ControlFlowGraph};
+
+pub(crate) struct ReachabilityAnalyzer<'a, 'b> {
+    diagnostic_bag: &'a mut DiagnosticBag,
+    program: &'b BoundProgram,
+    graph: ControlFlowGraph,
+    reachable: Vec<bool>,
+}
+
+impl<'a, 'b> ReachabilityAnalyzer<'a, 'b> {
+    pub(crate) fn new(diagnostic_bag: &'a mut DiagnosticBag, program: &'b BoundProgram) -> Self {
+        let graph = ControlFlowGraph::build(program);
+        let reachable = graph.reachable_blocks();
+        Self {
+            diagnostic_bag,
+            program,
+            graph,
+            reachable,
+        }
+    }
+
+    pub(crate) fn analyze(mut self) {
+        self.check_statements(self.program.root());
+    }
+
+    fn check_statements(&mut self, statements: &[BoundStatementId]) {
+        for &statement in statements {
+            if !self.is_reachable(statement) {
+                self.report_unreachable(statement);
+                return;
+            }
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_nested(&mut self, statement: BoundStatementId) {
+        if self.is_reachable(statement) {
+            self.check_statement(statement);
+        } else {
+            self.report_unreachable(statement);
+        }
+    }
+
+    fn check_statement(&mut self, statement: BoundStatementId) {
+        match self.program.statement(statement) {
+            BoundStatement::Expression(_) | BoundStatement::Print(_) | BoundStatement::Var(_) => {}
+            BoundStatement::Block(statement) => self.check_statements(&statement.statements),
+            BoundStatement::If(statement) => {
+                self.check_condition(statement.condition);
+                self.check_nested(statement.consequence);
+                if let Some(else_clause) = statement.else_clause {
+                    self.check_nested(else_clause);
+                }
+            }
+            BoundStatement::While(statement) => {
+                self.check_condition(statement.condition);
+                self.check_nested(statement.body);
+            }
+            BoundStatement::For(statement) => self.check_nested(statement.body),
+        }
+    }
+
+    fn check_condition(&mut self, condition: BoundExpressionId) {
+        if let Some(value) = evaluate_condition(self.program, condition) {
+            self.diagnostic_bag
+                .constant_condition(self.program.expression(condition).get_position(), value);
+        }
+    }
+
+    fn is_reachable(&self, statement: BoundStatementId) -> bool {
+        self.graph
+            .block_of(statement)
+            .is_none_or(|block| self.reachable[block])
+    }
+
+    fn report_unreachable(&mut self, statement: BoundStatementId) {
+        let statement = self.program.statement(statement);
+        if let BoundStatement::Block(block) = statement {
+            if block.statements.is_empty() {
+                return;
+            }
+        }
+        self.diagnostic_bag
+            .unreachable_statement(statement.get_position());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        binding::bindings::Bindings,
+        common::{diagnostic::Severity, intern::Interner},
+        compilation::Compilation,
+    };
+
+    /// The warnings about a program, each with the line it points at.
+    fn warnings(source: &str) -> Vec<(usize, String)> {
+        let compilation = Compilation::compile(
+            source,
+            Rc::new(RefCell::new(Bindings::default())),
+            Rc::new(RefCell::new(Interner::default())),
+        );
+        let diagnostic_bag = compilation.diagnostic_bag.borrow();
+        diagnostic_bag
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+            .map(|diagnostic| {
+                (
+                    diagnostic.position.get_line(source),
+                    diagnostic.message.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn warning(line: usize, message: &str) -> (usize, String) {
+        (line, message.to_string())
+    }
+
+    #[test]
+    fn constant_conditions_are_reported() {
+        assert_eq!(
+            warnings("if 1 < 2 print 1\nwhile !true print 2\n"),
+            [
+                warning(1, "Condition is always 'true'"),
+                warning(2, "Condition is always 'false'"),
+                warning(2, "Statement is unreachable"),
+            ]
+        );
+    }
+
+    #[test]
+    fn conditions_on_variables_are_not_constant() {
+        assert!(warnings("var c = true\nif c print 1 else print 2\n").is_empty());
+    }
+
+    #[test]
+    fn only_the_first_unreachable_statement_is_reported() {
+        assert_eq!(
+            warnings("while true print 1\nprint 2\nprint 3\n"),
+            [
+                warning(1, "Condition is always 'true'"),
+                warning(2, "Statement is unreachable"),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_branch_that_never_runs_is_unreachable() {
+        assert_eq!(
+            warnings("if false {\n    print 1\n} else print 2\n"),
+            [
+                warning(1, "Condition is always 'false'"),
+                warning(1, "Statement is unreachable"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_blocks_are_not_reported() {
+        assert_eq!(
+            warnings("if false {\n}\n"),
+            [warning(1, "Condition is always 'false'")]
+        );
+    }
+}
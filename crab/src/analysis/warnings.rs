@@ -0,0 +1,497 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    binding::{
+        bound_tree::{
+            BoundExpression, BoundExpressionId, BoundProgram, BoundStatement, BoundStatementId,
+        },
+        symbol::VariableSymbol,
+    },
+    common::{
+        diagnostic::{DiagnosticBag, Position},
+        intern::{Interner, Symbol},
+    },
+};
+
+type VariableId = usize;
+
+struct Variable {
+    name: Symbol,
+    position: Position,
+    reads: usize,
+    ignored: bool,
+}
+
+impl Variable {
+    fn is_ignored(&self) -> bool {
+        self.ignored
+    }
+}
+
+enum Event {
+    Read(VariableId),
+    Write(VariableId, Option<Position>),
+    Branch(Vec<Event>, Vec<Event>),
+    /// The condition, the body, and the effect of the whole loop.
+    Loop(Vec<Event>, Vec<Event>, Effect),
+}
+
+impl Event {
+    fn looping(condition: Vec<Event>, body: Vec<Event>) -> Self {
+        // The body may not run at all, so only what the condition writes is written on every
+        // path through the loop.
+        let mut effect = Effect::of(&body);
+        effect.killed.clear();
+        effect.precede(&Effect::of(&condition));
+        Event::Loop(condition, body, effect)
+    }
+}
+
+/// What some events do to the variables live after them, in one step: those in `exposed` are
+/// read before being written on some path, those in `killed` are written on every path and those
+/// in `written` are written on some path.
+/// A loop keeps its effect, so the variables live at its head are known without going around
+/// its body until they stop changing, which takes time exponential in how deeply loops nest.
+#[derive(Default)]
+struct Effect {
+    exposed: HashSet<VariableId>,
+    killed: HashSet<VariableId>,
+    written: HashSet<VariableId>,
+}
+
+impl Effect {
+    fn of(events: &[Event]) -> Self {
+        let mut effect = Self::default();
+        for event in events.iter().rev() {
+            match event {
+                Event::Read(id) => {
+                    effect.exposed.insert(*id);
+                }
+                Event::Write(id, _) => {
+                    effect.exposed.remove(id);
+                    effect.killed.insert(*id);
+                    effect.written.insert(*id);
+                }
+                Event::Branch(consequence, alternative) => {
+                    let consequence = Self::of(consequence);
+                    let alternative = Self::of(alternative);
+                    effect.precede(&Self {
+                        exposed: &consequence.exposed | &alternative.exposed,
+                        killed: &consequence.killed & &alternative.killed,
+                        written: &consequence.written | &alternative.written,
+                    });
+                }
+                Event::Loop(_, _, loop_effect) => effect.precede(loop_effect),
+            }
+        }
+        effect
+    }
+
+    /// Makes this the effect of `before` followed by what this was.
+    fn precede(&mut self, before: &Effect) {
+        self.exposed.retain(|id| !before.killed.contains(id));
+        self.exposed.extend(&before.exposed);
+        self.killed.extend(&before.killed);
+        self.written.extend(&before.written);
+    }
+
+    fn live_before(&self, after: &Liveness) -> Liveness {
+        let mut live: HashSet<VariableId> = after.live.difference(&self.killed).copied().collect();
+        live.extend(&self.exposed);
+        Liveness {
+            live,
+            written: &after.written | &self.written,
+        }
+    }
+}
+
+/// The variables whose values are read later on some path, and those that are written later on
+/// some path. A value nothing reads is overwritten if its variable is written again, and is
+/// otherwise never read at all.
+#[derive(Clone, Default)]
+struct Liveness {
+    live: HashSet<VariableId>,
+    written: HashSet<VariableId>,
+}
+
+impl Liveness {
+    fn join(&mut self, other: Liveness) {
+        self.live.extend(other.live);
+        self.written.extend(other.written);
+    }
+}
+
+pub(crate) struct WarningAnalyzer<'a> {
+    diagnostic_bag: &'a mut DiagnosticBag,
+    program: &'a BoundProgram,
+    interner: &'a Interner,
+    variables: Vec<Variable>,
+    ids: HashMap<usize, VariableId>,
+    scopes: Vec<HashSet<Symbol>>,
+    export_globals: bool,
+    /// Each dead store, and whether a later write overwrites it.
+    unused_assignments: Vec<(Position, VariableId, bool)>,
+}
+
+impl<'a> WarningAnalyzer<'a> {
+    pub(crate) fn new(
+        diagnostic_bag: &'a mut DiagnosticBag,
+        program: &'a BoundProgram,
+        interner: &'a Interner,
+        export_globals: bool,
+    ) -> Self {
+        Self {
+            diagnostic_bag,
+            program,
+            interner,
+            variables: Vec::new(),
+            ids: HashMap::new(),
+            scopes: vec![HashSet::new()],
+            export_globals,
+            unused_assignments: Vec::new(),
+        }
+    }
+
+    pub(crate) fn analyze(mut self) {
+        let events = self.resolve_statements(self.program.root());
+
+        for variable in &self.variables {
+            if variable.reads == 0 && !variable.is_ignored() {
+                self.diagnostic_bag.unused_variable(
+                    variable.position.clone(),
+                    self.interner.resolve(variable.name),
+                );
+            }
+        }
+
+        self.live_before(&events, Liveness::default());
+        self.unused_assignments
+            .sort_by_key(|(position, _, _)| position.start);
+        for (position, id, overwritten) in &self.unused_assignments {
+            let name = self.interner.resolve(self.variables[*id].name);
+            if *overwritten {
+                self.diagnostic_bag
+                    .overwritten_assignment(position.clone(), name);
+            } else {
+                self.diagnostic_bag
+                    .unused_assignment(position.clone(), name);
+            }
+        }
+    }
+
+    fn resolve_statements(&mut self, statements: &[BoundStatementId]) -> Vec<Event> {
+        let mut events = vec![];
+        for &statement in statements {
+            self.resolve_statement(statement, &mut events);
+        }
+        events
+    }
+
+    fn resolve_statement(&mut self, statement: BoundStatementId, events: &mut Vec<Event>) {
+        match self.program.statement(statement) {
+            BoundStatement::Expression(statement) => {
+                self.resolve_expression(statement.expression, events)
+            }
+            BoundStatement::Print(statement) => {
+                self.resolve_expression(statement.expression, events)
+            }
+            BoundStatement::Var(statement) => {
+                self.resolve_expression(statement.expression, events);
+                let id = self.declare(&statement.variable, statement.get_position(), true);
+                events.push(Event::Write(id, Some(statement.get_position())));
+            }
+            BoundStatement::Block(statement) => {
+                self.scopes.push(HashSet::new());
+                for &statement in &statement.statements {
+                    self.resolve_statement(statement, events);
+                }
+                self.scopes.pop();
+            }
+            BoundStatement::If(statement) => {
+                self.resolve_expression(statement.condition, events);
+                let mut consequence = vec![];
+                self.resolve_nested(statement.consequence, &mut consequence);
+                let mut alternative = vec![];
+                if let Some(else_clause) = statement.else_clause {
+                    self.resolve_nested(else_clause, &mut alternative);
+                }
+                events.push(Event::Branch(consequence, alternative));
+            }
+            BoundStatement::While(statement) => {
+                let mut condition = vec![];
+                self.resolve_expression(statement.condition, &mut condition);
+                let mut body = vec![];
+                self.resolve_nested(statement.body, &mut body);
+                events.push(Event::looping(condition, body));
+            }
+            BoundStatement::For(statement) => {
+                self.resolve_expression(statement.lower_bound, events);
+                self.resolve_expression(statement.upper_bound, events);
+                self.scopes.push(HashSet::new());
+                let id = self.declare(&statement.variable, Position::default(), false);
+                self.variables[id].reads += 1;
+                events.push(Event::Write(id, None));
+                let mut body = vec![];
+                self.resolve_nested(statement.body, &mut body);
+                body.push(Event::Read(id));
+                body.push(Event::Write(id, None));
+                events.push(Event::looping(vec![Event::Read(id)], body));
+                self.scopes.pop();
+            }
+        }
+    }
+
+    fn resolve_nested(&mut self, statement: BoundStatementId, events: &mut Vec<Event>) {
+        if let BoundStatement::Block(_) = self.program.statement(statement) {
+            return self.resolve_statement(statement, events);
+        }
+        self.scopes.push(HashSet::new());
+        self.resolve_statement(statement, events);
+        self.scopes.pop();
+    }
+
+    fn resolve_expression(&mut self, expression: BoundExpressionId, events: &mut Vec<Event>) {
+        match self.program.expression(expression) {
+            BoundExpression::Literal(_) => {}
+            BoundExpression::Variable(expression) => {
+                if let Some(id) = self.lookup(&expression.variable) {
+                    self.variables[id].reads += 1;
+                    events.push(Event::Read(id));
+                }
+            }
+            BoundExpression::Unary(expression) => self.resolve_expression(expression.right, events),
+            BoundExpression::Binary(expression) => {
+                self.resolve_expression(expression.left, events);
+                self.resolve_expression(expression.right, events);
+            }
+            BoundExpression::Assignment(expression) => {
+                self.resolve_expression(expression.expression, events);
+                if let Some(id) = self.lookup(&expression.variable) {
+                    events.push(Event::Write(id, Some(expression.get_position())));
+                }
+            }
+        }
+    }
+
+    fn declare(
+        &mut self,
+        symbol: &VariableSymbol,
+        position: Position,
+        warn_shadowing: bool,
+    ) -> VariableId {
+        let shadows = self.scopes[..self.scopes.len() - 1]
+            .iter()
+            .any(|scope| scope.contains(&symbol.name));
+        let name = self.interner.resolve(symbol.name);
+        if shadows && warn_shadowing {
+            self.diagnostic_bag
+                .shadowed_variable(position.clone(), name);
+        }
+        let id = self.variables.len();
+        let exported = self.export_globals && symbol.depth == 0;
+        self.variables.push(Variable {
+            name: symbol.name,
+            position,
+            reads: 0,
+            ignored: exported || name.starts_with('_'),
+        });
+        self.ids.insert(symbol.id, id);
+        self.scopes.last_mut().unwrap().insert(symbol.name);
+        id
+    }
+
+    /// Symbols declared by an earlier interactive chunk are not tracked.
+    fn lookup(&self, symbol: &VariableSymbol) -> Option<VariableId> {
+        self.ids.get(&symbol.id).copied()
+    }
+
+    fn live_before(&mut self, events: &[Event], mut after: Liveness) -> Liveness {
+        for event in events.iter().rev() {
+            match event {
+                Event::Read(id) => {
+                    after.live.insert(*id);
+                }
+                Event::Write(id, position) => {
+                    if let Some(position) = position {
+                        let variable = &self.variables[*id];
+                        if !after.live.contains(id) && variable.reads > 0 && !variable.is_ignored()
+                        {
+                            let overwritten = after.written.contains(id);
+                            self.unused_assignments
+                                .push((position.clone(), *id, overwritten));
+                        }
+                    }
+                    after.live.remove(id);
+                    after.written.insert(*id);
+                }
+                Event::Branch(consequence, alternative) => {
+                    let mut consequence = self.live_before(consequence, after.clone());
+                    consequence.join(self.live_before(alternative, after));
+                    after = consequence;
+                }
+                Event::Loop(condition, body, effect) => {
+                    let head = effect.live_before(&after);
+                    let mut after_condition = self.live_before(body, head);
+                    after_condition.join(after);
+                    after = self.live_before(condition, after_condition);
+                }
+            }
+        }
+        after
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        binding::bindings::Bindings,
+        common::{diagnostic::Severity, intern::Interner},
+        compilation::Compilation,
+    };
+
+    /// The warnings about a program, each with the line it points at.
+    fn warnings(source: &str, interactive: bool) -> Vec<(usize, String)> {
+        let bindings = Rc::new(RefCell::new(Bindings::default()));
+        let interner = Rc::new(RefCell::new(Interner::default()));
+        let compilation = if interactive {
+            Compilation::compile_interactive(source, bindings, interner)
+        } else {
+            Compilation::compile(source, bindings, interner)
+        };
+        let diagnostic_bag = compilation.diagnostic_bag.borrow();
+        diagnostic_bag
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+            .map(|diagnostic| {
+                (
+                    diagnostic.position.get_line(source),
+                    diagnostic.message.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn warning(line: usize, message: &str) -> (usize, String) {
+        (line, message.to_string())
+    }
+
+    #[test]
+    fn variables_that_are_never_read_are_reported() {
+        assert_eq!(
+            warnings("var a = 1\nvar b = 2\nprint b\n", false),
+            [warning(1, "Variable 'a' is never read")]
+        );
+    }
+
+    #[test]
+    fn names_starting_with_an_underscore_are_not_reported() {
+        assert!(warnings("var _a = 1\n_a = 2\n", false).is_empty());
+    }
+
+    #[test]
+    fn globals_of_an_interactive_chunk_are_not_reported() {
+        assert!(warnings("var a = 1\n", true).is_empty());
+        assert_eq!(
+            warnings("{\n    var a = 1\n}\n", true),
+            [warning(2, "Variable 'a' is never read")]
+        );
+    }
+
+    #[test]
+    fn overwritten_assignments_are_reported() {
+        assert_eq!(
+            warnings("var a = 1\na = 2\nprint a\n", false),
+            [warning(
+                1,
+                "Value assigned to 'a' is overwritten before it is read"
+            )]
+        );
+    }
+
+    #[test]
+    fn final_assignments_are_reported_as_never_read() {
+        assert_eq!(
+            warnings("var a = 1\nprint a + (a = 5)\n", false),
+            [warning(2, "Value assigned to 'a' is never read")]
+        );
+        let source = "var a = 1\nvar c = true\nprint a\nif c a = 2 else a = 3\n";
+        assert_eq!(
+            warnings(source, false),
+            [
+                warning(4, "Value assigned to 'a' is never read"),
+                warning(4, "Value assigned to 'a' is never read")
+            ]
+        );
+        let source = "var a = 0\nprint a\nvar c = true\nwhile c {\n    a = 2\n    c = false\n}\n";
+        assert_eq!(
+            warnings(source, false),
+            [warning(
+                5,
+                "Value assigned to 'a' is overwritten before it is read"
+            )]
+        );
+    }
+
+    #[test]
+    fn assignments_read_on_one_branch_are_not_reported() {
+        let source = "var a = 1\nvar c = true\nif c a = 2\nprint a\n";
+        assert!(warnings(source, false).is_empty());
+    }
+
+    #[test]
+    fn loops_are_followed_into_the_next_iteration() {
+        let source = "var a = 0\nwhile a < 3 a = a + 1\n";
+        assert!(warnings(source, false).is_empty());
+        let source =
+            "var a = 0\nvar c = true\nwhile c {\n    a = 1\n    a = 2\n    c = false\n}\nprint a\n";
+        assert_eq!(
+            warnings(source, false),
+            [warning(
+                4,
+                "Value assigned to 'a' is overwritten before it is read"
+            )]
+        );
+    }
+
+    #[test]
+    fn values_live_into_an_enclosing_loop_are_not_reported() {
+        let source = "var a = 0\nvar b = 0\nwhile a < 3 {\n    while b < 1 b = b + 1\n    b = 0\n    a = a + 1\n}\n";
+        assert!(warnings(source, false).is_empty());
+    }
+
+    #[test]
+    fn deeply_nested_loops_are_analyzed_once() {
+        // Going around each loop until nothing changes would take 3^40 steps here.
+        let source = format!(
+            "var a = 0\n{}{{\n    a = 1\n    a = 2\n}}\n",
+            "while a < 1 ".repeat(40)
+        );
+        assert_eq!(
+            warnings(&source, false),
+            [warning(
+                3,
+                "Value assigned to 'a' is overwritten before it is read"
+            )]
+        );
+    }
+
+    #[test]
+    fn variables_that_shadow_an_outer_scope_are_reported() {
+        let source = "var a = 1\n{\n    var a = a + 1\n    print a\n}\n";
+        assert_eq!(
+            warnings(source, false),
+            [warning(
+                3,
+                "Variable 'a' shadows a variable from an outer scope"
+            )]
+        );
+    }
+
+    #[test]
+    fn loop_variables_are_never_reported() {
+        assert!(warnings("var i = 0\nprint i\nfor i = 1 to 2 print 0\n", false).is_empty());
+    }
+}
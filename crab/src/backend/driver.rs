@@ -0,0 +1,342 @@
+use std::{
+    cell::RefCell,
+    env::{args, consts::EXE_EXTENSION, temp_dir},
+    ffi::OsString,
+    fs::{read_to_string, remove_file, write},
+    io::{stdout, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{exit, id, Command},
+    rc::Rc,
+    thread::Builder,
+};
+
+use crate::{
+    binding::bindings::Bindings,
+    common::{intern::Interner, report::report_diagnostics},
+    compilation::{Compilation, CompileOptions},
+};
+
+use super::{file_name, BackendError, Input};
+
+/// A backend that translates programs to another language, whose compiler builds the code into
+/// an executable. `drive` gives one a command line.
+pub trait Translator {
+    /// The extension of the files the code goes in, like `go`.
+    fn extension(&self) -> &'static str;
+
+    /// The options the translator takes on the command line, as the usage line shows them.
+    fn usage(&self) -> &'static str {
+        ""
+    }
+
+    /// Takes an option the driver does not know, calling `value` for its value if it needs one.
+    /// Returns whether the option was the translator's.
+    fn parse_option(
+        &mut self,
+        _option: &str,
+        _value: &mut dyn FnMut() -> Option<String>,
+    ) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    /// Rejects options that do not go with the mode, or with writing to stdout.
+    fn check_options(&self, _mode: Mode, _stdout: bool) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Translates a program whose code will be written to `output`.
+    fn translate(&self, input: &Input, output: &Path) -> Translation;
+
+    /// The compiler that builds the code.
+    fn toolchain(&self) -> Toolchain;
+}
+
+/// Code translated from a program.
+pub struct Translation {
+    pub code: String,
+    /// Files that go beside the code, by what is appended to its path, like `.map` for a source
+    /// map, and their contents.
+    pub companions: Vec<(&'static str, String)>,
+}
+
+/// How to call the compiler of a target language, which is given `-o`, the executable and the
+/// code after the arguments.
+pub struct Toolchain {
+    pub program: OsString,
+    pub arguments: Vec<&'static str>,
+    /// What messages call the compiler, like `The Go toolchain`.
+    pub description: &'static str,
+}
+
+/// What to do with the inputs. Without a subcommand, they are transpiled.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    Transpile,
+    /// Only reports diagnostics.
+    Check,
+    /// Transpiles, then compiles the code to an executable.
+    Build,
+    /// Transpiles, compiles and runs the program.
+    Run,
+}
+
+/// What became of an input file.
+enum Outcome {
+    /// It has errors.
+    Rejected,
+    /// It was only checked, or written to stdout.
+    Accepted,
+    /// Its code was written to this file.
+    Written(PathBuf),
+}
+
+struct Arguments {
+    usage: String,
+    mode: Mode,
+    optimize: bool,
+    options: CompileOptions,
+    stdout: bool,
+    output: Option<String>,
+    paths: Vec<String>,
+}
+
+/// Runs the command line of a translator called `command`, which exits like crabi does:
+/// with 65 for diagnostics, 66 for unreadable input, 69 when the compiler is missing, 70 for a
+/// runtime error or code the compiler rejects and 73 when the code cannot be written.
+pub fn drive<T: Translator + Send + 'static>(command: &'static str, mut translator: T) {
+    let arguments = parse_arguments(command, &mut translator);
+    // Every pass recurses once per level of nesting, so the stack grows with the limit.
+    let stack_size = arguments.options.stack_size();
+    let work = move || {
+        let mut failed = false;
+        for path in &arguments.paths {
+            match (transpile(path, &arguments, &translator), arguments.mode) {
+                (Outcome::Rejected, _) => failed = true,
+                (Outcome::Written(code), Mode::Build) => {
+                    build(&translator, &code, &executable_path(path, &arguments))
+                }
+                (Outcome::Written(code), Mode::Run) => run(&translator, command, &code),
+                _ => {}
+            }
+        }
+        if failed {
+            exit(65);
+        }
+    };
+    let worker = Builder::new().stack_size(stack_size).spawn(work).unwrap();
+    if worker.join().is_err() {
+        exit(101);
+    }
+}
+
+fn parse_arguments(command: &'static str, translator: &mut impl Translator) -> Arguments {
+    let options = match translator.usage() {
+        "" => String::new(),
+        usage => format!(" {usage}"),
+    };
+    let mut arguments = Arguments {
+        usage: format!(
+            "Usage: {command} [check | build | run] [-O] [-o PATH] [--stdout] [--max-depth LEVELS]{options} FILE..."
+        ),
+        mode: Mode::Transpile,
+        optimize: false,
+        options: CompileOptions::default(),
+        stdout: false,
+        output: None,
+        paths: vec![],
+    };
+    let mut args = args().skip(1).peekable();
+    if let Some(mode) = args.peek().and_then(|arg| match arg.as_str() {
+        "check" => Some(Mode::Check),
+        "build" => Some(Mode::Build),
+        "run" => Some(Mode::Run),
+        _ => None,
+    }) {
+        arguments.mode = mode;
+        args.next();
+    }
+    while let Some(arg) = args.next() {
+        if arg == "-O" {
+            arguments.optimize = true;
+        } else if arg == "--stdout" {
+            arguments.stdout = true;
+        } else if arg == "--max-depth" {
+            arguments.options.max_depth = match args.next().map(|value| value.parse()) {
+                Some(Ok(limit)) if limit > 0 => limit,
+                Some(_) => arguments.invalid("'--max-depth' takes a positive number."),
+                None => arguments.invalid("'--max-depth' needs a value."),
+            };
+        } else if arg == "-o" {
+            let Some(value) = args.next() else {
+                arguments.invalid("'-o' needs a value.");
+            };
+            arguments.output = Some(value);
+        } else if arg.starts_with('-') {
+            let mut value = || args.next();
+            match translator.parse_option(&arg, &mut value) {
+                Ok(true) => {}
+                Ok(false) => arguments.invalid(&format!("Unknown option '{arg}'.")),
+                Err(message) => arguments.invalid(&message),
+            }
+        } else {
+            arguments.paths.push(arg);
+        }
+    }
+
+    if arguments.paths.is_empty() {
+        arguments.invalid("No input files.");
+    }
+    if arguments.paths.len() > 1 && (arguments.output.is_some() || arguments.stdout) {
+        arguments.invalid("'-o' and '--stdout' take a single input file.");
+    }
+    if arguments.stdout && (arguments.output.is_some() || arguments.mode != Mode::Transpile) {
+        arguments.invalid("'--stdout' writes no files, so it takes no '-o' or subcommand.");
+    }
+    if arguments.mode == Mode::Run && arguments.output.is_some() {
+        arguments.invalid("'run' keeps no executable, so it takes no '-o'.");
+    }
+    if let Err(message) = translator.check_options(arguments.mode, arguments.stdout) {
+        arguments.invalid(&message);
+    }
+    arguments
+}
+
+impl Arguments {
+    fn invalid(&self, message: &str) -> ! {
+        eprintln!("{message}");
+        eprintln!("{}", self.usage);
+        exit(65);
+    }
+}
+
+/// Compiles a file and, unless only checking, writes the code. Files that cannot be read or
+/// written end the program.
+fn transpile(path: &str, arguments: &Arguments, translator: &impl Translator) -> Outcome {
+    let source = read_to_string(path).unwrap_or_else(|error| {
+        eprintln!("Could not read '{path}': {error}.");
+        exit(66);
+    });
+    let mut compilation = Compilation::compile_with_options(
+        &source,
+        Rc::new(RefCell::new(Bindings::default())),
+        Rc::new(RefCell::new(Interner::default())),
+        arguments.options,
+    );
+    if arguments.optimize {
+        compilation.optimize();
+    }
+    // With several inputs, say which one the diagnostics are about.
+    let name = (arguments.paths.len() > 1).then_some(path);
+    report_diagnostics(
+        name,
+        &source,
+        &compilation.diagnostic_bag.borrow().diagnostics,
+    );
+    if compilation.diagnostic_bag.borrow().has_errors() {
+        return Outcome::Rejected;
+    }
+    if arguments.mode == Mode::Check {
+        return Outcome::Accepted;
+    }
+
+    let output_path = match (&arguments.output, arguments.mode) {
+        (Some(output), Mode::Transpile) => PathBuf::from(output),
+        _ => Path::new(path).with_extension(translator.extension()),
+    };
+    let input = Input {
+        path: Path::new(path),
+        source: &source,
+        compilation: &compilation,
+        optimized: arguments.optimize,
+    };
+    let translation = translator.translate(&input, &output_path);
+    if arguments.stdout {
+        stdout().write_all(translation.code.as_bytes()).unwrap();
+        return Outcome::Accepted;
+    }
+    if let Err(error) = write_translation(&output_path, &translation) {
+        exit(error.exit_code);
+    }
+    Outcome::Written(output_path)
+}
+
+/// Translates a program and writes the code beside its source, as a backend does.
+pub fn write_beside(translator: &impl Translator, input: &Input) -> Result<(), BackendError> {
+    let output_path = input.path.with_extension(translator.extension());
+    write_translation(&output_path, &translator.translate(input, &output_path))
+}
+
+fn write_translation(path: &Path, translation: &Translation) -> Result<(), BackendError> {
+    write_file(path, &translation.code)?;
+    for (suffix, contents) in &translation.companions {
+        let mut companion = path.as_os_str().to_owned();
+        companion.push(suffix);
+        write_file(Path::new(&companion), contents)?;
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), BackendError> {
+    write(path, contents).map_err(|error| {
+        eprintln!("Could not write '{}': {error}.", path.display());
+        BackendError { exit_code: 73 }
+    })
+}
+
+/// Where `build` puts the executable: the `-o` path, or beside the source without extension.
+fn executable_path(path: &str, arguments: &Arguments) -> PathBuf {
+    match &arguments.output {
+        Some(output) => PathBuf::from(output),
+        None => Path::new(path).with_extension(EXE_EXTENSION),
+    }
+}
+
+fn build(translator: &impl Translator, code: &Path, executable: &Path) {
+    let toolchain = translator.toolchain();
+    let status = Command::new(&toolchain.program)
+        .args(&toolchain.arguments)
+        .arg("-o")
+        .arg(executable)
+        .arg(code)
+        .status()
+        .unwrap_or_else(|error| {
+            let program = toolchain.program.to_string_lossy();
+            if error.kind() == ErrorKind::NotFound {
+                eprintln!(
+                    "{} is not installed; '{program}' was not found.",
+                    toolchain.description
+                );
+            } else {
+                eprintln!("Could not run '{program}': {error}.");
+            }
+            exit(69);
+        });
+    // The code is generated, so when the compiler rejects it the fault is the translator's.
+    if !status.success() {
+        eprintln!(
+            "{} could not compile '{}'.",
+            toolchain.description,
+            code.display()
+        );
+        exit(70);
+    }
+}
+
+/// Builds the program into a temporary executable and runs it, exiting like it does when it
+/// fails, so that a runtime error exits with 70 as it does in crabi.
+fn run(translator: &impl Translator, command: &str, code: &Path) {
+    let executable = temp_dir()
+        .join(format!("{command}-{}-{}", id(), file_name(code)))
+        .with_extension(EXE_EXTENSION);
+    build(translator, code, &executable);
+    let status = Command::new(&executable).status();
+    let _ = remove_file(&executable);
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => exit(status.code().unwrap_or(70)),
+        Err(error) => {
+            eprintln!("Could not run '{}': {error}.", executable.display());
+            exit(70);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{
+    binding::bound_tree::{BoundIfStatement, BoundProgram, BoundStatement, BoundStatementId},
+    syntax::token::Comment,
+};
+
+use super::writer::Writer;
+
+/// Carries the layout of the source over to generated code: comments go in front of the
+/// statement that follows them, or at the end of the line of a one-line statement they trail,
+/// and statements the source separates by an empty line stay separated.
+pub struct Layout<'a> {
+    source: &'a str,
+    comments: &'a [Comment<'a>],
+    next_comment: usize,
+    /// Turns a comment of the source into one of the target.
+    comment_text: fn(&'a str) -> &'a str,
+}
+
+impl<'a> Layout<'a> {
+    pub fn new(
+        source: &'a str,
+        comments: &'a [Comment<'a>],
+        comment_text: fn(&'a str) -> &'a str,
+    ) -> Self {
+        Self {
+            source,
+            comments,
+            next_comment: 0,
+            comment_text,
+        }
+    }
+
+    /// Writes a statement that fits on one line, followed by the comment that trails it in the
+    /// source, if any.
+    pub fn simple_statement(&mut self, writer: &mut Writer, mut line: String, start: usize) {
+        if let Some(comment) = self.comments.get(self.next_comment) {
+            let end_of_line = self.source[start..]
+                .find('\n')
+                .map_or(self.source.len(), |offset| start + offset);
+            if comment.position.start < end_of_line {
+                line.push(' ');
+                line.push_str((self.comment_text)(comment.text));
+                self.next_comment += 1;
+            }
+        }
+        writer.line(&line);
+    }
+
+    /// Writes the comments that start before `offset` and have not been written yet.
+    pub fn comments(&mut self, writer: &mut Writer, offset: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if offset <= comment.position.start {
+                break;
+            }
+            self.separate(writer, comment.position.start);
+            writer.line((self.comment_text)(comment.text));
+            self.next_comment += 1;
+        }
+    }
+
+    /// Writes the comments that have not been written yet.
+    pub fn remaining_comments(&mut self, writer: &mut Writer) {
+        self.comments(writer, self.source.len());
+    }
+
+    /// Keeps the empty line the source has in front of the line that `offset` is on.
+    pub fn separate(&self, writer: &mut Writer, offset: usize) {
+        let Some(line_end) = self.source[..offset].rfind('\n') else {
+            return;
+        };
+        let line_start = self.source[..line_end]
+            .rfind('\n')
+            .map_or(0, |start| start + 1);
+        if self.source[line_start..line_end].trim().is_empty() {
+            writer.blank_line();
+        }
+    }
+}
+
+/// An `if` and the `else if` chain after it, which targets write as one statement.
+pub struct IfChain<'a> {
+    /// The first `if`, then the `if` of every `else if`.
+    pub branches: Vec<&'a BoundIfStatement>,
+    /// The body of the final `else`, if there is one.
+    pub alternative: Option<BoundStatementId>,
+}
+
+impl<'a> IfChain<'a> {
+    pub fn of(program: &'a BoundProgram, statement: &'a BoundIfStatement) -> Self {
+        let mut branches = vec![statement];
+        let mut alternative = statement.else_clause;
+        while let Some(BoundStatement::If(next)) = alternative.map(|id| program.statement(id)) {
+            branches.push(next);
+            alternative = next.else_clause;
+        }
+        Self {
+            branches,
+            alternative,
+        }
+    }
+}
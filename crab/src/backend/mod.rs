@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use crate::compilation::Compilation;
+
+pub mod driver;
+pub mod layout;
+pub mod names;
+pub mod usage;
+pub mod writer;
+
+/// A program that compiled without errors, on its way to a backend.
+pub struct Input<'a> {
+    /// The file the source was read from.
+    pub path: &'a Path,
+    pub source: &'a str,
+    pub compilation: &'a Compilation<'a>,
+    /// Whether the program went through the optimizer.
+    pub optimized: bool,
+}
+
+impl Input<'_> {
+    /// The name of the source file without its directory. Generated code is usually written
+    /// beside its source, so it refers to the source by its name alone.
+    pub fn source_name(&self) -> String {
+        file_name(self.path)
+    }
+}
+
+/// The last component of a path, or the whole path if it has none.
+pub fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.to_string_lossy().into_owned(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// Why a backend did not finish. The backend has already reported what went wrong, so all the
+/// front end needs is the code to exit with.
+#[derive(Debug)]
+pub struct BackendError {
+    pub exit_code: i32,
+}
+
+/// Something a front end can hand a compiled program to: a code generator that writes the
+/// program in another language, or an interpreter that runs it.
+pub trait Backend {
+    /// The name that selects the backend, like `go`.
+    fn name(&self) -> &'static str;
+
+    fn run(&self, input: &Input) -> Result<(), BackendError>;
+}
+
+/// The backends a front end offers, in the order they were registered.
+#[derive(Default)]
+pub struct Registry {
+    backends: Vec<Box<dyn Backend>>,
+}
+
+impl Registry {
+    /// Adds a backend. One registered later under the same name takes its place.
+    pub fn register(&mut self, backend: Box<dyn Backend>) {
+        self.backends
+            .retain(|registered| registered.name() != backend.name());
+        self.backends.push(backend);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Backend> {
+        self.backends
+            .iter()
+            .find(|backend| backend.name() == name)
+            .map(|backend| backend.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.backends.iter().map(|backend| backend.name()).collect()
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{binding::symbol::VariableSymbol, common::intern::Interner};
+
+/// How the target scopes names, which decides how many identifiers a name of the program needs.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scoping {
+    /// The target shadows names the way Crab does, so a name always maps to the same
+    /// identifier, whatever declares it, and the generated code can reuse its own names.
+    Shadowing,
+    /// Every variable gets an identifier of its own, and so does everything the generated code
+    /// names itself.
+    Distinct,
+}
+
+/// Turns the names of a program into identifiers of the target. Names the target would reject
+/// or misread get an underscore appended, and a number after that if the program already uses
+/// the result, so `type` becomes `type_` and, next to a variable called `type_`, `type_1`.
+///
+/// With distinct scoping, the first variable to use a name keeps it, and later ones are
+/// renamed like reserved names are.
+pub struct Names<'a> {
+    interner: &'a Interner,
+    keywords: &'static [&'static str],
+    reserved: fn(&str) -> bool,
+    scoping: Scoping,
+    variables: RefCell<HashMap<usize, String>>,
+    taken: RefCell<HashSet<String>>,
+}
+
+impl<'a> Names<'a> {
+    /// `keywords` can never name anything in the target, and `reserved` tells the other names
+    /// it would misread, like those of its standard library or the runtime.
+    pub fn new(
+        interner: &'a Interner,
+        keywords: &'static [&'static str],
+        reserved: fn(&str) -> bool,
+        scoping: Scoping,
+    ) -> Self {
+        Self {
+            interner,
+            keywords,
+            reserved,
+            scoping,
+            variables: RefCell::new(HashMap::new()),
+            taken: RefCell::new(HashSet::new()),
+        }
+    }
+
+    pub fn variable(&self, variable: &VariableSymbol) -> String {
+        if let Some(identifier) = self.variables.borrow().get(&variable.id) {
+            return identifier.clone();
+        }
+        let name = self.interner.resolve(variable.name);
+        let reserved = self.keywords.contains(&name) || (self.reserved)(name);
+        let identifier = if reserved || self.taken.borrow().contains(name) {
+            self.fresh(&format!("{name}_"))
+        } else {
+            if self.scoping == Scoping::Distinct {
+                self.taken.borrow_mut().insert(name.to_string());
+            }
+            name.to_string()
+        };
+        self.variables
+            .borrow_mut()
+            .insert(variable.id, identifier.clone());
+        identifier
+    }
+
+    /// A name for something the generated code needs that no identifier of the program uses.
+    pub fn fresh(&self, base: &str) -> String {
+        let mut taken = self.taken.borrow_mut();
+        let mut name = base.to_string();
+        let mut suffix = 1;
+        while self.interner.get(&name).is_some() || taken.contains(&name) {
+            name = format!("{base}{suffix}");
+            suffix += 1;
+        }
+        if self.scoping == Scoping::Distinct {
+            taken.insert(name.clone());
+        }
+        name
+    }
+}
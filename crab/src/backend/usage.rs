@@ -0,0 +1,61 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{
+    binding::{
+        bound_tree::{
+            BoundExpressionId, BoundForStatement, BoundPrintStatement, BoundProgram,
+            BoundVariableExpression,
+        },
+        visitor::{walk_expression, walk_for_statement, walk_program, BoundTreeVisitor},
+    },
+    common::arithmetic::CheckedOperation,
+};
+
+/// What a program uses that a code generator needs to know about up front: the variables it
+/// reads, which compilers warn about or reject when they are only ever written, and the
+/// runtime it calls.
+#[derive(Default)]
+pub struct Usage {
+    /// The ids of the variables that are read somewhere.
+    pub reads: HashSet<usize>,
+    /// Whether the program prints anything.
+    pub prints: bool,
+    /// The checked operations the program does. Every `for` loop adds, to increment its
+    /// variable.
+    pub operations: BTreeSet<CheckedOperation>,
+}
+
+impl Usage {
+    pub fn of(program: &BoundProgram) -> Self {
+        let mut usage = Self::default();
+        walk_program(&mut usage, program);
+        usage
+    }
+}
+
+impl BoundTreeVisitor for Usage {
+    fn visit_print_statement(&mut self, program: &BoundProgram, statement: &BoundPrintStatement) {
+        self.prints = true;
+        self.visit_expression(program, statement.expression);
+    }
+
+    fn visit_for_statement(&mut self, program: &BoundProgram, statement: &BoundForStatement) {
+        self.operations.insert(CheckedOperation::Addition);
+        walk_for_statement(self, program, statement);
+    }
+
+    fn visit_expression(&mut self, program: &BoundProgram, expression: BoundExpressionId) {
+        if let Some(operation) = CheckedOperation::of(program, expression) {
+            self.operations.insert(operation);
+        }
+        walk_expression(self, program, expression);
+    }
+
+    fn visit_variable_expression(
+        &mut self,
+        _program: &BoundProgram,
+        expression: &BoundVariableExpression,
+    ) {
+        self.reads.insert(expression.variable.id);
+    }
+}
@@ -0,0 +1,130 @@
+/// A line of the generated code and the place in the source it was compiled from. Lines and
+/// columns count from 1, and columns count bytes.
+pub struct Mapping {
+    pub generated_line: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Writes a directive that makes the target's compiler report a line of the source, from its
+/// line and column.
+pub type Directive = Box<dyn Fn(usize, usize) -> String>;
+
+/// Builds source code line by line, indenting every block by `indent`. Blank lines are
+/// requested rather than written, so they never pile up, open a block or end one.
+///
+/// A line can be marked with the place in the source it comes from. Marked lines are recorded
+/// as mappings and, when the writer has a directive for them, preceded by one, so that the
+/// target's compiler reports positions in the source instead.
+pub struct Writer {
+    output: String,
+    lines: usize,
+    indent: &'static str,
+    indentation: usize,
+    blank_line: bool,
+    block_start: bool,
+    directive: Option<Directive>,
+    mark: Option<(usize, usize)>,
+    mappings: Vec<Mapping>,
+}
+
+impl Writer {
+    pub fn new(indent: &'static str) -> Self {
+        Self {
+            output: String::new(),
+            lines: 0,
+            indent,
+            indentation: 0,
+            blank_line: false,
+            block_start: false,
+            directive: None,
+            mark: None,
+            mappings: vec![],
+        }
+    }
+
+    /// A writer for code that goes `indentation` levels deep into code written elsewhere.
+    pub fn indented(self, indentation: usize) -> Self {
+        Self {
+            indentation,
+            block_start: true,
+            ..self
+        }
+    }
+
+    /// A writer that precedes every marked line with a directive.
+    pub fn with_directives(self, directive: Directive) -> Self {
+        Self {
+            directive: Some(directive),
+            ..self
+        }
+    }
+
+    pub fn line(&mut self, text: &str) {
+        if self.blank_line && !self.block_start && !self.output.is_empty() {
+            self.output.push('\n');
+            self.lines += 1;
+        }
+        self.blank_line = false;
+        self.block_start = false;
+        if let Some((line, column)) = self.mark.take() {
+            // A directive must start its line, so it is never indented. Its column is that of
+            // the start of the line, so the code after the indentation lands on the column of
+            // the source.
+            if let Some(directive) = &self.directive {
+                let indentation = self.indentation * self.indent.len();
+                let column = column.saturating_sub(indentation).max(1);
+                self.output.push_str(&directive(line, column));
+                self.output.push('\n');
+                self.lines += 1;
+            }
+            self.mappings.push(Mapping {
+                generated_line: self.lines + 1,
+                line,
+                column,
+            });
+        }
+        for _ in 0..self.indentation {
+            self.output.push_str(self.indent);
+        }
+        self.output.push_str(text);
+        self.output.push('\n');
+        self.lines += 1;
+    }
+
+    /// Writes a line that opens a block, like `for n > 1 {`, and indents what follows.
+    pub fn open(&mut self, text: &str) {
+        self.line(text);
+        self.indentation += 1;
+        self.block_start = true;
+    }
+
+    /// Ends a block with a line like `}`.
+    pub fn close(&mut self, text: &str) {
+        self.blank_line = false;
+        self.indentation -= 1;
+        self.line(text);
+    }
+
+    /// Ends a block and opens the next on the same line, like `} else {`.
+    pub fn reopen(&mut self, text: &str) {
+        self.close(text);
+        self.indentation += 1;
+        self.block_start = true;
+    }
+
+    /// Separates what comes next from what came before by an empty line.
+    pub fn blank_line(&mut self) {
+        self.blank_line = true;
+    }
+
+    /// Marks the next line as compiled from the given line and column of the source.
+    pub fn mark(&mut self, line: usize, column: usize) {
+        self.mark = Some((line, column));
+    }
+
+    /// Returns the code and where its marked lines come from in the source.
+    pub fn finish(self) -> (String, Vec<Mapping>) {
+        (self.output, self.mappings)
+    }
+}
@@ -2,13 +2,18 @@ use std::{cell::RefCell, rc::Rc};
 
 use crate::{
     common::{
-        diagnostic::DiagnosticBag,
+        diagnostic::{DiagnosticBag, Position, Suggestion},
+        intern::{Interner, Symbol},
+        spelling::closest_match,
         types::{Object, Type},
     },
-    syntax::syntax_tree::{
-        AssignmentExpression, BinaryExpression, BlockStatement, Expression, ForStatement,
-        IfStatement, LiteralExpression, NameExpression, ParenthesizedExpression, Statement,
-        UnaryExpression, VarStatement, WhileStatement,
+    syntax::{
+        syntax_tree::{
+            AssignmentExpression, BinaryExpression, BlockStatement, Expression, ExpressionId,
+            ForStatement, IfStatement, LiteralExpression, NameExpression, Statement, StatementId,
+            SyntaxTree, UnaryExpression, VarStatement, WhileStatement,
+        },
+        token::{Token, TokenKind},
     },
 };
 
@@ -16,275 +21,585 @@ use super::{
     bindings::Bindings,
     bound_tree::{
         BoundAssignmentExpression, BoundBinaryExpression, BoundBinaryOperator, BoundBlockStatement,
-        BoundExpression, BoundExpressionStatement, BoundForStatement, BoundIfStatement,
-        BoundLiteralExpression, BoundPrintStatement, BoundStatement, BoundUnaryExpression,
-        BoundUnaryOperator, BoundVarStatement, BoundVariableExpression, BoundWhileStatement,
+        BoundExpression, BoundExpressionId, BoundExpressionStatement, BoundForStatement,
+        BoundIfStatement, BoundLiteralExpression, BoundPrintStatement, BoundProgram,
+        BoundStatement, BoundStatementId, BoundUnaryExpression, BoundUnaryOperator,
+        BoundVarStatement, BoundVariableExpression, BoundWhileStatement,
     },
+    semantic_model::SemanticModel,
 };
 
-pub(crate) struct Binder {
+/// Walks a syntax tree into a bound program, recording the type of every expression and the
+/// symbol behind every name and declaration in a `SemanticModel` as it goes.
+pub(crate) struct Binder<'t, 'a> {
+    tree: &'t SyntaxTree<'a>,
+    program: BoundProgram,
+    model: SemanticModel,
     bindings: Rc<RefCell<Bindings>>,
+    interner: Rc<RefCell<Interner>>,
     pub(crate) diagnostic_bag: Rc<RefCell<DiagnosticBag>>,
+    interactive: bool,
 }
 
-impl Binder {
+impl<'t, 'a> Binder<'t, 'a> {
     pub(crate) fn new(
+        tree: &'t SyntaxTree<'a>,
         bindings: Rc<RefCell<Bindings>>,
+        interner: Rc<RefCell<Interner>>,
         diagnostic_bag: Rc<RefCell<DiagnosticBag>>,
+        interactive: bool,
     ) -> Self {
         Self {
+            tree,
+            program: BoundProgram::default(),
+            model: SemanticModel::default(),
             bindings,
+            interner,
             diagnostic_bag,
+            interactive,
         }
     }
 
-    pub(crate) fn bind(&mut self, program: Vec<Statement>) -> Vec<BoundStatement> {
-        let mut bound_statements = vec![];
-        for statement in program {
-            bound_statements.push(self.bind_statement(statement));
+    pub(crate) fn bind(mut self) -> (BoundProgram, SemanticModel) {
+        for &statement in self.tree.root() {
+            let statement = self.bind_statement(statement);
+            self.program.push_root(statement);
         }
-        bound_statements
-    }
-
-    fn bind_statement(&mut self, statement: Statement) -> BoundStatement {
-        match statement {
-            Statement::Expression(statement) => BoundStatement::Expression(
-                BoundExpressionStatement::new(self.bind_expression(statement.expression)),
-            ),
-            Statement::Print(statement) => BoundStatement::Print(BoundPrintStatement::new(
-                self.bind_expression(statement.expression),
-            )),
-            Statement::Var(statement) => self.bind_var_statement(statement),
+        (self.program, self.model)
+    }
+
+    fn bind_statement(&mut self, id: StatementId) -> BoundStatementId {
+        match self.tree.statement(id) {
+            Statement::Expression(statement) => {
+                let expression = self.bind_expression(statement.expression);
+                let position = self.position(expression);
+                self.statement(BoundStatement::Expression(BoundExpressionStatement::new(
+                    expression, position,
+                )))
+            }
+            Statement::Print(statement) => {
+                let expression = self.bind_expression(statement.expression);
+                let position = self.position(expression);
+                self.statement(BoundStatement::Print(BoundPrintStatement::new(
+                    expression, position,
+                )))
+            }
+            Statement::Var(statement) => self.bind_var_statement(id, statement),
             Statement::Block(statement) => self.bind_block_statement(statement),
             Statement::If(statement) => self.bind_if_statement(statement),
             Statement::While(statement) => self.bind_while_statement(statement),
-            Statement::For(statement) => self.bind_for_statement(statement),
+            Statement::For(statement) => self.bind_for_statement(id, statement),
         }
     }
 
-    fn bind_var_statement(&mut self, statement: VarStatement) -> BoundStatement {
+    fn bind_var_statement(
+        &mut self,
+        id: StatementId,
+        statement: &VarStatement,
+    ) -> BoundStatementId {
         let bound_expression = self.bind_expression(statement.expression);
-        self.bindings.borrow_mut().set(
-            statement.identifier.lexeme.clone(),
-            bound_expression.get_type(),
+        let is_global = self.bindings.borrow().outer.is_none();
+        let name = self.intern(&statement.identifier);
+        let previous_position = self.bindings.borrow().get_local_position(name);
+        if let Some(previous_position) = previous_position {
+            if !(self.interactive && is_global) {
+                self.diagnostic_bag.borrow_mut().redeclared_name(
+                    statement.identifier.position.clone(),
+                    statement.identifier.lexeme.to_string(),
+                    previous_position,
+                );
+                return self.error_statement(statement.identifier.position.clone());
+            }
+        }
+        let variable = self.bindings.borrow_mut().declare(
+            name,
+            self.type_of(bound_expression),
+            statement.identifier.position.clone(),
         );
-        BoundStatement::Var(BoundVarStatement::new(
-            statement.identifier.lexeme,
+        self.model.declarations.insert(id, variable.clone());
+        self.statement(BoundStatement::Var(BoundVarStatement::new(
+            variable,
             bound_expression,
-        ))
+            statement.identifier.position.clone(),
+        )))
     }
 
-    fn bind_block_statement(&mut self, statement: BlockStatement) -> BoundStatement {
+    fn bind_block_statement(&mut self, statement: &BlockStatement) -> BoundStatementId {
         let mut statements = vec![];
-        self.bindings = Rc::new(RefCell::new(Bindings::extend(Rc::clone(&self.bindings))));
-        for statement in statement.statements {
+        self.begin_scope();
+        for &statement in &statement.statements {
             statements.push(self.bind_statement(statement));
         }
+        self.end_scope();
+        self.statement(BoundStatement::Block(BoundBlockStatement::new(
+            statements,
+            statement.get_position(),
+        )))
+    }
+
+    /// Binds the body of an `if` or `while` in a scope of its own, so a declaration there is
+    /// never visible after the statement. A block already opens one.
+    fn bind_nested_statement(&mut self, id: StatementId) -> BoundStatementId {
+        if let Statement::Block(statement) = self.tree.statement(id) {
+            return self.bind_block_statement(statement);
+        }
+        self.begin_scope();
+        let statement = self.bind_statement(id);
+        self.end_scope();
+        statement
+    }
+
+    fn begin_scope(&mut self) {
+        self.bindings = Rc::new(RefCell::new(Bindings::extend(Rc::clone(&self.bindings))));
+    }
+
+    fn end_scope(&mut self) {
         let old_bindings = self.bindings.borrow().outer.clone().unwrap();
         self.bindings = old_bindings;
-        BoundStatement::Block(BoundBlockStatement::new(statements))
     }
 
-    fn bind_if_statement(&mut self, statement: IfStatement) -> BoundStatement {
-        let condition = self.bind_expression(statement.condition.clone());
-        if condition.get_type() == Type::Boolean {
-            let consequence = self.bind_statement(*statement.consequence);
-            let else_clause =
-                (*statement.else_clause).map(|statement| self.bind_statement(statement));
-            BoundStatement::If(BoundIfStatement::new(condition, consequence, else_clause))
+    fn bind_if_statement(&mut self, statement: &IfStatement) -> BoundStatementId {
+        let condition = self.bind_expression(statement.condition);
+        let position = self.position(condition);
+        if self.type_of(condition) == Type::Boolean {
+            let consequence = self.bind_nested_statement(statement.consequence);
+            let else_clause = statement
+                .else_clause
+                .map(|statement| self.bind_nested_statement(statement));
+            self.statement(BoundStatement::If(BoundIfStatement::new(
+                condition,
+                consequence,
+                else_clause,
+                position,
+            )))
         } else {
             self.diagnostic_bag.borrow_mut().invalid_expression_type(
-                condition.get_position(),
+                position.clone(),
                 Type::Boolean,
-                condition.get_type(),
+                self.type_of(condition),
             );
-            BoundStatement::Expression(BoundExpressionStatement::new(BoundExpression::Literal(
-                BoundLiteralExpression::new(Object::Unit, statement.condition.get_position()),
-            )))
+            self.error_statement(position)
         }
     }
 
-    fn bind_while_statement(&mut self, statement: WhileStatement) -> BoundStatement {
-        let condition = self.bind_expression(statement.condition.clone());
-        if condition.get_type() == Type::Boolean {
-            let body = self.bind_statement(*statement.body);
-            BoundStatement::While(BoundWhileStatement::new(condition, body))
+    fn bind_while_statement(&mut self, statement: &WhileStatement) -> BoundStatementId {
+        let condition = self.bind_expression(statement.condition);
+        let position = self.position(condition);
+        if self.type_of(condition) == Type::Boolean {
+            let body = self.bind_nested_statement(statement.body);
+            self.statement(BoundStatement::While(BoundWhileStatement::new(
+                condition, body, position,
+            )))
         } else {
             self.diagnostic_bag.borrow_mut().invalid_expression_type(
-                condition.get_position(),
+                position.clone(),
                 Type::Boolean,
-                condition.get_type(),
+                self.type_of(condition),
             );
-            BoundStatement::Expression(BoundExpressionStatement::new(BoundExpression::Literal(
-                BoundLiteralExpression::new(Object::Unit, statement.condition.get_position()),
-            )))
+            self.error_statement(position)
         }
     }
 
-    fn bind_for_statement(&mut self, statement: ForStatement) -> BoundStatement {
+    fn bind_for_statement(
+        &mut self,
+        id: StatementId,
+        statement: &ForStatement,
+    ) -> BoundStatementId {
         let lower_bound = self.bind_expression(statement.lower_bound);
         let upper_bound = self.bind_expression(statement.upper_bound);
-        match (lower_bound.get_type(), upper_bound.get_type()) {
+        match (self.type_of(lower_bound), self.type_of(upper_bound)) {
             (Type::Number, Type::Number) => {
-                self.bindings
-                    .borrow_mut()
-                    .set(statement.identifier.lexeme.clone(), Type::Number);
-                let body = self.bind_statement(*statement.body);
-                BoundStatement::For(BoundForStatement::new(
-                    statement.identifier.lexeme,
+                self.begin_scope();
+                let name = self.intern(&statement.identifier);
+                let variable = self.bindings.borrow_mut().declare(
+                    name,
+                    Type::Number,
+                    statement.identifier.position.clone(),
+                );
+                self.model.declarations.insert(id, variable.clone());
+                let body = self.bind_statement(statement.body);
+                self.end_scope();
+                self.statement(BoundStatement::For(BoundForStatement::new(
+                    variable,
                     lower_bound,
                     upper_bound,
                     body,
-                ))
+                    statement.identifier.position.clone(),
+                )))
             }
-            (_, _) => {
+            (lower_type, upper_type) => {
                 self.diagnostic_bag.borrow_mut().invalid_expression_type(
-                    lower_bound.get_position(),
+                    self.position(lower_bound),
                     Type::Number,
-                    lower_bound.get_type(),
+                    lower_type,
                 );
                 self.diagnostic_bag.borrow_mut().invalid_expression_type(
-                    upper_bound.get_position(),
+                    self.position(upper_bound),
                     Type::Number,
-                    upper_bound.get_type(),
+                    upper_type,
                 );
-                BoundStatement::Expression(BoundExpressionStatement::new(BoundExpression::Literal(
-                    BoundLiteralExpression::new(Object::Unit, statement.identifier.position),
-                )))
+                self.error_statement(statement.identifier.position.clone())
             }
         }
     }
 
-    fn bind_expression(&mut self, expression: Expression) -> BoundExpression {
-        match expression {
+    fn bind_expression(&mut self, id: ExpressionId) -> BoundExpressionId {
+        let bound_expression = match self.tree.expression(id) {
             Expression::Literal(expression) => self.bind_literal_expression(expression),
-            Expression::Name(expression) => self.bind_name_expression(expression),
-            Expression::Parenthesized(expression) => self.bind_parenthesized_expression(expression),
+            Expression::Name(expression) => self.bind_name_expression(id, expression),
+            Expression::Parenthesized(expression) => self.bind_expression(expression.expression),
             Expression::Unary(expression) => self.bind_unary_expression(expression),
             Expression::Binary(expression) => self.bind_binary_expression(expression),
-            Expression::Assignment(expression) => self.bind_assignment_expression(expression),
+            Expression::Assignment(expression) => self.bind_assignment_expression(id, expression),
+        };
+        let typ = self.type_of(bound_expression);
+        if typ != Type::Unit {
+            self.model.types.insert(id, typ);
         }
+        bound_expression
     }
 
-    fn bind_literal_expression(&self, expression: LiteralExpression) -> BoundExpression {
-        BoundExpression::Literal(BoundLiteralExpression::new(
+    fn bind_literal_expression(&mut self, expression: &LiteralExpression) -> BoundExpressionId {
+        self.expression(BoundExpression::Literal(BoundLiteralExpression::new(
             expression.value.clone(),
             expression.get_position(),
-        ))
+        )))
     }
 
-    fn bind_name_expression(&mut self, expression: NameExpression) -> BoundExpression {
-        if let Some(typ) = self.bindings.borrow().get(&expression.identifier.lexeme) {
-            BoundExpression::Variable(BoundVariableExpression::new(
-                expression.identifier.lexeme.clone(),
-                typ,
+    fn bind_name_expression(
+        &mut self,
+        id: ExpressionId,
+        expression: &NameExpression,
+    ) -> BoundExpressionId {
+        let variable = self
+            .bindings
+            .borrow()
+            .get(self.intern(&expression.identifier));
+        if let Some(variable) = variable {
+            self.model.references.insert(id, variable.clone());
+            self.expression(BoundExpression::Variable(BoundVariableExpression::new(
+                variable,
                 expression.get_position(),
-            ))
+            )))
         } else {
+            let suggestion = self.suggest_name(&expression.identifier);
             self.diagnostic_bag.borrow_mut().undefined_name(
                 expression.get_position(),
-                expression.identifier.lexeme.clone(),
+                expression.identifier.lexeme.to_string(),
+                suggestion,
             );
-            BoundExpression::Literal(BoundLiteralExpression::new(
-                Object::Unit,
-                expression.get_position(),
-            ))
+            self.error_expression(expression.get_position())
         }
     }
 
-    fn bind_parenthesized_expression(
-        &mut self,
-        expression: ParenthesizedExpression,
-    ) -> BoundExpression {
-        self.bind_expression(*expression.expression)
-    }
-
-    fn bind_unary_expression(&mut self, expression: UnaryExpression) -> BoundExpression {
-        let right = self.bind_expression(*expression.right.clone());
+    fn bind_unary_expression(&mut self, expression: &UnaryExpression) -> BoundExpressionId {
+        let right = self.bind_expression(expression.right);
+        let right_type = self.type_of(right);
         if let Some(operator) =
-            BoundUnaryOperator::bind(expression.operator.kind.clone(), right.get_type())
+            BoundUnaryOperator::bind(expression.operator.kind.clone(), right_type.clone())
         {
-            BoundExpression::Unary(BoundUnaryExpression::new(
+            self.expression(BoundExpression::Unary(BoundUnaryExpression::new(
                 operator,
                 right,
                 expression.get_position(),
-            ))
+            )))
         } else {
             self.diagnostic_bag.borrow_mut().invalid_unary_operator(
                 expression.get_position(),
                 expression.operator.kind.clone(),
-                right.get_type(),
+                right_type,
             );
-            BoundExpression::Literal(BoundLiteralExpression::new(
-                Object::Unit,
-                expression.get_position(),
-            ))
+            self.error_expression(expression.get_position())
         }
     }
 
-    fn bind_binary_expression(&mut self, expression: BinaryExpression) -> BoundExpression {
-        let left = self.bind_expression(*expression.left.clone());
-        let right = self.bind_expression(*expression.right.clone());
+    fn bind_binary_expression(&mut self, expression: &BinaryExpression) -> BoundExpressionId {
+        let left = self.bind_expression(expression.left);
+        let right = self.bind_expression(expression.right);
+        let (left_type, right_type) = (self.type_of(left), self.type_of(right));
         if let Some(operator) = BoundBinaryOperator::bind(
             expression.operator.kind.clone(),
-            left.get_type(),
-            right.get_type(),
+            left_type.clone(),
+            right_type.clone(),
         ) {
-            BoundExpression::Binary(BoundBinaryExpression::new(
+            self.expression(BoundExpression::Binary(BoundBinaryExpression::new(
                 left,
                 operator,
                 right,
                 expression.get_position(),
-            ))
+            )))
         } else {
             self.diagnostic_bag.borrow_mut().invalid_binary_operator(
                 expression.get_position(),
                 expression.operator.kind.clone(),
-                left.get_type(),
-                right.get_type(),
+                left_type,
+                right_type,
             );
-            BoundExpression::Literal(BoundLiteralExpression::new(
-                Object::Unit,
-                expression.get_position(),
-            ))
+            self.error_expression(expression.get_position())
         }
     }
 
-    fn bind_assignment_expression(&mut self, expression: AssignmentExpression) -> BoundExpression {
-        let bound_expression = self.bind_expression(*expression.expression.clone());
-        let typ = self.bindings.borrow().get(&expression.identifier.lexeme);
-        if let Some(typ) = typ {
-            if typ == bound_expression.get_type() {
-                self.bindings.borrow_mut().reset(
-                    expression.identifier.lexeme.clone(),
-                    bound_expression.get_type(),
-                );
-                BoundExpression::Assignment(BoundAssignmentExpression::new(
-                    expression.identifier.lexeme.clone(),
+    fn bind_assignment_expression(
+        &mut self,
+        id: ExpressionId,
+        expression: &AssignmentExpression,
+    ) -> BoundExpressionId {
+        let bound_expression = self.bind_expression(expression.expression);
+        let variable = self
+            .bindings
+            .borrow()
+            .get(self.intern(&expression.identifier));
+        if let Some(variable) = variable {
+            if variable.typ == self.type_of(bound_expression) {
+                self.model.references.insert(id, variable.clone());
+                self.expression(BoundExpression::Assignment(BoundAssignmentExpression::new(
+                    variable,
                     bound_expression,
                     expression.get_position(),
-                ))
+                )))
             } else {
                 self.diagnostic_bag.borrow_mut().invalid_assignment(
                     expression.get_position(),
-                    expression.identifier.lexeme.clone(),
-                    typ,
-                    bound_expression.get_type(),
+                    expression.identifier.lexeme.to_string(),
+                    variable.typ,
+                    self.type_of(bound_expression),
                 );
-                BoundExpression::Literal(BoundLiteralExpression::new(
-                    Object::Unit,
-                    expression.get_position(),
-                ))
+                self.error_expression(expression.get_position())
             }
         } else {
+            let suggestion = self.suggest_name(&expression.identifier);
             self.diagnostic_bag.borrow_mut().undefined_name(
                 expression.get_position(),
-                expression.identifier.lexeme.clone(),
+                expression.identifier.lexeme.to_string(),
+                suggestion,
             );
-            BoundExpression::Literal(BoundLiteralExpression::new(
-                Object::Unit,
-                expression.get_position(),
-            ))
+            self.error_expression(expression.get_position())
         }
     }
+
+    /// Stands in for an expression that failed to bind; the error has already been reported.
+    fn error_expression(&mut self, position: Position) -> BoundExpressionId {
+        self.expression(BoundExpression::Literal(BoundLiteralExpression::new(
+            Object::Unit,
+            position,
+        )))
+    }
+
+    /// Stands in for a statement that failed to bind; the error has already been reported.
+    fn error_statement(&mut self, position: Position) -> BoundStatementId {
+        let expression = self.error_expression(position.clone());
+        self.statement(BoundStatement::Expression(BoundExpressionStatement::new(
+            expression, position,
+        )))
+    }
+
+    fn expression(&mut self, expression: BoundExpression) -> BoundExpressionId {
+        self.program.alloc_expression(expression)
+    }
+
+    fn statement(&mut self, statement: BoundStatement) -> BoundStatementId {
+        self.program.alloc_statement(statement)
+    }
+
+    fn type_of(&self, expression: BoundExpressionId) -> Type {
+        self.program.expression(expression).get_type()
+    }
+
+    fn position(&self, expression: BoundExpressionId) -> Position {
+        self.program.expression(expression).get_position()
+    }
+
+    fn intern(&self, identifier: &Token) -> Symbol {
+        self.interner.borrow_mut().intern(identifier.lexeme)
+    }
+
+    fn suggest_name(&self, identifier: &Token) -> Option<Suggestion> {
+        let names = self.bindings.borrow().names();
+        let interner = self.interner.borrow();
+        let mut names: Vec<&str> = names.iter().map(|name| interner.resolve(*name)).collect();
+        names.sort();
+        let position = identifier.position.clone();
+        if let Some(name) = closest_match(identifier.lexeme, names) {
+            Some(Suggestion::replace_name(position, name))
+        } else {
+            closest_match(identifier.lexeme, TokenKind::keywords())
+                .map(|keyword| Suggestion::replace_keyword(position, keyword))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        binding::{
+            bindings::Bindings,
+            bound_tree::{BoundForStatement, BoundProgram, BoundVarStatement},
+            symbol::VariableSymbol,
+            visitor::{walk_for_statement, walk_program, BoundTreeVisitor},
+        },
+        common::{diagnostic::Severity, intern::Interner},
+        compilation::Compilation,
+    };
+
+    /// The errors in a program, each with the line it points at and the lines of its notes.
+    fn errors(source: &str, interactive: bool) -> Vec<(usize, String, Vec<usize>)> {
+        let bindings = Rc::new(RefCell::new(Bindings::default()));
+        let interner = Rc::new(RefCell::new(Interner::default()));
+        let compilation = if interactive {
+            Compilation::compile_interactive(source, bindings, interner)
+        } else {
+            Compilation::compile(source, bindings, interner)
+        };
+        let diagnostic_bag = compilation.diagnostic_bag.borrow();
+        diagnostic_bag
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| {
+                (
+                    diagnostic.position.get_line(source),
+                    diagnostic.message.clone(),
+                    diagnostic
+                        .notes
+                        .iter()
+                        .map(|note| note.position.get_line(source))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn redeclaring_a_name_in_the_same_scope_is_an_error() {
+        assert_eq!(
+            errors("var a = 1\nprint a\nvar a = 2\nprint a\n", false),
+            [(
+                3,
+                "Name 'a' is already declared in this scope".to_string(),
+                vec![1]
+            )]
+        );
+        assert_eq!(
+            errors("{\n    var a = 1\n    var a = a\n    print a\n}\n", false).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn names_may_be_declared_again_in_an_inner_scope() {
+        assert!(errors(
+            "var a = 1\n{\n    var a = 2\n    print a\n}\nprint a\n",
+            false
+        )
+        .is_empty());
+        assert!(errors("var a = 1\nif a > 0 var a = 2\nprint a\n", false).is_empty());
+    }
+
+    #[test]
+    fn interactive_globals_may_be_declared_again() {
+        assert!(errors("var a = 1\nvar a = \"a\"\nprint a\n", true).is_empty());
+        assert_eq!(
+            errors("{\n    var a = 1\n    var a = 2\n    print a\n}\n", true).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn loop_variables_are_scoped_to_their_loop() {
+        assert!(errors(
+            "for i = 0 to 1 print i\nfor i = 0 to 1 print i\nvar i = 2\nprint i\n",
+            false
+        )
+        .is_empty());
+        assert!(errors("var i = 5\nfor i = 0 to i print i\nprint i\n", false).is_empty());
+        assert_eq!(
+            errors("for i = 0 to 1 print i\nprint i\n", false),
+            [(2, "Name 'i' is not defined".to_string(), vec![])]
+        );
+    }
+
+    /// The variables a program declares, in order, with the frame slots they were given.
+    fn declarations(source: &str) -> Vec<(String, usize, usize)> {
+        struct Declarations(Vec<VariableSymbol>);
+
+        impl BoundTreeVisitor for Declarations {
+            fn visit_var_statement(
+                &mut self,
+                program: &BoundProgram,
+                statement: &BoundVarStatement,
+            ) {
+                self.visit_expression(program, statement.expression);
+                self.0.push(statement.variable.clone());
+            }
+
+            fn visit_for_statement(
+                &mut self,
+                program: &BoundProgram,
+                statement: &BoundForStatement,
+            ) {
+                self.0.push(statement.variable.clone());
+                walk_for_statement(self, program, statement);
+            }
+        }
+
+        let compilation = Compilation::compile(
+            source,
+            Rc::new(RefCell::new(Bindings::default())),
+            Rc::new(RefCell::new(Interner::default())),
+        );
+        let mut declarations = Declarations(vec![]);
+        walk_program(&mut declarations, &compilation.bound_program);
+        let interner = compilation.interner.borrow();
+        declarations
+            .0
+            .into_iter()
+            .map(|symbol| {
+                (
+                    interner.resolve(symbol.name).to_string(),
+                    symbol.depth,
+                    symbol.slot,
+                )
+            })
+            .collect()
+    }
+
+    fn slot(name: &str, depth: usize, slot: usize) -> (String, usize, usize) {
+        (name.to_string(), depth, slot)
+    }
+
+    #[test]
+    fn variables_get_a_slot_in_the_scope_that_declares_them() {
+        assert_eq!(
+            declarations("var a = 1\n{\n    var b = a\n    var c = b\n}\nvar d = a\n"),
+            [
+                slot("a", 0, 0),
+                slot("b", 1, 0),
+                slot("c", 1, 1),
+                slot("d", 0, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn bodies_that_are_not_blocks_get_a_scope_of_their_own() {
+        assert_eq!(
+            declarations("var c = true\nif c var a = 1 else var b = 2\nwhile c var d = 3\n"),
+            [
+                slot("c", 0, 0),
+                slot("a", 1, 0),
+                slot("b", 1, 0),
+                slot("d", 1, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn loop_variables_live_in_a_scope_around_the_body() {
+        assert_eq!(
+            declarations("var n = 2\nfor i = 0 to n {\n    var j = i\n}\n"),
+            [slot("n", 0, 0), slot("i", 1, 0), slot("j", 2, 0)]
+        );
+    }
 }
@@ -1,24 +1,39 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use crate::common::types::Type;
+use crate::common::{diagnostic::Position, intern::Symbol, types::Type};
+
+use super::symbol::VariableSymbol;
+
+#[derive(Debug)]
+struct Binding {
+    symbol: VariableSymbol,
+    position: Position,
+}
 
 #[derive(Debug, Default)]
 pub struct Bindings {
     pub(crate) outer: Option<Rc<RefCell<Bindings>>>,
-    bindings: HashMap<String, Type>,
+    bindings: HashMap<Symbol, Binding>,
+    depth: usize,
+    slots: usize,
+    next_id: usize,
 }
 
 impl Bindings {
     pub fn extend(with: Rc<RefCell<Bindings>>) -> Self {
+        let depth = with.borrow().depth + 1;
         Self {
             outer: Some(with),
             bindings: HashMap::new(),
+            depth,
+            slots: 0,
+            next_id: 0,
         }
     }
 
-    pub(crate) fn get(&self, name: &str) -> Option<Type> {
-        if let Some(object) = self.bindings.get(name) {
-            Some(object.clone())
+    pub(crate) fn get(&self, name: Symbol) -> Option<VariableSymbol> {
+        if let Some(binding) = self.bindings.get(&name) {
+            Some(binding.symbol.clone())
         } else if let Some(outer) = &self.outer {
             outer.borrow().get(name)
         } else {
@@ -26,17 +41,135 @@ impl Bindings {
         }
     }
 
-    pub(crate) fn reset(&mut self, name: String, typ: Type) {
-        if self.bindings.get(&name).is_some() {
-            self.bindings.insert(name, typ);
-        } else if let Some(outer) = &self.outer {
-            outer.borrow_mut().reset(name, typ)
+    pub(crate) fn get_local_position(&self, name: Symbol) -> Option<Position> {
+        self.bindings
+            .get(&name)
+            .map(|binding| binding.position.clone())
+    }
+
+    pub(crate) fn names(&self) -> Vec<Symbol> {
+        let mut names: Vec<Symbol> = self.bindings.keys().copied().collect();
+        if let Some(outer) = &self.outer {
+            names.extend(outer.borrow().names());
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Declares `name` in this scope. Redeclaring a name (which the binder only allows for
+    /// globals of an interactive session) gives it a fresh symbol in the old slot.
+    pub(crate) fn declare(
+        &mut self,
+        name: Symbol,
+        typ: Type,
+        position: Position,
+    ) -> VariableSymbol {
+        let slot = match self.bindings.get(&name) {
+            Some(binding) => binding.symbol.slot,
+            None => {
+                self.slots += 1;
+                self.slots - 1
+            }
+        };
+        let symbol = VariableSymbol::new(self.allocate_id(), name, typ, self.depth, slot);
+        self.bindings.insert(
+            name,
+            Binding {
+                symbol: symbol.clone(),
+                position,
+            },
+        );
+        symbol
+    }
+
+    fn allocate_id(&mut self) -> usize {
+        if let Some(outer) = &self.outer {
+            outer.borrow_mut().allocate_id()
         } else {
-            return;
+            self.next_id += 1;
+            self.next_id - 1
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::common::{diagnostic::Position, intern::Interner, types::Type};
+
+    use super::Bindings;
+
+    #[test]
+    fn each_scope_counts_its_own_slots() {
+        let mut interner = Interner::default();
+        let (a, b, c) = (
+            interner.intern("a"),
+            interner.intern("b"),
+            interner.intern("c"),
+        );
+        let global = Rc::new(RefCell::new(Bindings::default()));
+        let first = global
+            .borrow_mut()
+            .declare(a, Type::Number, Position::default());
+        let mut block = Bindings::extend(Rc::clone(&global));
+        let second = block.declare(b, Type::Number, Position::default());
+        let third = block.declare(c, Type::Boolean, Position::default());
+        assert_eq!((first.depth, first.slot), (0, 0));
+        assert_eq!((second.depth, second.slot), (1, 0));
+        assert_eq!((third.depth, third.slot), (1, 1));
+        let later = global
+            .borrow_mut()
+            .declare(b, Type::Number, Position::default());
+        assert_eq!((later.depth, later.slot), (0, 1));
+    }
+
+    #[test]
+    fn ids_are_unique_across_scopes() {
+        let mut interner = Interner::default();
+        let name = interner.intern("a");
+        let global = Rc::new(RefCell::new(Bindings::default()));
+        let outer = global
+            .borrow_mut()
+            .declare(name, Type::Number, Position::default());
+        let mut first = Bindings::extend(Rc::clone(&global));
+        let mut second = Bindings::extend(Rc::clone(&global));
+        let inner = first.declare(name, Type::Number, Position::default());
+        let sibling = second.declare(name, Type::Number, Position::default());
+        assert_eq!([outer.id, inner.id, sibling.id], [0, 1, 2]);
+    }
+
+    #[test]
+    fn names_resolve_to_the_innermost_declaration() {
+        let mut interner = Interner::default();
+        let (a, b) = (interner.intern("a"), interner.intern("b"));
+        let global = Rc::new(RefCell::new(Bindings::default()));
+        global
+            .borrow_mut()
+            .declare(a, Type::Number, Position::default());
+        global
+            .borrow_mut()
+            .declare(b, Type::Number, Position::default());
+        let mut block = Bindings::extend(Rc::clone(&global));
+        let inner = block.declare(a, Type::String, Position::default());
+        assert_eq!(block.get(a), Some(inner));
+        assert_eq!(block.get(b).map(|symbol| symbol.depth), Some(0));
+        assert_eq!(block.get(interner.intern("c")), None);
+        assert!(block.get_local_position(b).is_none());
+        assert_eq!(block.names(), [a, b]);
+    }
 
-    pub(crate) fn set(&mut self, name: String, typ: Type) {
-        self.bindings.insert(name, typ);
+    #[test]
+    fn a_redeclared_name_keeps_its_slot() {
+        let mut interner = Interner::default();
+        let (a, b) = (interner.intern("a"), interner.intern("b"));
+        let mut global = Bindings::default();
+        let first = global.declare(a, Type::Number, Position::default());
+        global.declare(b, Type::Number, Position::default());
+        let again = global.declare(a, Type::String, Position::default());
+        assert_eq!(again.slot, first.slot);
+        assert_ne!(again.id, first.id);
+        assert_eq!(global.get(a).map(|symbol| symbol.typ), Some(Type::String));
     }
 }
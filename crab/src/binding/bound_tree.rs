@@ -2,12 +2,15 @@ use std::fmt::Display;
 
 use crate::{
     common::{
+        arena::{node_id, Arena},
         diagnostic::Position,
         types::{Object, Type},
     },
     syntax::token::TokenKind,
 };
 
+use super::symbol::VariableSymbol;
+
 #[derive(Debug, Clone)]
 pub enum BoundBinaryOperationKind {
     Addition,
@@ -255,6 +258,44 @@ impl BoundBinaryOperator {
     }
 }
 
+node_id!(BoundExpressionId);
+node_id!(BoundStatementId);
+
+/// A bound program. Like the syntax tree its nodes live in arenas and refer to their children by
+/// id, so the analyses and the backends walk one shared tree without copying it.
+#[derive(Debug, Clone, Default)]
+pub struct BoundProgram {
+    expressions: Arena<BoundExpressionId, BoundExpression>,
+    statements: Arena<BoundStatementId, BoundStatement>,
+    root: Vec<BoundStatementId>,
+}
+
+impl BoundProgram {
+    pub fn root(&self) -> &[BoundStatementId] {
+        &self.root
+    }
+
+    pub fn expression(&self, id: BoundExpressionId) -> &BoundExpression {
+        &self.expressions[id]
+    }
+
+    pub fn statement(&self, id: BoundStatementId) -> &BoundStatement {
+        &self.statements[id]
+    }
+
+    pub(crate) fn alloc_expression(&mut self, expression: BoundExpression) -> BoundExpressionId {
+        self.expressions.alloc(expression)
+    }
+
+    pub(crate) fn alloc_statement(&mut self, statement: BoundStatement) -> BoundStatementId {
+        self.statements.alloc(statement)
+    }
+
+    pub(crate) fn push_root(&mut self, statement: BoundStatementId) {
+        self.root.push(statement);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum BoundExpression {
     Literal(BoundLiteralExpression),
@@ -293,7 +334,7 @@ pub struct BoundLiteralExpression {
 }
 
 impl BoundLiteralExpression {
-    pub(super) fn new(value: Object, position: Position) -> Self {
+    pub(crate) fn new(value: Object, position: Position) -> Self {
         Self { value, position }
     }
 
@@ -308,22 +349,17 @@ impl BoundLiteralExpression {
 
 #[derive(Debug, Clone)]
 pub struct BoundVariableExpression {
-    pub name: String,
-    pub value_type: Type,
+    pub variable: VariableSymbol,
     position: Position,
 }
 
 impl BoundVariableExpression {
-    pub(super) fn new(name: String, value_type: Type, position: Position) -> Self {
-        Self {
-            name,
-            value_type,
-            position,
-        }
+    pub(crate) fn new(variable: VariableSymbol, position: Position) -> Self {
+        Self { variable, position }
     }
 
     fn get_type(&self) -> Type {
-        self.value_type.clone()
+        self.variable.typ.clone()
     }
 
     fn get_position(&self) -> Position {
@@ -334,19 +370,19 @@ impl BoundVariableExpression {
 #[derive(Debug, Clone)]
 pub struct BoundUnaryExpression {
     pub operator: BoundUnaryOperator,
-    pub right: Box<BoundExpression>,
+    pub right: BoundExpressionId,
     position: Position,
 }
 
 impl BoundUnaryExpression {
-    pub(super) fn new(
+    pub(crate) fn new(
         operator: BoundUnaryOperator,
-        right: BoundExpression,
+        right: BoundExpressionId,
         position: Position,
     ) -> Self {
         Self {
             operator,
-            right: Box::new(right),
+            right,
             position,
         }
     }
@@ -362,23 +398,23 @@ impl BoundUnaryExpression {
 
 #[derive(Debug, Clone)]
 pub struct BoundBinaryExpression {
-    pub left: Box<BoundExpression>,
+    pub left: BoundExpressionId,
     pub operator: BoundBinaryOperator,
-    pub right: Box<BoundExpression>,
+    pub right: BoundExpressionId,
     position: Position,
 }
 
 impl BoundBinaryExpression {
-    pub(super) fn new(
-        left: BoundExpression,
+    pub(crate) fn new(
+        left: BoundExpressionId,
         operator: BoundBinaryOperator,
-        right: BoundExpression,
+        right: BoundExpressionId,
         position: Position,
     ) -> Self {
         Self {
-            left: Box::new(left),
+            left,
             operator,
-            right: Box::new(right),
+            right,
             position,
         }
     }
@@ -394,25 +430,30 @@ impl BoundBinaryExpression {
 
 #[derive(Debug, Clone)]
 pub struct BoundAssignmentExpression {
-    pub name: String,
-    pub expression: Box<BoundExpression>,
+    pub variable: VariableSymbol,
+    pub expression: BoundExpressionId,
     position: Position,
 }
 
 impl BoundAssignmentExpression {
-    pub fn new(name: String, expression: BoundExpression, position: Position) -> Self {
+    pub fn new(
+        variable: VariableSymbol,
+        expression: BoundExpressionId,
+        position: Position,
+    ) -> Self {
         Self {
-            name,
-            expression: Box::new(expression),
+            variable,
+            expression,
             position,
         }
     }
 
+    /// The binder only accepts values of the variable's own type.
     pub fn get_type(&self) -> Type {
-        self.expression.get_type()
+        self.variable.typ.clone()
     }
 
-    fn get_position(&self) -> Position {
+    pub fn get_position(&self) -> Position {
         self.position.clone()
     }
 }
@@ -428,110 +469,184 @@ pub enum BoundStatement {
     For(BoundForStatement),
 }
 
+impl BoundStatement {
+    pub fn get_position(&self) -> Position {
+        match self {
+            BoundStatement::Expression(statement) => statement.get_position(),
+            BoundStatement::Print(statement) => statement.get_position(),
+            BoundStatement::Var(statement) => statement.get_position(),
+            BoundStatement::Block(statement) => statement.get_position(),
+            BoundStatement::If(statement) => statement.get_position(),
+            BoundStatement::While(statement) => statement.get_position(),
+            BoundStatement::For(statement) => statement.get_position(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BoundExpressionStatement {
-    pub expression: BoundExpression,
+    pub expression: BoundExpressionId,
+    position: Position,
 }
 
 impl BoundExpressionStatement {
-    pub fn new(expression: BoundExpression) -> Self {
-        Self { expression }
+    pub fn new(expression: BoundExpressionId, position: Position) -> Self {
+        Self {
+            expression,
+            position,
+        }
+    }
+
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct BoundPrintStatement {
-    pub expression: BoundExpression,
+    pub expression: BoundExpressionId,
+    position: Position,
 }
 
 impl BoundPrintStatement {
-    pub fn new(expression: BoundExpression) -> Self {
-        Self { expression }
+    pub fn new(expression: BoundExpressionId, position: Position) -> Self {
+        Self {
+            expression,
+            position,
+        }
+    }
+
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct BoundVarStatement {
-    pub name: String,
-    pub expression: Box<BoundExpression>,
+    pub variable: VariableSymbol,
+    pub expression: BoundExpressionId,
+    position: Position,
 }
 
 impl BoundVarStatement {
-    pub fn new(name: String, expression: BoundExpression) -> Self {
+    pub fn new(
+        variable: VariableSymbol,
+        expression: BoundExpressionId,
+        position: Position,
+    ) -> Self {
         Self {
-            name,
-            expression: Box::new(expression),
+            variable,
+            expression,
+            position,
         }
     }
+
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct BoundBlockStatement {
-    pub statements: Vec<BoundStatement>,
+    pub statements: Vec<BoundStatementId>,
+    position: Position,
 }
 
 impl BoundBlockStatement {
-    pub(super) fn new(statements: Vec<BoundStatement>) -> Self {
-        Self { statements }
+    pub(crate) fn new(statements: Vec<BoundStatementId>, position: Position) -> Self {
+        Self {
+            statements,
+            position,
+        }
+    }
+
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct BoundIfStatement {
-    pub condition: BoundExpression,
-    pub consequence: Box<BoundStatement>,
-    pub else_clause: Box<Option<BoundStatement>>,
+    pub condition: BoundExpressionId,
+    pub consequence: BoundStatementId,
+    pub else_clause: Option<BoundStatementId>,
+    position: Position,
 }
 
 impl BoundIfStatement {
-    pub(super) fn new(
-        condition: BoundExpression,
-        consequence: BoundStatement,
-        else_clause: Option<BoundStatement>,
+    pub(crate) fn new(
+        condition: BoundExpressionId,
+        consequence: BoundStatementId,
+        else_clause: Option<BoundStatementId>,
+        position: Position,
     ) -> Self {
         Self {
             condition,
-            consequence: Box::new(consequence),
-            else_clause: Box::new(else_clause),
+            consequence,
+            else_clause,
+            position,
         }
     }
+
+    /// The position of the condition.
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct BoundWhileStatement {
-    pub condition: BoundExpression,
-    pub body: Box<BoundStatement>,
+    pub condition: BoundExpressionId,
+    pub body: BoundStatementId,
+    position: Position,
 }
 
 impl BoundWhileStatement {
-    pub(super) fn new(condition: BoundExpression, body: BoundStatement) -> Self {
+    pub(crate) fn new(
+        condition: BoundExpressionId,
+        body: BoundStatementId,
+        position: Position,
+    ) -> Self {
         Self {
             condition,
-            body: Box::new(body),
+            body,
+            position,
         }
     }
+
+    /// The position of the condition.
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct BoundForStatement {
-    pub identifier: String,
-    pub lower_bound: BoundExpression,
-    pub upper_bound: BoundExpression,
-    pub body: Box<BoundStatement>,
+    pub variable: VariableSymbol,
+    pub lower_bound: BoundExpressionId,
+    pub upper_bound: BoundExpressionId,
+    pub body: BoundStatementId,
+    position: Position,
 }
 
 impl BoundForStatement {
-    pub(super) fn new(
-        identifier: String,
-        lower_bound: BoundExpression,
-        upper_bound: BoundExpression,
-        body: BoundStatement,
+    pub(crate) fn new(
+        variable: VariableSymbol,
+        lower_bound: BoundExpressionId,
+        upper_bound: BoundExpressionId,
+        body: BoundStatementId,
+        position: Position,
     ) -> Self {
         Self {
-            identifier,
+            variable,
             lower_bound,
             upper_bound,
-            body: Box::new(body),
+            body,
+            position,
         }
     }
+
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
+    }
 }
@@ -1,3 +1,6 @@
 pub mod binder;
 pub mod bindings;
 pub mod bound_tree;
+pub mod semantic_model;
+pub mod symbol;
+pub mod visitor;
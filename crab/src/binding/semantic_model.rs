@@ -0,0 +1,32 @@
+use crate::{
+    common::{arena::SideTable, types::Type},
+    syntax::syntax_tree::{ExpressionId, StatementId},
+};
+
+use super::symbol::VariableSymbol;
+
+/// What the binder learned about the syntax tree, keyed by the ids of its nodes, for tools that
+/// walk the syntax tree rather than the bound one.
+#[derive(Debug, Default)]
+pub struct SemanticModel {
+    pub(super) types: SideTable<ExpressionId, Type>,
+    pub(super) references: SideTable<ExpressionId, VariableSymbol>,
+    pub(super) declarations: SideTable<StatementId, VariableSymbol>,
+}
+
+impl SemanticModel {
+    /// The type of an expression that bound without errors.
+    pub fn type_of(&self, expression: ExpressionId) -> Option<&Type> {
+        self.types.get(expression)
+    }
+
+    /// The variable that a name or an assignment refers to.
+    pub fn referenced_symbol(&self, expression: ExpressionId) -> Option<&VariableSymbol> {
+        self.references.get(expression)
+    }
+
+    /// The variable that a `var` or `for` statement declares.
+    pub fn declared_symbol(&self, statement: StatementId) -> Option<&VariableSymbol> {
+        self.declarations.get(statement)
+    }
+}
@@ -0,0 +1,25 @@
+use crate::common::{intern::Symbol, types::Type};
+
+/// A variable resolved by the binder. `id` is unique for the lifetime of the root `Bindings`,
+/// while `depth` and `slot` address the variable at runtime: `depth` is the number of scopes
+/// between the variable and the global scope, and `slot` its index among that scope's variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableSymbol {
+    pub id: usize,
+    pub name: Symbol,
+    pub typ: Type,
+    pub depth: usize,
+    pub slot: usize,
+}
+
+impl VariableSymbol {
+    pub(crate) fn new(id: usize, name: Symbol, typ: Type, depth: usize, slot: usize) -> Self {
+        Self {
+            id,
+            name,
+            typ,
+            depth,
+            slot,
+        }
+    }
+}
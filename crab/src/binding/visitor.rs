@@ -0,0 +1,179 @@
+use super::bound_tree::{
+    BoundAssignmentExpression, BoundBinaryExpression, BoundBlockStatement, BoundExpression,
+    BoundExpressionId, BoundExpressionStatement, BoundForStatement, BoundIfStatement,
+    BoundLiteralExpression, BoundPrintStatement, BoundProgram, BoundStatement, BoundStatementId,
+    BoundUnaryExpression, BoundVarStatement, BoundVariableExpression, BoundWhileStatement,
+};
+
+/// Walks a bound program. There is a method for every kind of node, and by default each one
+/// walks the children of its node in the order they are evaluated, so an implementation only
+/// overrides the nodes it cares about. An override that still wants the children visited calls
+/// the `walk_` function of its node.
+pub trait BoundTreeVisitor {
+    fn visit_statement(&mut self, program: &BoundProgram, statement: BoundStatementId) {
+        walk_statement(self, program, statement);
+    }
+
+    fn visit_expression_statement(
+        &mut self,
+        program: &BoundProgram,
+        statement: &BoundExpressionStatement,
+    ) {
+        self.visit_expression(program, statement.expression);
+    }
+
+    fn visit_print_statement(&mut self, program: &BoundProgram, statement: &BoundPrintStatement) {
+        self.visit_expression(program, statement.expression);
+    }
+
+    fn visit_var_statement(&mut self, program: &BoundProgram, statement: &BoundVarStatement) {
+        self.visit_expression(program, statement.expression);
+    }
+
+    fn visit_block_statement(&mut self, program: &BoundProgram, statement: &BoundBlockStatement) {
+        walk_block_statement(self, program, statement);
+    }
+
+    fn visit_if_statement(&mut self, program: &BoundProgram, statement: &BoundIfStatement) {
+        walk_if_statement(self, program, statement);
+    }
+
+    fn visit_while_statement(&mut self, program: &BoundProgram, statement: &BoundWhileStatement) {
+        walk_while_statement(self, program, statement);
+    }
+
+    fn visit_for_statement(&mut self, program: &BoundProgram, statement: &BoundForStatement) {
+        walk_for_statement(self, program, statement);
+    }
+
+    fn visit_expression(&mut self, program: &BoundProgram, expression: BoundExpressionId) {
+        walk_expression(self, program, expression);
+    }
+
+    fn visit_literal_expression(
+        &mut self,
+        _program: &BoundProgram,
+        _expression: &BoundLiteralExpression,
+    ) {
+    }
+
+    fn visit_variable_expression(
+        &mut self,
+        _program: &BoundProgram,
+        _expression: &BoundVariableExpression,
+    ) {
+    }
+
+    fn visit_unary_expression(
+        &mut self,
+        program: &BoundProgram,
+        expression: &BoundUnaryExpression,
+    ) {
+        self.visit_expression(program, expression.right);
+    }
+
+    fn visit_binary_expression(
+        &mut self,
+        program: &BoundProgram,
+        expression: &BoundBinaryExpression,
+    ) {
+        self.visit_expression(program, expression.left);
+        self.visit_expression(program, expression.right);
+    }
+
+    fn visit_assignment_expression(
+        &mut self,
+        program: &BoundProgram,
+        expression: &BoundAssignmentExpression,
+    ) {
+        self.visit_expression(program, expression.expression);
+    }
+}
+
+/// Visits the top-level statements of a program.
+pub fn walk_program<V: BoundTreeVisitor + ?Sized>(visitor: &mut V, program: &BoundProgram) {
+    for &statement in program.root() {
+        visitor.visit_statement(program, statement);
+    }
+}
+
+/// Hands a statement to the method for its kind.
+pub fn walk_statement<V: BoundTreeVisitor + ?Sized>(
+    visitor: &mut V,
+    program: &BoundProgram,
+    statement: BoundStatementId,
+) {
+    match program.statement(statement) {
+        BoundStatement::Expression(statement) => {
+            visitor.visit_expression_statement(program, statement)
+        }
+        BoundStatement::Print(statement) => visitor.visit_print_statement(program, statement),
+        BoundStatement::Var(statement) => visitor.visit_var_statement(program, statement),
+        BoundStatement::Block(statement) => visitor.visit_block_statement(program, statement),
+        BoundStatement::If(statement) => visitor.visit_if_statement(program, statement),
+        BoundStatement::While(statement) => visitor.visit_while_statement(program, statement),
+        BoundStatement::For(statement) => visitor.visit_for_statement(program, statement),
+    }
+}
+
+pub fn walk_block_statement<V: BoundTreeVisitor + ?Sized>(
+    visitor: &mut V,
+    program: &BoundProgram,
+    statement: &BoundBlockStatement,
+) {
+    for &statement in &statement.statements {
+        visitor.visit_statement(program, statement);
+    }
+}
+
+pub fn walk_if_statement<V: BoundTreeVisitor + ?Sized>(
+    visitor: &mut V,
+    program: &BoundProgram,
+    statement: &BoundIfStatement,
+) {
+    visitor.visit_expression(program, statement.condition);
+    visitor.visit_statement(program, statement.consequence);
+    if let Some(else_clause) = statement.else_clause {
+        visitor.visit_statement(program, else_clause);
+    }
+}
+
+pub fn walk_while_statement<V: BoundTreeVisitor + ?Sized>(
+    visitor: &mut V,
+    program: &BoundProgram,
+    statement: &BoundWhileStatement,
+) {
+    visitor.visit_expression(program, statement.condition);
+    visitor.visit_statement(program, statement.body);
+}
+
+pub fn walk_for_statement<V: BoundTreeVisitor + ?Sized>(
+    visitor: &mut V,
+    program: &BoundProgram,
+    statement: &BoundForStatement,
+) {
+    visitor.visit_expression(program, statement.lower_bound);
+    visitor.visit_expression(program, statement.upper_bound);
+    visitor.visit_statement(program, statement.body);
+}
+
+/// Hands an expression to the method for its kind.
+pub fn walk_expression<V: BoundTreeVisitor + ?Sized>(
+    visitor: &mut V,
+    program: &BoundProgram,
+    expression: BoundExpressionId,
+) {
+    match program.expression(expression) {
+        BoundExpression::Literal(expression) => {
+            visitor.visit_literal_expression(program, expression)
+        }
+        BoundExpression::Variable(expression) => {
+            visitor.visit_variable_expression(program, expression)
+        }
+        BoundExpression::Unary(expression) => visitor.visit_unary_expression(program, expression),
+        BoundExpression::Binary(expression) => visitor.visit_binary_expression(program, expression),
+        BoundExpression::Assignment(expression) => {
+            visitor.visit_assignment_expression(program, expression)
+        }
+    }
+}
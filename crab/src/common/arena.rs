@@ -0,0 +1,151 @@
+use std::{
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+/// The index of a node in an `Arena`. Each kind of node gets its own id type, so an id can only
+/// be used with the arena (and the side tables) it belongs to.
+pub trait NodeId: Copy {
+    fn from_index(index: usize) -> Self;
+    fn index(self) -> usize;
+}
+
+macro_rules! node_id {
+    ($(#[$attribute:meta])* $name:ident) => {
+        $(#[$attribute])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(u32);
+
+        impl $crate::common::arena::NodeId for $name {
+            fn from_index(index: usize) -> Self {
+                Self(index as u32)
+            }
+
+            fn index(self) -> usize {
+                self.0 as usize
+            }
+        }
+    };
+}
+
+pub(crate) use node_id;
+
+/// Owns every node of one kind in a tree. Nodes refer to each other by id, so a tree can be
+/// shared by reference between phases and nodes are never cloned to be moved around.
+#[derive(Debug, Clone)]
+pub struct Arena<I, T> {
+    nodes: Vec<T>,
+    marker: PhantomData<I>,
+}
+
+impl<I: NodeId, T> Arena<I, T> {
+    pub(crate) fn alloc(&mut self, node: T) -> I {
+        self.nodes.push(node);
+        I::from_index(self.nodes.len() - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (I, &T)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (I::from_index(index), node))
+    }
+}
+
+impl<I, T> Default for Arena<I, T> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<I: NodeId, T> Index<I> for Arena<I, T> {
+    type Output = T;
+
+    fn index(&self, id: I) -> &T {
+        &self.nodes[id.index()]
+    }
+}
+
+impl<I: NodeId, T> IndexMut<I> for Arena<I, T> {
+    fn index_mut(&mut self, id: I) -> &mut T {
+        &mut self.nodes[id.index()]
+    }
+}
+
+/// Information about the nodes of an arena that a later phase computed, stored next to the tree
+/// instead of in it.
+#[derive(Debug, Clone)]
+pub struct SideTable<I, V> {
+    values: Vec<Option<V>>,
+    marker: PhantomData<I>,
+}
+
+impl<I: NodeId, V> SideTable<I, V> {
+    pub(crate) fn insert(&mut self, id: I, value: V) {
+        let index = id.index();
+        if self.values.len() <= index {
+            self.values.resize_with(index + 1, || None);
+        }
+        self.values[index] = Some(value);
+    }
+
+    pub fn get(&self, id: I) -> Option<&V> {
+        self.values.get(id.index())?.as_ref()
+    }
+}
+
+impl<I, V> Default for SideTable<I, V> {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arena, NodeId, SideTable};
+
+    node_id!(TestId);
+
+    #[test]
+    fn nodes_are_found_by_the_id_they_were_given() {
+        let mut arena: Arena<TestId, &str> = Arena::default();
+        assert!(arena.is_empty());
+        let first = arena.alloc("first");
+        let second = arena.alloc("second");
+        assert_eq!((first.index(), second.index()), (0, 1));
+        assert_eq!(arena[second], "second");
+        arena[first] = "changed";
+        let nodes: Vec<(TestId, &&str)> = arena.iter().collect();
+        assert_eq!(nodes, [(first, &"changed"), (second, &"second")]);
+        assert_eq!(arena.len(), 2);
+    }
+
+    #[test]
+    fn side_tables_only_hold_what_was_inserted() {
+        let mut table: SideTable<TestId, usize> = SideTable::default();
+        let (near, far) = (TestId::from_index(1), TestId::from_index(5));
+        assert_eq!(table.get(near), None);
+        table.insert(far, 50);
+        table.insert(near, 10);
+        assert_eq!(table.get(near), Some(&10));
+        assert_eq!(table.get(far), Some(&50));
+        assert_eq!(table.get(TestId::from_index(3)), None);
+        assert_eq!(table.get(TestId::from_index(9)), None);
+        table.insert(near, 11);
+        assert_eq!(table.get(near), Some(&11));
+    }
+}
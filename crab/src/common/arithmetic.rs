@@ -0,0 +1,158 @@
+use crate::binding::bound_tree::{
+    BoundBinaryOperationKind, BoundExpression, BoundExpressionId, BoundProgram,
+    BoundUnaryOperationKind,
+};
+
+use super::types::{Object, Type};
+
+/// Why an operation on numbers has no result. Numbers are 32-bit signed integers on every
+/// backend, and leaving that range is an error rather than wrapping around, as is dividing by
+/// zero. Division truncates towards zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithmeticError {
+    Overflow,
+    DivisionByZero,
+}
+
+impl ArithmeticError {
+    /// What a program is told when `operator` fails this way.
+    pub fn message(self, operator: &str) -> String {
+        match self {
+            ArithmeticError::Overflow => format!("Arithmetic overflow in '{operator}' operation"),
+            ArithmeticError::DivisionByZero => "Division by zero".to_string(),
+        }
+    }
+}
+
+pub fn negate(right: i32) -> Result<i32, ArithmeticError> {
+    right.checked_neg().ok_or(ArithmeticError::Overflow)
+}
+
+pub fn add(left: i32, right: i32) -> Result<i32, ArithmeticError> {
+    left.checked_add(right).ok_or(ArithmeticError::Overflow)
+}
+
+pub fn subtract(left: i32, right: i32) -> Result<i32, ArithmeticError> {
+    left.checked_sub(right).ok_or(ArithmeticError::Overflow)
+}
+
+pub fn multiply(left: i32, right: i32) -> Result<i32, ArithmeticError> {
+    left.checked_mul(right).ok_or(ArithmeticError::Overflow)
+}
+
+/// Dividing the smallest number by -1 overflows, since its negation is one too large.
+pub fn divide(left: i32, right: i32) -> Result<i32, ArithmeticError> {
+    if right == 0 {
+        return Err(ArithmeticError::DivisionByZero);
+    }
+    left.checked_div(right).ok_or(ArithmeticError::Overflow)
+}
+
+/// An operation on numbers that can fail. Backends whose target language wraps around, traps or
+/// leaves the result undefined instead compute these with checks of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckedOperation {
+    Negation,
+    Addition,
+    Subtraction,
+    Multiplication,
+    Division,
+}
+
+impl CheckedOperation {
+    /// The operation that computes an expression, if it can fail. Negating a literal that is not
+    /// below zero cannot overflow, so `-5` needs no check. Optimized code can hold any number as
+    /// a literal, `i32::MIN` among them, so other literals are negated with a check.
+    pub fn of(program: &BoundProgram, expression: BoundExpressionId) -> Option<Self> {
+        match program.expression(expression) {
+            BoundExpression::Unary(unary) => match unary.operator.operation_kind {
+                BoundUnaryOperationKind::Negation
+                    if !matches!(
+                        program.expression(unary.right),
+                        BoundExpression::Literal(literal)
+                            if matches!(literal.value, Object::Number(value) if value >= 0)
+                    ) =>
+                {
+                    Some(CheckedOperation::Negation)
+                }
+                _ => None,
+            },
+            BoundExpression::Binary(binary) => match binary.operator.operation_kind {
+                BoundBinaryOperationKind::Addition
+                    if program.expression(expression).get_type() == Type::Number =>
+                {
+                    Some(CheckedOperation::Addition)
+                }
+                BoundBinaryOperationKind::Subtraction => Some(CheckedOperation::Subtraction),
+                BoundBinaryOperationKind::Multiplication => Some(CheckedOperation::Multiplication),
+                BoundBinaryOperationKind::Division => Some(CheckedOperation::Division),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::CheckedOperation;
+    use crate::{
+        binding::{
+            bindings::Bindings,
+            bound_tree::{BoundProgram, BoundStatement},
+        },
+        common::intern::Interner,
+        compilation::Compilation,
+    };
+
+    /// The checked operation of every printed expression of a program.
+    fn printed(source: &str, optimize: bool) -> Vec<Option<CheckedOperation>> {
+        let mut compilation = Compilation::compile(
+            source,
+            Rc::new(RefCell::new(Bindings::default())),
+            Rc::new(RefCell::new(Interner::default())),
+        );
+        if optimize {
+            compilation.optimize();
+        }
+        let program: &BoundProgram = &compilation.bound_program;
+        program
+            .root()
+            .iter()
+            .filter_map(|&statement| match program.statement(statement) {
+                BoundStatement::Print(print) => {
+                    Some(CheckedOperation::of(program, print.expression))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn operations_on_numbers_that_can_fail_are_checked() {
+        let source = "var a = 1\nprint -a\nprint a + 1\nprint a - 1\nprint a * 2\nprint a / 2\nprint a < 2\nprint \"a\" + \"b\"\n";
+        assert_eq!(
+            printed(source, false),
+            [
+                Some(CheckedOperation::Negation),
+                Some(CheckedOperation::Addition),
+                Some(CheckedOperation::Subtraction),
+                Some(CheckedOperation::Multiplication),
+                Some(CheckedOperation::Division),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn only_literals_below_zero_are_negated_with_a_check() {
+        assert_eq!(printed("print -5\n", false), [None]);
+        assert_eq!(
+            printed("print -(0 - 2147483647 - 1)\n", true),
+            [Some(CheckedOperation::Negation)]
+        );
+    }
+}
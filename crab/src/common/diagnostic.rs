@@ -2,6 +2,7 @@ use crate::syntax::token::TokenKind;
 
 use super::types::Type;
 
+/// A range of byte offsets into the source.
 #[derive(Debug, Clone, Default)]
 pub struct Position {
     pub start: usize,
@@ -21,24 +22,94 @@ impl Position {
     }
 
     pub fn get_line(&self, source: &str) -> usize {
-        let mut line = 1;
-        for char in source.chars() {
-            if char == '\n' {
-                line += 1;
-            }
+        source[..self.start].matches('\n').count() + 1
+    }
+
+    /// The column of the start, counted in bytes from 1.
+    pub fn get_column(&self, source: &str) -> usize {
+        self.start
+            - source[..self.start]
+                .rfind('\n')
+                .map_or(0, |newline| newline + 1)
+            + 1
+    }
+}
+
+pub struct Suggestion {
+    pub position: Position,
+    pub message: String,
+    pub replacement: String,
+}
+
+impl Suggestion {
+    pub(crate) fn replace_name(position: Position, replacement: &str) -> Self {
+        Self {
+            position,
+            message: format!("Did you mean '{replacement}'?"),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    pub(crate) fn replace_keyword(position: Position, replacement: &str) -> Self {
+        Self {
+            position,
+            message: format!("Did you mean the keyword '{replacement}'?"),
+            replacement: replacement.to_string(),
         }
-        line
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+pub struct Note {
+    pub position: Position,
+    pub message: String,
+}
+
 pub struct Diagnostic {
+    pub severity: Severity,
     pub position: Position,
     pub message: String,
+    pub notes: Vec<Note>,
+    pub suggestions: Vec<Suggestion>,
 }
 
 impl Diagnostic {
     fn new(position: Position, message: String) -> Self {
-        Self { position, message }
+        Self {
+            severity: Severity::Error,
+            position,
+            message,
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    fn warning(position: Position, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            position,
+            message,
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    fn with_note(mut self, position: Position, message: &str) -> Self {
+        self.notes.push(Note {
+            position,
+            message: message.to_string(),
+        });
+        self
+    }
+
+    fn with_suggestion(mut self, suggestion: Option<Suggestion>) -> Self {
+        self.suggestions.extend(suggestion);
+        self
     }
 }
 
@@ -53,6 +124,12 @@ impl DiagnosticBag {
         }
     }
 
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
     pub(crate) fn unexpected_character(&mut self, position: Position, char: char) {
         self.diagnostics.push(Diagnostic::new(
             position,
@@ -60,6 +137,25 @@ impl DiagnosticBag {
         ));
     }
 
+    pub(crate) fn unterminated_string(&mut self, position: Position) {
+        self.diagnostics
+            .push(Diagnostic::new(position, "Unterminated string".to_string()));
+    }
+
+    pub(crate) fn number_out_of_range(&mut self, position: Position, lexeme: &str) {
+        self.diagnostics.push(Diagnostic::new(
+            position,
+            format!("Integer literal '{lexeme}' is out of range"),
+        ));
+    }
+
+    pub(crate) fn too_deeply_nested(&mut self, position: Position, limit: usize) {
+        self.diagnostics.push(Diagnostic::new(
+            position,
+            format!("Nesting is deeper than {limit} levels"),
+        ));
+    }
+
     pub(crate) fn unexpected_token(
         &mut self,
         position: Position,
@@ -99,11 +195,31 @@ impl DiagnosticBag {
         ))
     }
 
-    pub(crate) fn undefined_name(&mut self, position: Position, name: String) {
-        self.diagnostics.push(Diagnostic::new(
-            position,
-            format!("Name '{name}' is not defined"),
-        ))
+    pub(crate) fn undefined_name(
+        &mut self,
+        position: Position,
+        name: String,
+        suggestion: Option<Suggestion>,
+    ) {
+        self.diagnostics.push(
+            Diagnostic::new(position, format!("Name '{name}' is not defined"))
+                .with_suggestion(suggestion),
+        )
+    }
+
+    pub(crate) fn redeclared_name(
+        &mut self,
+        position: Position,
+        name: String,
+        previous_position: Position,
+    ) {
+        self.diagnostics.push(
+            Diagnostic::new(
+                position,
+                format!("Name '{name}' is already declared in this scope"),
+            )
+            .with_note(previous_position, "previously declared here"),
+        )
     }
 
     pub(crate) fn invalid_assignment(
@@ -130,4 +246,89 @@ impl DiagnosticBag {
             format!("Expected '{expected_type}' expression, got '{given_type}'"),
         ))
     }
+
+    pub(crate) fn unused_variable(&mut self, position: Position, name: &str) {
+        self.diagnostics.push(Diagnostic::warning(
+            position,
+            format!("Variable '{name}' is never read"),
+        ))
+    }
+
+    pub(crate) fn unused_assignment(&mut self, position: Position, name: &str) {
+        self.diagnostics.push(Diagnostic::warning(
+            position,
+            format!("Value assigned to '{name}' is never read"),
+        ))
+    }
+
+    pub(crate) fn overwritten_assignment(&mut self, position: Position, name: &str) {
+        self.diagnostics.push(Diagnostic::warning(
+            position,
+            format!("Value assigned to '{name}' is overwritten before it is read"),
+        ))
+    }
+
+    pub(crate) fn shadowed_variable(&mut self, position: Position, name: &str) {
+        self.diagnostics.push(Diagnostic::warning(
+            position,
+            format!("Variable '{name}' shadows a variable from an outer scope"),
+        ))
+    }
+
+    pub(crate) fn unreachable_statement(&mut self, position: Position) {
+        self.diagnostics.push(Diagnostic::warning(
+            position,
+            "Statement is unreachable".to_string(),
+        ))
+    }
+
+    pub(crate) fn constant_condition(&mut self, position: Position, value: bool) {
+        self.diagnostics.push(Diagnostic::warning(
+            position,
+            format!("Condition is always '{value}'"),
+        ))
+    }
+
+    pub(crate) fn constant_overflow(&mut self, position: Position) {
+        self.diagnostics.push(Diagnostic::warning(
+            position,
+            "Expression always overflows".to_string(),
+        ))
+    }
+
+    pub(crate) fn constant_division_by_zero(&mut self, position: Position) {
+        self.diagnostics.push(Diagnostic::warning(
+            position,
+            "Expression always divides by zero".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Position;
+
+    #[test]
+    fn lines_are_counted_up_to_the_start() {
+        let source = "var a = 1\nprint a\n\nprint b\n";
+        assert_eq!(Position::new(0, 3).get_line(source), 1);
+        assert_eq!(Position::new(10, 15).get_line(source), 2);
+        assert_eq!(Position::new(25, 26).get_line(source), 4);
+        // Newlines after the start, even inside the position, do not count.
+        assert_eq!(Position::new(0, source.len()).get_line(source), 1);
+    }
+
+    #[test]
+    fn a_position_at_a_newline_is_on_the_line_it_ends() {
+        assert_eq!(Position::new(9, 10).get_line("var a = 1\nprint a"), 1);
+        assert_eq!(Position::new(10, 10).get_line("var a = 1\nprint a"), 2);
+    }
+
+    #[test]
+    fn columns_are_counted_in_bytes_from_the_line_start() {
+        let source = "print 1\n  print \"ü\" + x\n";
+        assert_eq!(Position::new(0, 5).get_column(source), 1);
+        assert_eq!(Position::new(10, 15).get_column(source), 3);
+        assert_eq!(Position::new(23, 24).get_column(source), 16);
+    }
 }
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, rc::Rc};
+
+/// A handle to a string stored in an `Interner`. Two symbols from the same interner are equal
+/// exactly when their strings are, so names can be compared and hashed as integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+/// Stores every identifier and string literal of a session once. A REPL keeps one interner for
+/// all its chunks, so symbols stay valid as long as the bindings that refer to them.
+#[derive(Debug, Default)]
+pub struct Interner {
+    symbols: HashMap<Rc<str>, Symbol>,
+    strings: Vec<Rc<str>>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(string) {
+            return *symbol;
+        }
+        let symbol = Symbol(self.strings.len() as u32);
+        let string: Rc<str> = Rc::from(string);
+        self.strings.push(Rc::clone(&string));
+        self.symbols.insert(string, symbol);
+        symbol
+    }
+
+    /// The symbol of a string, if it was ever interned.
+    pub fn get(&self, string: &str) -> Option<Symbol> {
+        self.symbols.get(string).copied()
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.strings[symbol.0 as usize]
+    }
+
+    /// The interned string itself, for values that need to own it.
+    pub fn shared(&self, symbol: Symbol) -> Rc<str> {
+        Rc::clone(&self.strings[symbol.0 as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::Interner;
+
+    #[test]
+    fn equal_strings_get_the_same_symbol() {
+        let mut interner = Interner::default();
+        let first = interner.intern("count");
+        let other = interner.intern("total");
+        assert_eq!(interner.intern("count"), first);
+        assert_ne!(first, other);
+        assert_eq!(interner.resolve(first), "count");
+        assert_eq!(interner.resolve(other), "total");
+    }
+
+    #[test]
+    fn get_does_not_intern() {
+        let mut interner = Interner::default();
+        assert_eq!(interner.get("count"), None);
+        let symbol = interner.intern("count");
+        assert_eq!(interner.get("count"), Some(symbol));
+        assert_eq!(interner.get("total"), None);
+    }
+
+    #[test]
+    fn shared_strings_are_not_copied() {
+        let mut interner = Interner::default();
+        let symbol = interner.intern("a string");
+        assert!(Rc::ptr_eq(
+            &interner.shared(symbol),
+            &interner.shared(symbol)
+        ));
+    }
+}
@@ -1,2 +1,7 @@
+pub mod arena;
+pub mod arithmetic;
 pub mod diagnostic;
+pub mod intern;
+pub mod report;
+pub(crate) mod spelling;
 pub mod types;
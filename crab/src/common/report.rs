@@ -0,0 +1,81 @@
+use colored::Colorize;
+
+use super::diagnostic::{Diagnostic, Position, Severity, Suggestion};
+
+/// Prints diagnostics in the order of their positions in the source, so that warnings found by
+/// later passes are not listed after every error. With a path, they are introduced by the file
+/// they are about.
+pub fn report_diagnostics(path: Option<&str>, source: &str, diagnostics: &[Diagnostic]) {
+    if let (Some(path), false) = (path, diagnostics.is_empty()) {
+        eprintln!("{}", format!("In {path}:").truecolor(155, 155, 155));
+    }
+    let mut diagnostics: Vec<&Diagnostic> = diagnostics.iter().collect();
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.position.start, diagnostic.position.end));
+    for diagnostic in diagnostics {
+        let (label, color) = match diagnostic.severity {
+            Severity::Error => ("Error", (255, 0, 0)),
+            Severity::Warning => ("Warning", (255, 165, 0)),
+        };
+        report(
+            source,
+            &diagnostic.position,
+            label,
+            color,
+            &diagnostic.message,
+        );
+        for note in &diagnostic.notes {
+            let line = note.position.get_line(source);
+            eprintln!(
+                "{}",
+                format!("Note: {} (line: {line}).", note.message).truecolor(155, 155, 155)
+            );
+            eprintln!("\t{}", &source[note.position.start..note.position.end]);
+        }
+        report_suggestions(&diagnostic.suggestions);
+    }
+}
+
+/// Prints what a diagnostic suggests, one `Help:` line each.
+pub fn report_suggestions(suggestions: &[Suggestion]) {
+    for suggestion in suggestions {
+        eprintln!(
+            "{}",
+            format!("Help: {}", suggestion.message).truecolor(0, 255, 255)
+        );
+    }
+}
+
+/// Prints an error that stopped a program, in the same form as a diagnostic.
+pub fn report_runtime_error(source: &str, position: &Position, message: &str) {
+    report(source, position, "Runtime error", (255, 0, 0), message);
+}
+
+fn report(source: &str, position: &Position, label: &str, color: (u8, u8, u8), message: &str) {
+    let line = position.get_line(source);
+    eprintln!(
+        "{}",
+        format!("[{} in line: {line}]", label.to_lowercase()).truecolor(255, 255, 0)
+    );
+    eprintln!(
+        "{}",
+        format!("{label}: {message}.").truecolor(color.0, color.1, color.2)
+    );
+    // A position can span several lines, like a block does. Each line is underlined on its
+    // own, from its first character, keeping the indentation so that tabs line up.
+    let snippet = &source[position.start..position.end];
+    let lines: Vec<&str> = snippet.lines().collect();
+    for (index, line) in lines.iter().enumerate() {
+        let text = line.trim_end();
+        let indentation = &text[..text.len() - text.trim_start().len()];
+        let carets = "^".repeat(text.trim_start().chars().count());
+        eprintln!("\t{text}");
+        eprint!("\t{indentation}{}", carets.truecolor(255, 255, 0));
+        if index + 1 < lines.len() {
+            eprintln!();
+        }
+    }
+    if lines.is_empty() {
+        eprint!("\t");
+    }
+    eprintln!("{}", " --- here".truecolor(255, 255, 0));
+}
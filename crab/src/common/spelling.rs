@@ -0,0 +1,68 @@
+pub(crate) fn edit_distance(left: &str, right: &str) -> usize {
+    let left: Vec<char> = left.chars().collect();
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, left_char) in left.iter().enumerate() {
+        let mut current = vec![i + 1; right.len() + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left_char != right_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+pub(crate) fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let name_length = name.chars().count();
+    let threshold = (name_length / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold && *distance < name_length)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closest_match, edit_distance};
+
+    #[test]
+    fn edit_distance_counts_insertions_deletions_and_substitutions() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("count", "count"), 0);
+        assert_eq!(edit_distance("count", "counts"), 1);
+        assert_eq!(edit_distance("count", "cont"), 1);
+        assert_eq!(edit_distance("count", "mount"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn edit_distance_counts_characters_not_bytes() {
+        assert_eq!(edit_distance("über", "uber"), 1);
+    }
+
+    #[test]
+    fn the_closest_candidate_within_the_threshold_is_suggested() {
+        let candidates = ["counter", "total", "count"];
+        assert_eq!(closest_match("coun", candidates), Some("count"));
+        assert_eq!(closest_match("totl", candidates), Some("total"));
+    }
+
+    #[test]
+    fn distant_or_identical_candidates_are_not_suggested() {
+        assert_eq!(closest_match("count", ["count"]), None);
+        assert_eq!(closest_match("count", ["total"]), None);
+        // A single character is never suggested for another one.
+        assert_eq!(closest_match("x", ["y"]), None);
+        assert_eq!(closest_match("x", []), None);
+        // A swap costs two edits, more than a short name allows.
+        assert_eq!(closest_match("coutn", ["count"]), None);
+    }
+}
@@ -1,4 +1,7 @@
-use std::fmt::{Display, Formatter, Result};
+use std::{
+    fmt::{Display, Formatter, Result},
+    rc::Rc,
+};
 
 type Number = i32;
 type Boolean = bool;
@@ -38,7 +41,7 @@ pub enum Object {
     Unit,
     Number(Number),
     Boolean(Boolean),
-    String(String),
+    String(Rc<str>),
 }
 
 impl Object {
@@ -65,9 +68,9 @@ impl Object {
         }
     }
 
-    pub fn as_string(&self) -> String {
+    pub fn as_string(&self) -> Rc<str> {
         match self {
-            Object::String(s) => s.clone(),
+            Object::String(s) => Rc::clone(s),
             o => panic!("Can not convert {o} to {}", Type::String),
         }
     }
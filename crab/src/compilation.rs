@@ -1,32 +1,153 @@
 use std::{cell::RefCell, rc::Rc};
 
 use crate::{
-    binding::{binder::Binder, bindings::Bindings, bound_tree::BoundStatement},
-    common::diagnostic::DiagnosticBag,
-    syntax::{lexer::Lexer, parser::Parser, syntax_tree::Statement},
+    analysis::{reachability::ReachabilityAnalyzer, warnings::WarningAnalyzer},
+    binding::{
+        binder::Binder, bindings::Bindings, bound_tree::BoundProgram, semantic_model::SemanticModel,
+    },
+    common::{diagnostic::DiagnosticBag, intern::Interner},
+    ir::{function::Function, lowering::Lowerer, passes},
+    optimization::optimizer::Optimizer,
+    syntax::{lexer::Lexer, parser::Parser, syntax_tree::SyntaxTree},
 };
 
-pub struct Compilation {
+/// How deeply statements and expressions may nest unless the options say otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// The stack each level of nesting may take up, with room to spare, in the pass or engine that
+/// needs the most.
+const STACK_PER_LEVEL: usize = 8 * 1024;
+
+/// How a program is compiled.
+#[derive(Clone, Copy)]
+pub struct CompileOptions {
+    /// Whether the program is one chunk of an interactive session.
+    pub interactive: bool,
+    /// How deeply statements and expressions may nest. Every pass after the parser walks the
+    /// tree recursively, so a deeper program is reported instead of overflowing the stack of
+    /// one of them. A higher limit needs a stack that is larger in proportion.
+    pub max_depth: usize,
+}
+
+impl CompileOptions {
+    /// A stack on which a program nested up to `max_depth` can be compiled and run, at least
+    /// the 8 MiB a main thread usually gets.
+    pub fn stack_size(&self) -> usize {
+        self.max_depth
+            .saturating_mul(STACK_PER_LEVEL)
+            .max(DEFAULT_MAX_DEPTH * STACK_PER_LEVEL)
+    }
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            interactive: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+pub struct Compilation<'a> {
     pub diagnostic_bag: Rc<RefCell<DiagnosticBag>>,
     pub bindings: Rc<RefCell<Bindings>>,
-    pub unbound_program: Vec<Statement>,
-    pub bound_program: Vec<BoundStatement>,
+    pub interner: Rc<RefCell<Interner>>,
+    pub unbound_program: SyntaxTree<'a>,
+    pub bound_program: BoundProgram,
+    pub semantic_model: SemanticModel,
+    interactive: bool,
 }
 
-impl Compilation {
-    pub fn compile(source: &str, bindings: Rc<RefCell<Bindings>>) -> Self {
+impl<'a> Compilation<'a> {
+    pub fn compile(
+        source: &'a str,
+        bindings: Rc<RefCell<Bindings>>,
+        interner: Rc<RefCell<Interner>>,
+    ) -> Self {
+        Self::compile_with_options(source, bindings, interner, CompileOptions::default())
+    }
+
+    /// Compiles one chunk of an interactive session, where top-level variables stay visible to
+    /// the chunks that follow, so they are never reported as unused and may be redeclared.
+    pub fn compile_interactive(
+        source: &'a str,
+        bindings: Rc<RefCell<Bindings>>,
+        interner: Rc<RefCell<Interner>>,
+    ) -> Self {
+        let options = CompileOptions {
+            interactive: true,
+            ..CompileOptions::default()
+        };
+        Self::compile_with_options(source, bindings, interner, options)
+    }
+
+    pub fn compile_with_options(
+        source: &'a str,
+        bindings: Rc<RefCell<Bindings>>,
+        interner: Rc<RefCell<Interner>>,
+        options: CompileOptions,
+    ) -> Self {
+        let interactive = options.interactive;
         let diagnostic_bag = Rc::new(RefCell::new(DiagnosticBag::new()));
-        let mut lexer = Lexer::new(source, Rc::clone(&diagnostic_bag));
-        let tokens = lexer.lex();
-        let mut parser = Parser::new(tokens, Rc::clone(&diagnostic_bag));
+        let lexer = Lexer::new(source, Rc::clone(&diagnostic_bag));
+        let parser = Parser::new(
+            lexer,
+            Rc::clone(&interner),
+            Rc::clone(&diagnostic_bag),
+            options.max_depth,
+        );
         let unbound_program = parser.parse();
-        let mut binder = Binder::new(Rc::clone(&bindings), Rc::clone(&diagnostic_bag));
-        let bound_program = binder.bind(unbound_program.clone());
+        let (bound_program, semantic_model) = Binder::new(
+            &unbound_program,
+            Rc::clone(&bindings),
+            Rc::clone(&interner),
+            Rc::clone(&diagnostic_bag),
+            interactive,
+        )
+        .bind();
+        if !diagnostic_bag.borrow().has_errors() {
+            WarningAnalyzer::new(
+                &mut diagnostic_bag.borrow_mut(),
+                &bound_program,
+                &interner.borrow(),
+                interactive,
+            )
+            .analyze();
+            ReachabilityAnalyzer::new(&mut diagnostic_bag.borrow_mut(), &bound_program).analyze();
+        }
         Self {
             diagnostic_bag: Rc::clone(&diagnostic_bag),
             bindings,
+            interner,
             unbound_program,
             bound_program,
+            semantic_model,
+            interactive,
+        }
+    }
+
+    /// Replaces the bound program with an optimized one. Operations that always fail at runtime
+    /// are reported as warnings; a program with errors is left alone.
+    pub fn optimize(&mut self) {
+        if self.diagnostic_bag.borrow().has_errors() {
+            return;
+        }
+        self.bound_program =
+            Optimizer::new(&mut self.diagnostic_bag.borrow_mut(), &self.bound_program).optimize();
+    }
+
+    /// Lowers the bound program to the three-address IR, running its passes when `optimize`
+    /// is set. The program must not have errors.
+    pub fn lower(&self, optimize: bool) -> Function {
+        let mut function = Lowerer::new(
+            &self.bound_program,
+            &self.interner.borrow(),
+            self.interactive,
+        )
+        .lower();
+        if optimize {
+            passes::optimize(&mut function);
         }
+        function
     }
 }
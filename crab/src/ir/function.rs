@@ -0,0 +1,321 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::{
+    binding::{
+        bound_tree::{BoundBinaryOperationKind, BoundUnaryOperationKind},
+        symbol::VariableSymbol,
+    },
+    common::{diagnostic::Position, types::Object},
+};
+
+pub type BlockId = usize;
+
+/// A value computed by one instruction. Every temporary is assigned exactly once, by an
+/// instruction that runs before any of its uses, so a temporary never changes its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Temp(pub u32);
+
+impl Display for Temp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "t{}", self.0)
+    }
+}
+
+/// A storage location that may be written any number of times: a variable of the program, or a
+/// hidden one the lowering needs, like the counter of a `for` loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Variable(pub u32);
+
+#[derive(Debug, Clone)]
+pub struct VariableInfo {
+    /// A name that is unique within the function, used by the textual dump.
+    pub label: String,
+    /// The source variable, or `None` for a hidden one.
+    pub symbol: Option<VariableSymbol>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Constant(Object),
+    Temp(Temp),
+}
+
+impl Operand {
+    pub fn as_constant(&self) -> Option<&Object> {
+        match self {
+            Operand::Constant(value) => Some(value),
+            Operand::Temp(_) => None,
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Operand::Constant(Object::String(value)) => write!(f, "{value:?}"),
+            Operand::Constant(value) => write!(f, "{value}"),
+            Operand::Temp(temp) => write!(f, "{temp}"),
+        }
+    }
+}
+
+/// A three-address instruction. Only `Unary` and `Binary` can fail at runtime, so they carry
+/// the position to report.
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    Copy {
+        destination: Temp,
+        source: Operand,
+    },
+    Unary {
+        destination: Temp,
+        operation: BoundUnaryOperationKind,
+        operand: Operand,
+        position: Position,
+    },
+    Binary {
+        destination: Temp,
+        operation: BoundBinaryOperationKind,
+        left: Operand,
+        right: Operand,
+        position: Position,
+        /// Where a division by zero is reported.
+        right_position: Position,
+    },
+    Load {
+        destination: Temp,
+        variable: Variable,
+    },
+    Store {
+        variable: Variable,
+        value: Operand,
+    },
+    Print {
+        value: Operand,
+    },
+}
+
+impl Instruction {
+    pub fn destination(&self) -> Option<Temp> {
+        match self {
+            Instruction::Copy { destination, .. }
+            | Instruction::Unary { destination, .. }
+            | Instruction::Binary { destination, .. }
+            | Instruction::Load { destination, .. } => Some(*destination),
+            Instruction::Store { .. } | Instruction::Print { .. } => None,
+        }
+    }
+
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Instruction::Copy { source, .. } => vec![source],
+            Instruction::Unary { operand, .. } => vec![operand],
+            Instruction::Binary { left, right, .. } => vec![left, right],
+            Instruction::Load { .. } => vec![],
+            Instruction::Store { value, .. } | Instruction::Print { value } => vec![value],
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Instruction::Copy { source, .. } => vec![source],
+            Instruction::Unary { operand, .. } => vec![operand],
+            Instruction::Binary { left, right, .. } => vec![left, right],
+            Instruction::Load { .. } => vec![],
+            Instruction::Store { value, .. } | Instruction::Print { value } => vec![value],
+        }
+    }
+
+    /// Whether running the instruction can fail. Arithmetic can overflow or divide by zero;
+    /// an addition may also be a concatenation, which can not, but the IR does not know.
+    pub fn may_fail(&self) -> bool {
+        match self {
+            Instruction::Unary { operation, .. } => {
+                matches!(operation, BoundUnaryOperationKind::Negation)
+            }
+            Instruction::Binary { operation, .. } => matches!(
+                operation,
+                BoundBinaryOperationKind::Addition
+                    | BoundBinaryOperationKind::Subtraction
+                    | BoundBinaryOperationKind::Multiplication
+                    | BoundBinaryOperationKind::Division
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether the instruction does anything besides computing its destination.
+    pub fn has_effects(&self) -> bool {
+        matches!(self, Instruction::Store { .. } | Instruction::Print { .. }) || self.may_fail()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Terminator {
+    Jump(BlockId),
+    Branch {
+        condition: Operand,
+        when_true: BlockId,
+        when_false: BlockId,
+    },
+    /// Ends the program with the value of its last statement.
+    Return(Operand),
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch {
+                when_true,
+                when_false,
+                ..
+            } => vec![*when_true, *when_false],
+            Terminator::Return(_) => vec![],
+        }
+    }
+
+    pub fn successors_mut(&mut self) -> Vec<&mut BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch {
+                when_true,
+                when_false,
+                ..
+            } => vec![when_true, when_false],
+            Terminator::Return(_) => vec![],
+        }
+    }
+
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Terminator::Jump(_) => vec![],
+            Terminator::Branch { condition, .. } => vec![condition],
+            Terminator::Return(value) => vec![value],
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Terminator::Jump(_) => vec![],
+            Terminator::Branch { condition, .. } => vec![condition],
+            Terminator::Return(value) => vec![value],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub instructions: Vec<Instruction>,
+    pub terminator: Terminator,
+}
+
+/// A whole program lowered to basic blocks. Execution starts at block 0.
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub blocks: Vec<Block>,
+    pub variables: Vec<VariableInfo>,
+    /// Whether the top-level variables outlive the function, as they do in an interactive
+    /// session, so their last values must be stored.
+    pub exports_globals: bool,
+    pub(crate) temps: u32,
+}
+
+impl Function {
+    pub const ENTRY: BlockId = 0;
+
+    pub fn variable(&self, variable: Variable) -> &VariableInfo {
+        &self.variables[variable.0 as usize]
+    }
+
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut predecessors = vec![vec![]; self.blocks.len()];
+        for (id, block) in self.blocks.iter().enumerate() {
+            for successor in block.terminator.successors() {
+                predecessors[successor].push(id);
+            }
+        }
+        predecessors
+    }
+
+    /// The blocks reachable from the entry, in reverse postorder, so every block comes after
+    /// its predecessors except along loop back edges.
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        let mut visited = vec![false; self.blocks.len()];
+        let mut order = vec![];
+        let mut stack = vec![(Self::ENTRY, 0)];
+        visited[Self::ENTRY] = true;
+        while let Some((block, next)) = stack.pop() {
+            let successors = self.blocks[block].terminator.successors();
+            if let Some(&successor) = successors.get(next) {
+                stack.push((block, next + 1));
+                if !visited[successor] {
+                    visited[successor] = true;
+                    stack.push((successor, 0));
+                }
+            } else {
+                order.push(block);
+            }
+        }
+        order.reverse();
+        order
+    }
+
+    /// The number of temporaries, which are numbered from zero.
+    pub fn temps(&self) -> u32 {
+        self.temps
+    }
+
+    pub(crate) fn new_temp(&mut self) -> Temp {
+        self.temps += 1;
+        Temp(self.temps - 1)
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for (id, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "bb{id}:")?;
+            for instruction in &block.instructions {
+                write!(f, "    ")?;
+                match instruction {
+                    Instruction::Copy {
+                        destination,
+                        source,
+                    } => writeln!(f, "{destination} = {source}")?,
+                    Instruction::Unary {
+                        destination,
+                        operation,
+                        operand,
+                        ..
+                    } => writeln!(f, "{destination} = {operation}{operand}")?,
+                    Instruction::Binary {
+                        destination,
+                        operation,
+                        left,
+                        right,
+                        ..
+                    } => writeln!(f, "{destination} = {left} {operation} {right}")?,
+                    Instruction::Load {
+                        destination,
+                        variable,
+                    } => writeln!(f, "{destination} = load {}", self.variable(*variable).label)?,
+                    Instruction::Store { variable, value } => {
+                        writeln!(f, "store {}, {value}", self.variable(*variable).label)?
+                    }
+                    Instruction::Print { value } => writeln!(f, "print {value}")?,
+                }
+            }
+            match &block.terminator {
+                Terminator::Jump(target) => writeln!(f, "    jump bb{target}")?,
+                Terminator::Branch {
+                    condition,
+                    when_true,
+                    when_false,
+                } => writeln!(f, "    branch {condition}, bb{when_true}, bb{when_false}")?,
+                Terminator::Return(value) => writeln!(f, "    return {value}")?,
+            }
+        }
+        Ok(())
+    }
+}
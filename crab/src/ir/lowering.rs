@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    binding::{
+        bound_tree::{
+            BoundBinaryOperationKind, BoundExpression, BoundExpressionId, BoundForStatement,
+            BoundProgram, BoundStatement, BoundStatementId,
+        },
+        symbol::VariableSymbol,
+    },
+    common::{intern::Interner, types::Object},
+};
+
+use super::function::{
+    Block, BlockId, Function, Instruction, Operand, Terminator, Variable, VariableInfo,
+};
+
+/// Lowers a bound program to a `Function`. Scopes disappear: every variable symbol gets a
+/// variable of its own, so shadowed names can not clash.
+pub(crate) struct Lowerer<'a> {
+    program: &'a BoundProgram,
+    interner: &'a Interner,
+    function: Function,
+    variables: HashMap<usize, Variable>,
+    labels: HashSet<String>,
+    current: BlockId,
+}
+
+impl<'a> Lowerer<'a> {
+    pub(crate) fn new(
+        program: &'a BoundProgram,
+        interner: &'a Interner,
+        interactive: bool,
+    ) -> Self {
+        Self {
+            program,
+            interner,
+            function: Function {
+                blocks: vec![],
+                variables: vec![],
+                exports_globals: interactive,
+                temps: 0,
+            },
+            variables: HashMap::new(),
+            labels: HashSet::new(),
+            current: Function::ENTRY,
+        }
+    }
+
+    pub(crate) fn lower(mut self) -> Function {
+        self.current = self.new_block();
+        let mut result = Operand::Constant(Object::Unit);
+        for &statement in self.program.root() {
+            result = self.lower_statement(statement);
+        }
+        self.terminate(Terminator::Return(result));
+        self.function
+    }
+
+    /// Returns the value of the statement, which is only used for the last one.
+    fn lower_statement(&mut self, id: BoundStatementId) -> Operand {
+        match self.program.statement(id) {
+            BoundStatement::Expression(statement) => {
+                return self.lower_expression(statement.expression)
+            }
+            BoundStatement::Print(statement) => {
+                let value = self.lower_expression(statement.expression);
+                self.emit(Instruction::Print { value });
+            }
+            BoundStatement::Var(statement) => {
+                let value = self.lower_expression(statement.expression);
+                let variable = self.variable(&statement.variable);
+                self.emit(Instruction::Store {
+                    variable,
+                    value: value.clone(),
+                });
+                return value;
+            }
+            BoundStatement::Block(statement) => {
+                for &statement in &statement.statements {
+                    self.lower_statement(statement);
+                }
+            }
+            BoundStatement::If(statement) => {
+                let condition = self.lower_expression(statement.condition);
+                let consequence = self.new_block();
+                let alternative = statement.else_clause.map(|_| self.new_block());
+                let join = self.new_block();
+                let alternative = alternative.unwrap_or(join);
+                self.terminate(Terminator::Branch {
+                    condition,
+                    when_true: consequence,
+                    when_false: alternative,
+                });
+                self.current = consequence;
+                self.lower_statement(statement.consequence);
+                self.terminate(Terminator::Jump(join));
+                if let Some(else_clause) = statement.else_clause {
+                    self.current = alternative;
+                    self.lower_statement(else_clause);
+                    self.terminate(Terminator::Jump(join));
+                }
+                self.current = join;
+            }
+            BoundStatement::While(statement) => {
+                let header = self.new_block();
+                let body = self.new_block();
+                let exit = self.new_block();
+                self.terminate(Terminator::Jump(header));
+                self.current = header;
+                let condition = self.lower_expression(statement.condition);
+                self.terminate(Terminator::Branch {
+                    condition,
+                    when_true: body,
+                    when_false: exit,
+                });
+                self.current = body;
+                self.lower_statement(statement.body);
+                self.terminate(Terminator::Jump(header));
+                self.current = exit;
+            }
+            BoundStatement::For(statement) => self.lower_for_statement(statement),
+        }
+        Operand::Constant(Object::Unit)
+    }
+
+    /// Follows the evaluator: the bounds are evaluated once, and after each iteration the
+    /// counter is reloaded from the loop variable, so assigning to the variable in the body
+    /// moves the loop along with it.
+    fn lower_for_statement(&mut self, statement: &BoundForStatement) {
+        let position = statement.get_position();
+        let name = self.interner.resolve(statement.variable.name).to_string();
+        let variable = self.variable(&statement.variable);
+        let counter = self.hidden_variable(&format!("{name}.counter"));
+        let limit = self.hidden_variable(&format!("{name}.limit"));
+
+        let lower_bound = self.lower_expression(statement.lower_bound);
+        let upper_bound = self.lower_expression(statement.upper_bound);
+        self.emit(Instruction::Store {
+            variable: counter,
+            value: lower_bound.clone(),
+        });
+        self.emit(Instruction::Store {
+            variable: limit,
+            value: upper_bound,
+        });
+        self.emit(Instruction::Store {
+            variable,
+            value: lower_bound,
+        });
+
+        let header = self.new_block();
+        let body = self.new_block();
+        let exit = self.new_block();
+        self.terminate(Terminator::Jump(header));
+
+        self.current = header;
+        let current = self.load(counter);
+        let last = self.load(limit);
+        let condition = self.function.new_temp();
+        self.emit(Instruction::Binary {
+            destination: condition,
+            operation: BoundBinaryOperationKind::Lesser,
+            left: current,
+            right: last,
+            position: position.clone(),
+            right_position: position.clone(),
+        });
+        self.terminate(Terminator::Branch {
+            condition: Operand::Temp(condition),
+            when_true: body,
+            when_false: exit,
+        });
+
+        self.current = body;
+        self.lower_statement(statement.body);
+        let reached = self.load(variable);
+        self.emit(Instruction::Store {
+            variable: counter,
+            value: reached.clone(),
+        });
+        let next = self.function.new_temp();
+        self.emit(Instruction::Binary {
+            destination: next,
+            operation: BoundBinaryOperationKind::Addition,
+            left: reached,
+            right: Operand::Constant(Object::Number(1)),
+            position: position.clone(),
+            right_position: position,
+        });
+        self.emit(Instruction::Store {
+            variable,
+            value: Operand::Temp(next),
+        });
+        self.terminate(Terminator::Jump(header));
+        self.current = exit;
+    }
+
+    fn lower_expression(&mut self, id: BoundExpressionId) -> Operand {
+        let expression = self.program.expression(id);
+        let position = expression.get_position();
+        match expression {
+            BoundExpression::Literal(expression) => Operand::Constant(expression.value.clone()),
+            BoundExpression::Variable(expression) => {
+                let variable = self.variable(&expression.variable);
+                self.load(variable)
+            }
+            BoundExpression::Unary(expression) => {
+                let operand = self.lower_expression(expression.right);
+                let destination = self.function.new_temp();
+                self.emit(Instruction::Unary {
+                    destination,
+                    operation: expression.operator.operation_kind.clone(),
+                    operand,
+                    position,
+                });
+                Operand::Temp(destination)
+            }
+            BoundExpression::Binary(expression) => {
+                let left = self.lower_expression(expression.left);
+                let right = self.lower_expression(expression.right);
+                let destination = self.function.new_temp();
+                self.emit(Instruction::Binary {
+                    destination,
+                    operation: expression.operator.operation_kind.clone(),
+                    left,
+                    right,
+                    position,
+                    right_position: self.program.expression(expression.right).get_position(),
+                });
+                Operand::Temp(destination)
+            }
+            BoundExpression::Assignment(expression) => {
+                let value = self.lower_expression(expression.expression);
+                let variable = self.variable(&expression.variable);
+                self.emit(Instruction::Store {
+                    variable,
+                    value: value.clone(),
+                });
+                value
+            }
+        }
+    }
+
+    fn load(&mut self, variable: Variable) -> Operand {
+        let destination = self.function.new_temp();
+        self.emit(Instruction::Load {
+            destination,
+            variable,
+        });
+        Operand::Temp(destination)
+    }
+
+    fn variable(&mut self, symbol: &VariableSymbol) -> Variable {
+        if let Some(variable) = self.variables.get(&symbol.id) {
+            return *variable;
+        }
+        let name = self.interner.resolve(symbol.name).to_string();
+        let variable = self.add_variable(&name, Some(symbol.clone()));
+        self.variables.insert(symbol.id, variable);
+        variable
+    }
+
+    fn hidden_variable(&mut self, name: &str) -> Variable {
+        self.add_variable(name, None)
+    }
+
+    fn add_variable(&mut self, name: &str, symbol: Option<VariableSymbol>) -> Variable {
+        let mut label = name.to_string();
+        let mut suffix = 1;
+        while self.labels.contains(&label) {
+            label = format!("{name}.{suffix}");
+            suffix += 1;
+        }
+        self.labels.insert(label.clone());
+        self.function.variables.push(VariableInfo { label, symbol });
+        Variable(self.function.variables.len() as u32 - 1)
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.function.blocks.push(Block {
+            instructions: vec![],
+            terminator: Terminator::Return(Operand::Constant(Object::Unit)),
+        });
+        self.function.blocks.len() - 1
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.function.blocks[self.current]
+            .instructions
+            .push(instruction);
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        self.function.blocks[self.current].terminator = terminator;
+    }
+}
@@ -0,0 +1,3 @@
+pub mod function;
+pub(crate) mod lowering;
+pub mod passes;
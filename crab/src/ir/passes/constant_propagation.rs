@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use crate::{
+    analysis::constant::{fold_binary, fold_unary},
+    common::types::Object,
+    ir::function::{Function, Instruction, Operand, Temp, Terminator},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Constant(Object),
+    Varying,
+}
+
+impl Value {
+    fn meet(&self, other: &Value) -> Value {
+        if self == other {
+            self.clone()
+        } else {
+            Value::Varying
+        }
+    }
+
+    fn fold(result: Option<Object>) -> Value {
+        result.map_or(Value::Varying, Value::Constant)
+    }
+}
+
+/// The value of every variable at some point of the program, indexed by variable.
+type State = Vec<Value>;
+
+/// What the analysis found: the value of every temporary, and the state on entry to every
+/// reachable block.
+struct Analysis {
+    temps: HashMap<Temp, Value>,
+    entries: Vec<Option<State>>,
+}
+
+impl Analysis {
+    fn operand(&self, operand: &Operand) -> Value {
+        match operand {
+            Operand::Constant(value) => Value::Constant(value.clone()),
+            Operand::Temp(temp) => self.temps.get(temp).cloned().unwrap_or(Value::Varying),
+        }
+    }
+
+    fn evaluate(&self, instruction: &Instruction, state: &State) -> Value {
+        match instruction {
+            Instruction::Copy { source, .. } => self.operand(source),
+            Instruction::Unary {
+                operation, operand, ..
+            } => match self.operand(operand) {
+                Value::Constant(value) => Value::fold(fold_unary(operation, value).ok()),
+                Value::Varying => Value::Varying,
+            },
+            Instruction::Binary {
+                operation,
+                left,
+                right,
+                ..
+            } => match (self.operand(left), self.operand(right)) {
+                (Value::Constant(left), Value::Constant(right)) => {
+                    Value::fold(fold_binary(operation, left, right).ok())
+                }
+                _ => Value::Varying,
+            },
+            Instruction::Load { variable, .. } => state[variable.0 as usize].clone(),
+            Instruction::Store { .. } | Instruction::Print { .. } => Value::Varying,
+        }
+    }
+
+    fn transfer(&self, instruction: &Instruction, state: &mut State) {
+        if let Instruction::Store { variable, value } = instruction {
+            state[variable.0 as usize] = self.operand(value);
+        }
+    }
+}
+
+/// Finds the variables and temporaries that hold the same constant every time they are used,
+/// replaces them with it, folds the operations whose operands are all constants, and turns
+/// branches on a constant into jumps.
+pub(super) fn run(function: &mut Function) -> bool {
+    let analysis = analyze(function);
+    let mut changed = false;
+    for (id, block) in function.blocks.iter_mut().enumerate() {
+        let Some(mut state) = analysis.entries[id].clone() else {
+            continue;
+        };
+        for instruction in &mut block.instructions {
+            for operand in instruction.operands_mut() {
+                changed |= replace(operand, &analysis);
+            }
+            if let (Some(destination), Value::Constant(value)) = (
+                instruction.destination(),
+                analysis.evaluate(instruction, &state),
+            ) {
+                if !matches!(instruction, Instruction::Copy { .. }) {
+                    *instruction = Instruction::Copy {
+                        destination,
+                        source: Operand::Constant(value),
+                    };
+                    changed = true;
+                }
+            }
+            analysis.transfer(instruction, &mut state);
+        }
+        for operand in block.terminator.operands_mut() {
+            changed |= replace(operand, &analysis);
+        }
+        if let Terminator::Branch {
+            condition: Operand::Constant(Object::Boolean(condition)),
+            when_true,
+            when_false,
+        } = block.terminator
+        {
+            block.terminator = Terminator::Jump(if condition { when_true } else { when_false });
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn replace(operand: &mut Operand, analysis: &Analysis) -> bool {
+    if let Operand::Temp(temp) = operand {
+        if let Some(Value::Constant(value)) = analysis.temps.get(temp) {
+            *operand = Operand::Constant(value.clone());
+            return true;
+        }
+    }
+    false
+}
+
+/// Visits the blocks in reverse postorder until nothing changes. A block is only visited once
+/// one of its predecessors has been, and meets the states its visited predecessors leave, so a
+/// loop header first assumes the values from before the loop and loses them once the body has
+/// been seen to change them. Every variable starts out varying: in an interactive session it
+/// may hold a value from an earlier chunk.
+fn analyze(function: &Function) -> Analysis {
+    let order = function.reverse_postorder();
+    let predecessors = function.predecessors();
+    let mut analysis = Analysis {
+        temps: HashMap::new(),
+        entries: vec![None; function.blocks.len()],
+    };
+    let mut exits: Vec<Option<State>> = vec![None; function.blocks.len()];
+    loop {
+        let mut changed = false;
+        for &block in &order {
+            let entry = if block == Function::ENTRY {
+                Some(vec![Value::Varying; function.variables.len()])
+            } else {
+                predecessors[block]
+                    .iter()
+                    .filter_map(|&predecessor| exits[predecessor].as_ref())
+                    .fold(None, |entry: Option<State>, exit| {
+                        Some(match entry {
+                            Some(entry) => entry
+                                .iter()
+                                .zip(exit)
+                                .map(|(left, right)| left.meet(right))
+                                .collect(),
+                            None => exit.clone(),
+                        })
+                    })
+            };
+            let Some(entry) = entry else {
+                continue;
+            };
+            let mut state = entry.clone();
+            for instruction in &function.blocks[block].instructions {
+                if let Some(destination) = instruction.destination() {
+                    let value = analysis.evaluate(instruction, &state);
+                    let value = match analysis.temps.get(&destination) {
+                        Some(previous) => previous.meet(&value),
+                        None => value,
+                    };
+                    if analysis.temps.get(&destination) != Some(&value) {
+                        analysis.temps.insert(destination, value);
+                        changed = true;
+                    }
+                }
+                analysis.transfer(instruction, &mut state);
+            }
+            analysis.entries[block] = Some(entry);
+            if exits[block].as_ref() != Some(&state) {
+                exits[block] = Some(state);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    analysis
+}
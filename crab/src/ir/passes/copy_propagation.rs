@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::ir::function::{Function, Instruction, Operand, Temp};
+
+/// Forwards values through copies and through variables.
+///
+/// Within a block, loading a variable whose value is already held by an operand, because it
+/// was just stored or loaded, becomes a copy of that operand. Then every use of a copy's
+/// destination is replaced by its source; since temporaries never change, that holds
+/// everywhere, and the copies are left for dead code elimination.
+pub(super) fn run(function: &mut Function) -> bool {
+    let mut changed = false;
+    for block in &mut function.blocks {
+        let mut known = HashMap::new();
+        for instruction in &mut block.instructions {
+            match instruction {
+                Instruction::Store { variable, value } => {
+                    known.insert(*variable, value.clone());
+                }
+                Instruction::Load {
+                    destination,
+                    variable,
+                } => match known.get(variable) {
+                    Some(value) => {
+                        *instruction = Instruction::Copy {
+                            destination: *destination,
+                            source: value.clone(),
+                        };
+                        changed = true;
+                    }
+                    None => {
+                        known.insert(*variable, Operand::Temp(*destination));
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    let mut copies: HashMap<Temp, Operand> = HashMap::new();
+    for block in &function.blocks {
+        for instruction in &block.instructions {
+            if let Instruction::Copy {
+                destination,
+                source,
+            } = instruction
+            {
+                copies.insert(*destination, source.clone());
+            }
+        }
+    }
+    let resolve = |operand: &Operand| {
+        let mut operand = operand.clone();
+        while let Operand::Temp(temp) = operand {
+            match copies.get(&temp) {
+                Some(source) => operand = source.clone(),
+                None => break,
+            }
+        }
+        operand
+    };
+    for block in &mut function.blocks {
+        let operands = block
+            .instructions
+            .iter_mut()
+            .flat_map(|instruction| instruction.operands_mut())
+            .chain(block.terminator.operands_mut());
+        for operand in operands {
+            let resolved = resolve(operand);
+            if resolved != *operand {
+                *operand = resolved;
+                changed = true;
+            }
+        }
+    }
+    changed
+}
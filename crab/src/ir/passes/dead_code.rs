@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+
+use crate::ir::function::{BlockId, Function, Instruction, Operand, Terminator, Variable};
+
+/// Removes what can not affect the program: blocks that are never reached, blocks that only
+/// jump elsewhere, temporaries that are never used, and stores that are never loaded.
+pub(super) fn run(function: &mut Function) -> bool {
+    let mut changed = skip_empty_blocks(function);
+    changed |= remove_unreachable_blocks(function);
+    changed |= remove_unused_temps(function);
+    changed |= remove_dead_stores(function);
+    changed
+}
+
+/// Points every edge to an empty block that only jumps on straight at the block it jumps to.
+fn skip_empty_blocks(function: &mut Function) -> bool {
+    let forward = |function: &Function, mut block: BlockId| {
+        let mut seen = HashSet::new();
+        while block != Function::ENTRY && seen.insert(block) {
+            let target = &function.blocks[block];
+            match target.terminator {
+                Terminator::Jump(next) if target.instructions.is_empty() => block = next,
+                _ => break,
+            }
+        }
+        block
+    };
+    let mut changed = false;
+    for id in 0..function.blocks.len() {
+        let mut terminator = function.blocks[id].terminator.clone();
+        for successor in terminator.successors_mut() {
+            let target = forward(function, *successor);
+            if target != *successor {
+                *successor = target;
+                changed = true;
+            }
+        }
+        if let Terminator::Branch {
+            when_true,
+            when_false,
+            ..
+        } = terminator
+        {
+            if when_true == when_false {
+                terminator = Terminator::Jump(when_true);
+                changed = true;
+            }
+        }
+        function.blocks[id].terminator = terminator;
+    }
+    changed
+}
+
+/// Drops the blocks that can not be reached from the entry and renumbers the rest in the
+/// order they were laid out in.
+fn remove_unreachable_blocks(function: &mut Function) -> bool {
+    let mut reachable = vec![false; function.blocks.len()];
+    for block in function.reverse_postorder() {
+        reachable[block] = true;
+    }
+    if reachable.iter().all(|&reachable| reachable) {
+        return false;
+    }
+    let mut numbers = vec![0; function.blocks.len()];
+    let mut next = 0;
+    for (id, &reachable) in reachable.iter().enumerate() {
+        numbers[id] = next;
+        if reachable {
+            next += 1;
+        }
+    }
+    let blocks = std::mem::take(&mut function.blocks);
+    function.blocks = blocks
+        .into_iter()
+        .zip(&reachable)
+        .filter(|(_, &reachable)| reachable)
+        .map(|(mut block, _)| {
+            for successor in block.terminator.successors_mut() {
+                *successor = numbers[*successor];
+            }
+            block
+        })
+        .collect();
+    true
+}
+
+fn remove_unused_temps(function: &mut Function) -> bool {
+    let mut changed = false;
+    loop {
+        let mut used = HashSet::new();
+        for block in &function.blocks {
+            let operands = block
+                .instructions
+                .iter()
+                .flat_map(|instruction| instruction.operands())
+                .chain(block.terminator.operands());
+            for operand in operands {
+                if let Operand::Temp(temp) = operand {
+                    used.insert(*temp);
+                }
+            }
+        }
+        let mut removed = false;
+        for block in &mut function.blocks {
+            block.instructions.retain(|instruction| {
+                let dead = instruction
+                    .destination()
+                    .is_some_and(|destination| !used.contains(&destination))
+                    && !instruction.has_effects();
+                removed |= dead;
+                !dead
+            });
+        }
+        if !removed {
+            return changed;
+        }
+        changed = true;
+    }
+}
+
+/// Removes the stores whose value no load can see, using the variables live at the end of
+/// each block. When the function exports its globals, they are all live at its end.
+fn remove_dead_stores(function: &mut Function) -> bool {
+    let exported: HashSet<Variable> = function
+        .variables
+        .iter()
+        .enumerate()
+        .filter(|(_, variable)| {
+            function.exports_globals
+                && variable
+                    .symbol
+                    .as_ref()
+                    .is_some_and(|symbol| symbol.depth == 0)
+        })
+        .map(|(index, _)| Variable(index as u32))
+        .collect();
+
+    let mut live_in: Vec<HashSet<Variable>> = vec![HashSet::new(); function.blocks.len()];
+    let live_out =
+        |function: &Function, live_in: &[HashSet<Variable>], block: BlockId| match function.blocks
+            [block]
+            .terminator
+        {
+            Terminator::Return(_) => exported.clone(),
+            ref terminator => terminator
+                .successors()
+                .into_iter()
+                .flat_map(|successor| live_in[successor].iter().copied())
+                .collect(),
+        };
+    loop {
+        let mut changed = false;
+        for block in (0..function.blocks.len()).rev() {
+            let mut live = live_out(function, &live_in, block);
+            for instruction in function.blocks[block].instructions.iter().rev() {
+                match instruction {
+                    Instruction::Load { variable, .. } => {
+                        live.insert(*variable);
+                    }
+                    Instruction::Store { variable, .. } => {
+                        live.remove(variable);
+                    }
+                    _ => {}
+                }
+            }
+            if live != live_in[block] {
+                live_in[block] = live;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut changed = false;
+    for block in 0..function.blocks.len() {
+        let mut live = live_out(function, &live_in, block);
+        let instructions = std::mem::take(&mut function.blocks[block].instructions);
+        let mut kept = Vec::with_capacity(instructions.len());
+        for instruction in instructions.into_iter().rev() {
+            match &instruction {
+                Instruction::Load { variable, .. } => {
+                    live.insert(*variable);
+                }
+                Instruction::Store { variable, .. } if !live.remove(variable) => {
+                    changed = true;
+                    continue;
+                }
+                _ => {}
+            }
+            kept.push(instruction);
+        }
+        kept.reverse();
+        function.blocks[block].instructions = kept;
+    }
+    changed
+}
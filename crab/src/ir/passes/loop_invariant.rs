@@ -0,0 +1,155 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::function::{Block, BlockId, Function, Instruction, Operand, Temp, Terminator};
+
+/// Moves the instructions of a loop that compute the same value on every iteration in front of
+/// the loop. Only instructions that can not fail are moved, since the loop may run no
+/// iterations at all, and a load only when the loop never stores to its variable.
+///
+/// Hoisting may add a preheader, which changes the loops around it, so one loop is handled
+/// per run; the pass manager runs the pass again.
+pub(super) fn run(function: &mut Function) -> bool {
+    loops(function)
+        .into_iter()
+        .any(|(header, body)| hoist(function, header, &body))
+}
+
+fn hoist(function: &mut Function, header: BlockId, body: &HashSet<BlockId>) -> bool {
+    let stored: HashSet<_> = body
+        .iter()
+        .flat_map(|&block| &function.blocks[block].instructions)
+        .filter_map(|instruction| match instruction {
+            Instruction::Store { variable, .. } => Some(*variable),
+            _ => None,
+        })
+        .collect();
+    let mut defined_inside: HashSet<Temp> = body
+        .iter()
+        .flat_map(|&block| &function.blocks[block].instructions)
+        .filter_map(Instruction::destination)
+        .collect();
+
+    let order: Vec<BlockId> = function
+        .reverse_postorder()
+        .into_iter()
+        .filter(|block| body.contains(block))
+        .collect();
+    let mut hoisted = vec![];
+    for &block in &order {
+        let instructions = std::mem::take(&mut function.blocks[block].instructions);
+        let mut kept = Vec::with_capacity(instructions.len());
+        for instruction in instructions {
+            let invariant = !instruction.has_effects()
+                && instruction
+                    .operands()
+                    .into_iter()
+                    .all(|operand| match operand {
+                        Operand::Temp(temp) => !defined_inside.contains(temp),
+                        Operand::Constant(_) => true,
+                    })
+                && match &instruction {
+                    Instruction::Load { variable, .. } => !stored.contains(variable),
+                    _ => true,
+                };
+            if invariant {
+                if let Some(destination) = instruction.destination() {
+                    defined_inside.remove(&destination);
+                }
+                hoisted.push(instruction);
+            } else {
+                kept.push(instruction);
+            }
+        }
+        function.blocks[block].instructions = kept;
+    }
+    if hoisted.is_empty() {
+        return false;
+    }
+    let preheader = preheader(function, header, body);
+    function.blocks[preheader].instructions.extend(hoisted);
+    true
+}
+
+/// The block that runs right before the loop is entered. The one outside predecessor of the
+/// header serves if it always jumps there; otherwise a new block is put in between.
+fn preheader(function: &mut Function, header: BlockId, body: &HashSet<BlockId>) -> BlockId {
+    let outside: Vec<BlockId> = function.predecessors()[header]
+        .iter()
+        .copied()
+        .filter(|predecessor| !body.contains(predecessor))
+        .collect();
+    if let [predecessor] = outside[..] {
+        if let Terminator::Jump(_) = function.blocks[predecessor].terminator {
+            return predecessor;
+        }
+    }
+    function.blocks.push(Block {
+        instructions: vec![],
+        terminator: Terminator::Jump(header),
+    });
+    let preheader = function.blocks.len() - 1;
+    for predecessor in outside {
+        for successor in function.blocks[predecessor].terminator.successors_mut() {
+            if *successor == header {
+                *successor = preheader;
+            }
+        }
+    }
+    preheader
+}
+
+/// The natural loops of the function, by header. A back edge goes from a block to one that
+/// dominates it; its loop is the header and every block that reaches the back edge without
+/// going through the header.
+fn loops(function: &Function) -> Vec<(BlockId, HashSet<BlockId>)> {
+    let dominators = dominators(function);
+    let predecessors = function.predecessors();
+    let mut loops: HashMap<BlockId, HashSet<BlockId>> = HashMap::new();
+    for block in function.reverse_postorder() {
+        for successor in function.blocks[block].terminator.successors() {
+            if !dominators[block].contains(&successor) {
+                continue;
+            }
+            let body = loops
+                .entry(successor)
+                .or_insert_with(|| HashSet::from([successor]));
+            let mut pending = vec![block];
+            while let Some(block) = pending.pop() {
+                if body.insert(block) {
+                    pending.extend(&predecessors[block]);
+                }
+            }
+        }
+    }
+    let mut loops: Vec<_> = loops.into_iter().collect();
+    loops.sort_by_key(|(header, _)| *header);
+    loops
+}
+
+/// For every reachable block, the blocks that every path from the entry to it goes through.
+fn dominators(function: &Function) -> Vec<HashSet<BlockId>> {
+    let order = function.reverse_postorder();
+    let predecessors = function.predecessors();
+    let reachable: HashSet<BlockId> = order.iter().copied().collect();
+    let mut dominators = vec![reachable.clone(); function.blocks.len()];
+    dominators[Function::ENTRY] = HashSet::from([Function::ENTRY]);
+    loop {
+        let mut changed = false;
+        for &block in order.iter().skip(1) {
+            let mut dominated = predecessors[block]
+                .iter()
+                .filter(|predecessor| reachable.contains(predecessor))
+                .map(|&predecessor| dominators[predecessor].clone())
+                .reduce(|left, right| &left & &right)
+                .unwrap_or_default();
+            dominated.insert(block);
+            if dominated != dominators[block] {
+                dominators[block] = dominated;
+                changed = true;
+            }
+        }
+        if !changed {
+            return dominators;
+        }
+    }
+}
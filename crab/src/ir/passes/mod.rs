@@ -0,0 +1,21 @@
+use super::function::Function;
+
+mod constant_propagation;
+mod copy_propagation;
+mod dead_code;
+mod loop_invariant;
+
+/// Runs every pass until none of them changes the function any more. Each pass returns whether
+/// it changed anything, and one pass often enables another: propagating a constant into a
+/// branch makes a block unreachable, removing it turns a join into straight-line code, and so on.
+pub fn optimize(function: &mut Function) {
+    loop {
+        let mut changed = constant_propagation::run(function);
+        changed |= copy_propagation::run(function);
+        changed |= dead_code::run(function);
+        changed |= loop_invariant::run(function);
+        if !changed {
+            break;
+        }
+    }
+}
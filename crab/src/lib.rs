@@ -1,4 +1,8 @@
+pub mod analysis;
+pub mod backend;
 pub mod binding;
 pub mod common;
 pub mod compilation;
+pub mod ir;
+pub mod optimization;
 pub mod syntax;
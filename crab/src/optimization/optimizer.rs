@@ -0,0 +1,296 @@
+use crate::{
+    analysis::constant::{fold_binary, fold_unary, FoldError},
+    binding::bound_tree::{
+        BoundAssignmentExpression, BoundBinaryExpression, BoundBinaryOperationKind,
+        BoundBlockStatement, BoundExpression, BoundExpressionId, BoundExpressionStatement,
+        BoundForStatement, BoundIfStatement, BoundLiteralExpression, BoundPrintStatement,
+        BoundProgram, BoundStatement, BoundStatementId, BoundUnaryExpression,
+        BoundUnaryOperationKind, BoundVarStatement, BoundWhileStatement,
+    },
+    common::{
+        diagnostic::{DiagnosticBag, Position},
+        types::Object,
+    },
+};
+
+/// Rewrites a bound program into a new one that does the same thing with less work: operations
+/// on constants are folded, operations that leave their operand unchanged are dropped, and the
+/// branch of an `if` that a constant condition never takes is pruned.
+///
+/// An operation on constants that would fail at runtime is reported and left in place, so the
+/// program still fails the same way when it gets there.
+pub(crate) struct Optimizer<'a> {
+    diagnostic_bag: &'a mut DiagnosticBag,
+    source: &'a BoundProgram,
+    program: BoundProgram,
+}
+
+impl<'a> Optimizer<'a> {
+    pub(crate) fn new(diagnostic_bag: &'a mut DiagnosticBag, source: &'a BoundProgram) -> Self {
+        Self {
+            diagnostic_bag,
+            source,
+            program: BoundProgram::default(),
+        }
+    }
+
+    pub(crate) fn optimize(mut self) -> BoundProgram {
+        let root = self.source.root();
+        for (index, &id) in root.iter().enumerate() {
+            let statement = match self.optimize_statement(id) {
+                Some(statement) => statement,
+                // The value of the last statement is the result of an interactive chunk, so it
+                // stays, if only as an empty block.
+                None if index == root.len() - 1 => {
+                    self.empty_block(self.source.statement(id).get_position())
+                }
+                None => continue,
+            };
+            self.program.push_root(statement);
+        }
+        self.program
+    }
+
+    /// Returns `None` for a statement that has nothing left to do.
+    fn optimize_statement(&mut self, id: BoundStatementId) -> Option<BoundStatementId> {
+        let statement = match self.source.statement(id) {
+            BoundStatement::Expression(statement) => {
+                let expression = self.optimize_expression(statement.expression);
+                BoundStatement::Expression(BoundExpressionStatement::new(
+                    expression,
+                    statement.get_position(),
+                ))
+            }
+            BoundStatement::Print(statement) => {
+                let expression = self.optimize_expression(statement.expression);
+                BoundStatement::Print(BoundPrintStatement::new(
+                    expression,
+                    statement.get_position(),
+                ))
+            }
+            BoundStatement::Var(statement) => {
+                let expression = self.optimize_expression(statement.expression);
+                BoundStatement::Var(BoundVarStatement::new(
+                    statement.variable.clone(),
+                    expression,
+                    statement.get_position(),
+                ))
+            }
+            BoundStatement::Block(statement) => {
+                let statements = statement
+                    .statements
+                    .iter()
+                    .filter_map(|&statement| self.optimize_statement(statement))
+                    .collect();
+                BoundStatement::Block(BoundBlockStatement::new(
+                    statements,
+                    statement.get_position(),
+                ))
+            }
+            BoundStatement::If(statement) => {
+                let condition = self.optimize_expression(statement.condition);
+                match self.literal(condition) {
+                    Some(Object::Boolean(true)) => {
+                        return self.optimize_nested(statement.consequence);
+                    }
+                    Some(Object::Boolean(false)) => {
+                        return statement
+                            .else_clause
+                            .and_then(|else_clause| self.optimize_nested(else_clause));
+                    }
+                    _ => {}
+                }
+                let consequence = self.optimize_body(statement.consequence);
+                let else_clause = statement
+                    .else_clause
+                    .and_then(|else_clause| self.optimize_scoped(else_clause));
+                BoundStatement::If(BoundIfStatement::new(
+                    condition,
+                    consequence,
+                    else_clause,
+                    statement.get_position(),
+                ))
+            }
+            BoundStatement::While(statement) => {
+                let condition = self.optimize_expression(statement.condition);
+                let body = self.optimize_body(statement.body);
+                BoundStatement::While(BoundWhileStatement::new(
+                    condition,
+                    body,
+                    statement.get_position(),
+                ))
+            }
+            BoundStatement::For(statement) => {
+                let lower_bound = self.optimize_expression(statement.lower_bound);
+                let upper_bound = self.optimize_expression(statement.upper_bound);
+                let body = self.optimize_body(statement.body);
+                BoundStatement::For(BoundForStatement::new(
+                    statement.variable.clone(),
+                    lower_bound,
+                    upper_bound,
+                    body,
+                    statement.get_position(),
+                ))
+            }
+        };
+        Some(self.program.alloc_statement(statement))
+    }
+
+    /// A branch that is taken unconditionally replaces its `if`. It had a scope of its own there,
+    /// so a branch that is not already a block is wrapped in one to keep it.
+    fn optimize_nested(&mut self, id: BoundStatementId) -> Option<BoundStatementId> {
+        let statement = self.optimize_statement(id)?;
+        if let BoundStatement::Block(_) = self.source.statement(id) {
+            return Some(statement);
+        }
+        let position = self.source.statement(id).get_position();
+        Some(
+            self.program
+                .alloc_statement(BoundStatement::Block(BoundBlockStatement::new(
+                    vec![statement],
+                    position,
+                ))),
+        )
+    }
+
+    fn optimize_body(&mut self, id: BoundStatementId) -> BoundStatementId {
+        match self.optimize_scoped(id) {
+            Some(statement) => statement,
+            None => self.empty_block(self.source.statement(id).get_position()),
+        }
+    }
+
+    /// The body of an `if`, `else`, `while` or `for` gets a scope of its own unless it is a
+    /// block, which has one already. A body that only becomes a block here, like a pruned `if`,
+    /// would lose that scope, so it is wrapped in one more block to keep it.
+    fn optimize_scoped(&mut self, id: BoundStatementId) -> Option<BoundStatementId> {
+        let statement = self.optimize_statement(id)?;
+        if let BoundStatement::Block(_) = self.source.statement(id) {
+            return Some(statement);
+        }
+        if let BoundStatement::Block(_) = self.program.statement(statement) {
+            let position = self.source.statement(id).get_position();
+            return Some(self.program.alloc_statement(BoundStatement::Block(
+                BoundBlockStatement::new(vec![statement], position),
+            )));
+        }
+        Some(statement)
+    }
+
+    fn optimize_expression(&mut self, id: BoundExpressionId) -> BoundExpressionId {
+        let expression = self.source.expression(id);
+        let position = expression.get_position();
+        let expression = match expression {
+            BoundExpression::Literal(_) | BoundExpression::Variable(_) => expression.clone(),
+            BoundExpression::Unary(expression) => {
+                let operation = &expression.operator.operation_kind;
+                let right = self.optimize_expression(expression.right);
+                if let Some(value) = self.literal(right) {
+                    if let Some(id) = self.fold(fold_unary(operation, value), &position) {
+                        return id;
+                    }
+                }
+                match (operation, self.program.expression(right)) {
+                    (BoundUnaryOperationKind::Identity, _) => return right,
+                    (
+                        BoundUnaryOperationKind::LogicalNegation,
+                        BoundExpression::Unary(BoundUnaryExpression {
+                            operator, right, ..
+                        }),
+                    ) if matches!(
+                        operator.operation_kind,
+                        BoundUnaryOperationKind::LogicalNegation
+                    ) =>
+                    {
+                        return *right
+                    }
+                    _ => {}
+                }
+                BoundExpression::Unary(BoundUnaryExpression::new(
+                    expression.operator.clone(),
+                    right,
+                    position,
+                ))
+            }
+            BoundExpression::Binary(expression) => {
+                let operation = &expression.operator.operation_kind;
+                let left = self.optimize_expression(expression.left);
+                let right = self.optimize_expression(expression.right);
+                match (self.literal(left), self.literal(right)) {
+                    (Some(l), Some(r)) => {
+                        if let Some(id) = self.fold(fold_binary(operation, l, r), &position) {
+                            return id;
+                        }
+                    }
+                    (Some(l), None) if is_identity(operation, &l) => {
+                        return right;
+                    }
+                    (None, Some(r)) if is_identity(operation, &r) => return left,
+                    _ => {}
+                }
+                BoundExpression::Binary(BoundBinaryExpression::new(
+                    left,
+                    expression.operator.clone(),
+                    right,
+                    position,
+                ))
+            }
+            BoundExpression::Assignment(expression) => {
+                let value = self.optimize_expression(expression.expression);
+                BoundExpression::Assignment(BoundAssignmentExpression::new(
+                    expression.variable.clone(),
+                    value,
+                    position,
+                ))
+            }
+        };
+        self.program.alloc_expression(expression)
+    }
+
+    /// Turns a folded value into a literal, or reports why the operation will fail at runtime.
+    fn fold(
+        &mut self,
+        result: Result<Object, FoldError>,
+        position: &Position,
+    ) -> Option<BoundExpressionId> {
+        match result {
+            Ok(value) => Some(self.program.alloc_expression(BoundExpression::Literal(
+                BoundLiteralExpression::new(value, position.clone()),
+            ))),
+            Err(FoldError::Overflow) => {
+                self.diagnostic_bag.constant_overflow(position.clone());
+                None
+            }
+            Err(FoldError::DivisionByZero) => {
+                self.diagnostic_bag
+                    .constant_division_by_zero(position.clone());
+                None
+            }
+            Err(FoldError::Mismatch) => None,
+        }
+    }
+
+    fn literal(&self, id: BoundExpressionId) -> Option<Object> {
+        match self.program.expression(id) {
+            BoundExpression::Literal(expression) => Some(expression.value.clone()),
+            _ => None,
+        }
+    }
+
+    fn empty_block(&mut self, position: Position) -> BoundStatementId {
+        self.program
+            .alloc_statement(BoundStatement::Block(BoundBlockStatement::new(
+                vec![],
+                position,
+            )))
+    }
+}
+
+/// Whether `value` on either side of `operation` leaves the other operand unchanged.
+fn is_identity(operation: &BoundBinaryOperationKind, value: &Object) -> bool {
+    matches!(
+        (operation, value),
+        (BoundBinaryOperationKind::Addition, Object::Number(0))
+            | (BoundBinaryOperationKind::Multiplication, Object::Number(1))
+    )
+}
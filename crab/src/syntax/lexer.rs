@@ -2,263 +2,289 @@ use std::{cell::RefCell, rc::Rc};
 
 use crate::common::diagnostic::{DiagnosticBag, Position};
 
-use super::token::{Token, TokenKind};
+use super::token::{Comment, Token, TokenKind};
 
-pub(crate) struct Lexer {
-    source: Vec<char>,
+/// Splits the source into tokens on demand. Positions are byte offsets into the source and every
+/// lexeme is a slice of it, so lexing allocates nothing. Whitespace and invalid characters are
+/// skipped, comments are collected in `comments`, and the last token yielded is always `Eof`.
+pub(crate) struct Lexer<'a> {
+    source: &'a str,
     current: usize,
+    finished: bool,
+    pub(crate) comments: Vec<Comment<'a>>,
     pub(crate) diagnostic_bag: Rc<RefCell<DiagnosticBag>>,
 }
 
-impl Lexer {
-    pub(crate) fn new(source: &str, diagnostic_bag: Rc<RefCell<DiagnosticBag>>) -> Self {
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(source: &'a str, diagnostic_bag: Rc<RefCell<DiagnosticBag>>) -> Self {
         Self {
-            source: source.chars().collect(),
+            source,
             current: 0,
+            finished: false,
+            comments: vec![],
             diagnostic_bag,
         }
     }
 
-    pub(crate) fn lex(&mut self) -> Vec<Token> {
-        let mut tokens = Vec::new();
-        loop {
-            let token = self.next_token();
-            if token.kind == TokenKind::Eof {
-                tokens.push(token);
-                break;
-            } else if token.kind == TokenKind::Whitespace || token.kind == TokenKind::Invalid {
-                continue;
-            } else {
-                tokens.push(token);
-            }
+    fn next_token(&mut self) -> Token<'a> {
+        let start = self.current;
+        if self.source.len() <= start {
+            let last = self
+                .source
+                .char_indices()
+                .next_back()
+                .map_or(0, |(index, _)| index);
+            return Token::new(TokenKind::Eof, "", Position::new(last, self.source.len()));
         }
-        tokens
+        let kind = match self.next_char() {
+            '+' => TokenKind::Plus,
+            '-' => TokenKind::Minus,
+            '*' => TokenKind::Star,
+            '/' if self.peek() == Some('/') => {
+                self.skip_while(|char| char != '\n');
+                TokenKind::Comment
+            }
+            '/' => TokenKind::Slash,
+
+            '>' => self.either('=', TokenKind::GreaterEqual, TokenKind::Greater),
+            '<' => self.either('=', TokenKind::LesserEqual, TokenKind::Lesser),
+
+            '!' => self.either('=', TokenKind::BangEqual, TokenKind::Bang),
+            '=' => self.either('=', TokenKind::EqualEqual, TokenKind::Equal),
+            '&' => self.either('&', TokenKind::AmpersandAmpersand, TokenKind::Ampersand),
+            '|' => self.either('|', TokenKind::PipePipe, TokenKind::Pipe),
+
+            '(' => TokenKind::OpenParen,
+            ')' => TokenKind::CloseParen,
+            '{' => TokenKind::OpenBrace,
+            '}' => TokenKind::CloseBrace,
+
+            '"' => return self.string(start),
+
+            char if char.is_ascii_whitespace() => {
+                self.skip_while(|char| char.is_ascii_whitespace());
+                TokenKind::Whitespace
+            }
+            char if char.is_ascii_digit() => {
+                self.skip_while(|char| char.is_ascii_digit());
+                TokenKind::Number
+            }
+            char if char.is_ascii_alphabetic() || char == '_' => {
+                self.skip_while(|char| char.is_ascii_alphanumeric() || char == '_');
+                TokenKind::get_lexeme_type(&self.source[start..self.current])
+            }
+            char => {
+                self.diagnostic_bag
+                    .borrow_mut()
+                    .unexpected_character(Position::new(start, self.current), char);
+                TokenKind::Invalid
+            }
+        };
+        Token::new(
+            kind,
+            &self.source[start..self.current],
+            Position::new(start, self.current),
+        )
     }
 
-    fn next_token(&mut self) -> Token {
+    /// The lexeme of a string is its contents; the position also covers the quotes.
+    fn string(&mut self, start: usize) -> Token<'a> {
+        self.skip_while(|char| char != '"');
+        let end = self.current;
         if self.source.len() <= self.current {
-            Token::new(
-                TokenKind::Eof,
-                "\0".to_string(),
-                Position::new(self.current - 1, self.current),
-            )
+            self.diagnostic_bag
+                .borrow_mut()
+                .unterminated_string(Position::new(start, self.current));
         } else {
-            match self.next_char() {
-                '+' => Token::new(
-                    TokenKind::Plus,
-                    "+".to_string(),
-                    Position::new(self.current - 1, self.current),
-                ),
-                '-' => Token::new(
-                    TokenKind::Minus,
-                    "-".to_string(),
-                    Position::new(self.current - 1, self.current),
-                ),
-                '*' => Token::new(
-                    TokenKind::Star,
-                    "*".to_string(),
-                    Position::new(self.current - 1, self.current),
-                ),
-                '/' => Token::new(
-                    TokenKind::Slash,
-                    "/".to_string(),
-                    Position::new(self.current - 1, self.current),
-                ),
+            self.next_char();
+        }
+        Token::new(
+            TokenKind::String,
+            &self.source[start + 1..end],
+            Position::new(start, self.current),
+        )
+    }
 
-                '>' => {
-                    if self.peek(0) == '=' {
-                        self.advance();
-                        Token::new(
-                            TokenKind::GreaterEqual,
-                            ">=".to_string(),
-                            Position::new(self.current - 2, self.current),
-                        )
-                    } else {
-                        Token::new(
-                            TokenKind::Greater,
-                            ">".to_string(),
-                            Position::new(self.current - 1, self.current),
-                        )
-                    }
-                }
-                '<' => {
-                    if self.peek(0) == '=' {
-                        self.advance();
-                        Token::new(
-                            TokenKind::LesserEqual,
-                            "<=".to_string(),
-                            Position::new(self.current - 2, self.current),
-                        )
-                    } else {
-                        Token::new(
-                            TokenKind::Lesser,
-                            "<".to_string(),
-                            Position::new(self.current - 1, self.current),
-                        )
-                    }
-                }
+    fn either(&mut self, next: char, matched: TokenKind, otherwise: TokenKind) -> TokenKind {
+        if self.peek() == Some(next) {
+            self.next_char();
+            matched
+        } else {
+            otherwise
+        }
+    }
 
-                '!' => {
-                    if self.peek(0) == '=' {
-                        self.advance();
-                        Token::new(
-                            TokenKind::BangEqual,
-                            "!=".to_string(),
-                            Position::new(self.current - 2, self.current),
-                        )
-                    } else {
-                        Token::new(
-                            TokenKind::Bang,
-                            "!".to_string(),
-                            Position::new(self.current - 1, self.current),
-                        )
-                    }
-                }
-                '=' => {
-                    if self.peek(0) == '=' {
-                        self.advance();
-                        Token::new(
-                            TokenKind::EqualEqual,
-                            "==".to_string(),
-                            Position::new(self.current - 2, self.current),
-                        )
-                    } else {
-                        Token::new(
-                            TokenKind::Equal,
-                            "=".to_string(),
-                            Position::new(self.current - 1, self.current),
-                        )
-                    }
-                }
-                '&' => {
-                    if self.peek(0) == '&' {
-                        self.advance();
-                        Token::new(
-                            TokenKind::AmpersandAmpersand,
-                            "&&".to_string(),
-                            Position::new(self.current - 2, self.current),
-                        )
-                    } else {
-                        Token::new(
-                            TokenKind::Ampersand,
-                            "&".to_string(),
-                            Position::new(self.current - 1, self.current),
-                        )
-                    }
-                }
-                '|' => {
-                    if self.peek(0) == '|' {
-                        self.advance();
-                        Token::new(
-                            TokenKind::PipePipe,
-                            "||".to_string(),
-                            Position::new(self.current - 2, self.current),
-                        )
-                    } else {
-                        Token::new(
-                            TokenKind::Pipe,
-                            "|".to_string(),
-                            Position::new(self.current - 1, self.current),
-                        )
-                    }
-                }
+    fn skip_while(&mut self, predicate: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&predicate) {
+            self.next_char();
+        }
+    }
 
-                '(' => Token::new(
-                    TokenKind::OpenParen,
-                    "(".to_string(),
-                    Position::new(self.current - 1, self.current),
-                ),
-                ')' => Token::new(
-                    TokenKind::CloseParen,
-                    ")".to_string(),
-                    Position::new(self.current - 1, self.current),
-                ),
-                '{' => Token::new(
-                    TokenKind::OpenBrace,
-                    "{".to_string(),
-                    Position::new(self.current - 1, self.current),
-                ),
-                '}' => Token::new(
-                    TokenKind::CloseBrace,
-                    "}".to_string(),
-                    Position::new(self.current - 1, self.current),
-                ),
+    fn peek(&self) -> Option<char> {
+        self.source[self.current..].chars().next()
+    }
 
-                '"' => {
-                    let start = self.current - 1;
-                    self.advance();
-                    while self.peek(0) != '"' {
-                        self.advance();
-                    }
-                    self.advance();
-                    Token::new(
-                        TokenKind::String,
-                        self.source[start + 1..self.current - 1].iter().collect(),
-                        Position::new(start, self.current),
-                    )
-                }
+    fn next_char(&mut self) -> char {
+        let char = self
+            .peek()
+            .expect("Lexer advanced past the end of the source.");
+        self.current += char.len_utf8();
+        char
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
 
-                char => {
-                    if char.is_ascii_whitespace() {
-                        let start = self.current - 1;
-                        while self.peek(0).is_ascii_whitespace() {
-                            self.advance();
-                        }
-                        Token::new(
-                            TokenKind::Whitespace,
-                            self.source[start..self.current].iter().collect(),
-                            Position::new(start, self.current),
-                        )
-                    } else if char.is_ascii_digit() {
-                        let start = self.current - 1;
-                        while self.peek(0).is_ascii_digit() {
-                            self.advance();
-                        }
-                        Token::new(
-                            TokenKind::Number,
-                            self.source[start..self.current].iter().collect(),
-                            Position::new(start, self.current),
-                        )
-                    } else if char.is_ascii_alphabetic() {
-                        let start = self.current - 1;
-                        while self.peek(0).is_ascii_alphanumeric() {
-                            self.advance();
-                        }
-                        let lexeme = self.source[start..self.current].iter().collect::<String>();
-                        let kind = TokenKind::get_lexeme_type(&lexeme);
-                        Token::new(kind, lexeme, Position::new(start, self.current))
-                    } else {
-                        self.diagnostic_bag.borrow_mut().unexpected_character(
-                            Position::new(self.current - 1, self.current),
-                            char,
-                        );
-                        Token::new(
-                            TokenKind::Invalid,
-                            char.to_string(),
-                            Position::new(self.current - 1, self.current),
-                        )
-                    }
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        loop {
+            let token = self.next_token();
+            match token.kind {
+                TokenKind::Whitespace | TokenKind::Invalid => continue,
+                TokenKind::Comment => {
+                    self.comments.push(Comment {
+                        text: token.lexeme,
+                        position: token.position,
+                    });
+                    continue;
+                }
+                TokenKind::Eof => {
+                    self.finished = true;
+                    return Some(token);
                 }
+                _ => return Some(token),
             }
         }
     }
+}
 
-    fn peek(&self, offset: usize) -> char {
-        let index = offset + self.current;
-        if index < self.source.len() {
-            self.source[index]
-        } else {
-            '\0'
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        common::diagnostic::DiagnosticBag,
+        syntax::token::{Token, TokenKind},
+    };
+
+    use super::Lexer;
+
+    fn lex(source: &str) -> (Vec<Token<'_>>, Vec<String>) {
+        let diagnostic_bag = Rc::new(RefCell::new(DiagnosticBag::new()));
+        let tokens = Lexer::new(source, Rc::clone(&diagnostic_bag)).collect();
+        let messages = diagnostic_bag
+            .borrow()
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect();
+        (tokens, messages)
+    }
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        lex(source).0.into_iter().map(|token| token.kind).collect()
+    }
+
+    #[test]
+    fn operators_take_the_longest_match() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("<= < >= > != ! == = && & || | + - * /"),
+            [
+                LesserEqual,
+                Lesser,
+                GreaterEqual,
+                Greater,
+                BangEqual,
+                Bang,
+                EqualEqual,
+                Equal,
+                AmpersandAmpersand,
+                Ampersand,
+                PipePipe,
+                Pipe,
+                Plus,
+                Minus,
+                Star,
+                Slash,
+                Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn keywords_are_told_apart_from_identifiers() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("var variable for to tofu _if true"),
+            [Var, Identifier, For, To, Identifier, Identifier, True, Eof]
+        );
+    }
+
+    #[test]
+    fn every_suggested_keyword_lexes_as_a_keyword() {
+        for keyword in TokenKind::keywords() {
+            let kinds = kinds(keyword);
+            assert_ne!(kinds[0], TokenKind::Identifier, "{keyword}");
+            assert_eq!(kinds.len(), 2, "{keyword}");
         }
     }
 
-    fn advance(&mut self) {
-        self.current += 1;
+    #[test]
+    fn lexemes_borrow_the_source_at_byte_offsets() {
+        let source = "var ü = 12\nprint \"hi\"";
+        let (tokens, _) = lex(source);
+        let spans: Vec<(&str, usize, usize)> = tokens
+            .iter()
+            .map(|token| (token.lexeme, token.position.start, token.position.end))
+            .collect();
+        // 'ü' is not an identifier character, and takes two bytes.
+        assert_eq!(
+            spans,
+            [
+                ("var", 0, 3),
+                ("=", 7, 8),
+                ("12", 9, 11),
+                ("print", 12, 17),
+                ("hi", 18, 22),
+                ("", 21, 22),
+            ]
+        );
     }
 
-    fn next_char(&mut self) -> char {
-        let char = self.peek(0);
-        self.advance();
-        char
+    #[test]
+    fn comments_are_collected_rather_than_yielded() {
+        let diagnostic_bag = Rc::new(RefCell::new(DiagnosticBag::new()));
+        let mut lexer = Lexer::new("print 1 // one\n// two", diagnostic_bag);
+        let kinds: Vec<TokenKind> = lexer.by_ref().map(|token| token.kind).collect();
+        assert_eq!(kinds, [TokenKind::Print, TokenKind::Number, TokenKind::Eof]);
+        let comments: Vec<&str> = lexer.comments.iter().map(|comment| comment.text).collect();
+        assert_eq!(comments, ["// one", "// two"]);
+    }
+
+    #[test]
+    fn invalid_characters_are_reported_and_skipped() {
+        let (tokens, messages) = lex("1 # 2");
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(messages, ["Unexpected character '#'"]);
+    }
+
+    #[test]
+    fn unterminated_strings_run_to_the_end() {
+        let (tokens, messages) = lex("\"abc");
+        assert_eq!(tokens[0].kind, TokenKind::String);
+        assert_eq!(tokens[0].lexeme, "abc");
+        assert_eq!(messages, ["Unterminated string"]);
+    }
+
+    #[test]
+    fn eof_is_yielded_once() {
+        let diagnostic_bag = Rc::new(RefCell::new(DiagnosticBag::new()));
+        let mut lexer = Lexer::new("", diagnostic_bag);
+        assert_eq!(lexer.next().map(|token| token.kind), Some(TokenKind::Eof));
+        assert!(lexer.next().is_none());
     }
 }
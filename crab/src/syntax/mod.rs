@@ -1,5 +1,5 @@
 pub(crate) mod lexer;
 pub(crate) mod parser;
-pub(crate) mod token;
+pub mod token;
 
 pub mod syntax_tree;
@@ -1,56 +1,102 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
 use crate::common::{
+    arena::NodeId,
     diagnostic::{DiagnosticBag, Position},
+    intern::Interner,
     types::Object,
 };
 
 use super::{
+    lexer::Lexer,
     syntax_tree::{
-        AssignmentExpression, BinaryExpression, BlockStatement, Expression, ExpressionStatement,
-        ForStatement, IfStatement, LiteralExpression, NameExpression, ParenthesizedExpression,
-        PrintStatement, Statement, UnaryExpression, VarStatement, WhileStatement,
+        AssignmentExpression, BinaryExpression, BlockStatement, Expression, ExpressionId,
+        ExpressionStatement, ForStatement, IfStatement, LiteralExpression, NameExpression,
+        ParenthesizedExpression, PrintStatement, Statement, StatementId, SyntaxTree,
+        UnaryExpression, VarStatement, WhileStatement,
     },
     token::{Token, TokenKind},
 };
 
-pub(crate) struct Parser {
-    tokens: Vec<Token>,
-    current: usize,
+/// The parser never needs to see more than this many tokens ahead.
+const LOOKAHEAD: usize = 2;
+
+/// Pulls tokens from the lexer as it goes, keeping only the next `LOOKAHEAD` of them. Once the
+/// lexer is exhausted its `Eof` token stays at the front of the buffer. Nodes are allocated in
+/// the tree as they are parsed, children first.
+///
+/// The parser keeps nesting below `max_depth`. `depth` counts the nodes it is inside of, which
+/// bounds its own recursion, and `heights` the levels below each expression, since a chain of
+/// binary operators grows deeper to the left without the parser recursing. Past the limit it
+/// reports once, skips the rest of the source and leaves the statement out of the tree.
+pub(crate) struct Parser<'a> {
+    lexer: Lexer<'a>,
+    tokens: VecDeque<Token<'a>>,
+    tree: SyntaxTree<'a>,
+    max_depth: usize,
+    depth: usize,
+    heights: Vec<usize>,
+    too_deep: bool,
+    interner: Rc<RefCell<Interner>>,
     pub(super) diagnostic_bag: Rc<RefCell<DiagnosticBag>>,
 }
 
-impl Parser {
-    pub(crate) fn new(tokens: Vec<Token>, diagnostic_bag: Rc<RefCell<DiagnosticBag>>) -> Self {
-        Self {
-            tokens,
-            current: 0,
+impl<'a> Parser<'a> {
+    pub(crate) fn new(
+        lexer: Lexer<'a>,
+        interner: Rc<RefCell<Interner>>,
+        diagnostic_bag: Rc<RefCell<DiagnosticBag>>,
+        max_depth: usize,
+    ) -> Self {
+        let mut parser = Self {
+            lexer,
+            tokens: VecDeque::with_capacity(LOOKAHEAD),
+            tree: SyntaxTree::default(),
+            max_depth,
+            depth: 0,
+            heights: vec![],
+            too_deep: false,
+            interner,
             diagnostic_bag,
-        }
+        };
+        parser.fill();
+        parser
     }
 
-    pub(crate) fn parse(&mut self) -> Vec<Statement> {
-        let mut statements = vec![];
+    pub(crate) fn parse(mut self) -> SyntaxTree<'a> {
         while self.peek(0).kind != TokenKind::Eof {
-            statements.push(self.parse_statement());
+            let statement = self.parse_statement();
+            if !self.too_deep {
+                self.tree.push_root(statement);
+            }
         }
         self.match_token(TokenKind::Eof);
-        statements
+        self.tree.set_comments(self.lexer.comments);
+        self.tree
     }
 
-    fn parse_statement(&mut self) -> Statement {
-        match self.peek(0).kind {
+    fn parse_statement(&mut self) -> StatementId {
+        if !self.enter() {
+            let expression = self.placeholder();
+            return self.statement(Statement::Expression(ExpressionStatement::new(expression)));
+        }
+        let statement = match self.peek(0).kind {
             TokenKind::For => self.parse_for_statement(),
             TokenKind::While => self.parse_while_statement(),
             TokenKind::If => self.parse_if_statement(),
             TokenKind::OpenBrace => self.parse_block_statement(),
             TokenKind::Var => self.parse_var_statement(),
             TokenKind::Print => self.parse_print_statement(),
-            _ => Statement::Expression(ExpressionStatement::new(self.parse_expression())),
-        }
+            _ => {
+                let expression = self.parse_expression();
+                self.statement(Statement::Expression(ExpressionStatement::new(expression)))
+            }
+        };
+        self.depth -= 1;
+        statement
     }
 
-    fn parse_for_statement(&mut self) -> Statement {
+    fn parse_for_statement(&mut self) -> StatementId {
         self.match_token(TokenKind::For);
         let identifier = self.match_token(TokenKind::Identifier);
         self.match_token(TokenKind::Equal);
@@ -58,29 +104,33 @@ impl Parser {
         self.match_token(TokenKind::To);
         let upper_bound = self.parse_expression();
         let body = self.parse_statement();
-        Statement::For(ForStatement::new(
+        self.statement(Statement::For(ForStatement::new(
             identifier,
             lower_bound,
             upper_bound,
             body,
-        ))
+        )))
     }
 
-    fn parse_while_statement(&mut self) -> Statement {
+    fn parse_while_statement(&mut self) -> StatementId {
         self.match_token(TokenKind::While);
         let condition = self.parse_expression();
         let body = self.parse_statement();
-        Statement::While(WhileStatement::new(condition, body))
+        self.statement(Statement::While(WhileStatement::new(condition, body)))
     }
 
-    fn parse_if_statement(&mut self) -> Statement {
+    fn parse_if_statement(&mut self) -> StatementId {
         self.match_token(TokenKind::If);
         let condition = self.parse_expression();
         let consequence = self.parse_statement();
-        let else_clause = match (self.peek(0).kind, self.peek(1).kind) {
+        let else_clause = match (&self.peek(0).kind, &self.peek(1).kind) {
             (TokenKind::Else, TokenKind::If) => {
                 self.advance();
-                Some(self.parse_if_statement())
+                self.enter().then(|| {
+                    let else_if = self.parse_if_statement();
+                    self.depth -= 1;
+                    else_if
+                })
             }
             (TokenKind::Else, _) => {
                 self.advance();
@@ -88,78 +138,108 @@ impl Parser {
             }
             (_, _) => None,
         };
-        Statement::If(IfStatement::new(condition, consequence, else_clause))
+        self.statement(Statement::If(IfStatement::new(
+            condition,
+            consequence,
+            else_clause,
+        )))
     }
 
-    fn parse_block_statement(&mut self) -> Statement {
-        self.match_token(TokenKind::OpenBrace);
+    fn parse_block_statement(&mut self) -> StatementId {
+        let open_brace = self.match_token(TokenKind::OpenBrace);
         let mut statements = vec![];
-        while self.peek(0).kind != TokenKind::CloseBrace {
+        while !self.token_matches(&[TokenKind::CloseBrace, TokenKind::Eof]) {
             statements.push(self.parse_statement());
         }
-        self.match_token(TokenKind::CloseBrace);
-        Statement::Block(BlockStatement::new(statements))
+        let close_brace = self.match_token(TokenKind::CloseBrace);
+        self.statement(Statement::Block(BlockStatement::new(
+            open_brace,
+            statements,
+            close_brace,
+        )))
     }
 
-    fn parse_var_statement(&mut self) -> Statement {
+    fn parse_var_statement(&mut self) -> StatementId {
         self.match_token(TokenKind::Var);
         let identifier = self.match_token(TokenKind::Identifier);
         self.match_token(TokenKind::Equal);
         let expression = self.parse_expression();
-        Statement::Var(VarStatement::new(identifier, expression))
+        self.statement(Statement::Var(VarStatement::new(identifier, expression)))
     }
 
-    fn parse_print_statement(&mut self) -> Statement {
+    fn parse_print_statement(&mut self) -> StatementId {
         self.match_token(TokenKind::Print);
-        Statement::Print(PrintStatement::new(self.parse_expression()))
+        let expression = self.parse_expression();
+        self.statement(Statement::Print(PrintStatement::new(expression)))
     }
 
-    fn parse_expression(&mut self) -> Expression {
-        self.parse_assignment_expression()
+    fn parse_expression(&mut self) -> ExpressionId {
+        if !self.enter() {
+            return self.placeholder();
+        }
+        let expression = self.parse_assignment_expression();
+        self.depth -= 1;
+        expression
     }
 
-    fn parse_assignment_expression(&mut self) -> Expression {
+    fn parse_assignment_expression(&mut self) -> ExpressionId {
         if self.peek(0).kind == TokenKind::Identifier && self.peek(1).kind == TokenKind::Equal {
             let identifier = self.match_token(TokenKind::Identifier);
             self.match_token(TokenKind::Equal);
+            if !self.enter() {
+                return self.placeholder();
+            }
             let expression = self.parse_assignment_expression();
-            Expression::Assignment(AssignmentExpression::new(identifier, expression))
+            self.depth -= 1;
+            let position = Position::from(identifier.position.clone(), self.position(expression));
+            self.expression(Expression::Assignment(AssignmentExpression::new(
+                identifier, expression, position,
+            )))
         } else {
             self.parse_or_expression()
         }
     }
 
-    fn parse_or_expression(&mut self) -> Expression {
+    fn parse_or_expression(&mut self) -> ExpressionId {
         let mut left = self.parse_and_expression();
         while self.token_matches(&[TokenKind::PipePipe]) {
             let operator = self.next_token();
             let right = self.parse_and_expression();
-            left = Expression::Binary(BinaryExpression::new(left, operator, right));
+            let position = Position::from(self.position(left), self.position(right));
+            left = self.expression(Expression::Binary(BinaryExpression::new(
+                left, operator, right, position,
+            )));
         }
         left
     }
 
-    fn parse_and_expression(&mut self) -> Expression {
+    fn parse_and_expression(&mut self) -> ExpressionId {
         let mut left = self.parse_equality_expression();
         while self.token_matches(&[TokenKind::AmpersandAmpersand]) {
             let operator = self.next_token();
             let right = self.parse_equality_expression();
-            left = Expression::Binary(BinaryExpression::new(left, operator, right));
+            let position = Position::from(self.position(left), self.position(right));
+            left = self.expression(Expression::Binary(BinaryExpression::new(
+                left, operator, right, position,
+            )));
         }
         left
     }
 
-    fn parse_equality_expression(&mut self) -> Expression {
+    fn parse_equality_expression(&mut self) -> ExpressionId {
         let mut left = self.parse_comparison_expression();
         while self.token_matches(&[TokenKind::BangEqual, TokenKind::EqualEqual]) {
             let operator = self.next_token();
             let right = self.parse_comparison_expression();
-            left = Expression::Binary(BinaryExpression::new(left, operator, right));
+            let position = Position::from(self.position(left), self.position(right));
+            left = self.expression(Expression::Binary(BinaryExpression::new(
+                left, operator, right, position,
+            )));
         }
         left
     }
 
-    fn parse_comparison_expression(&mut self) -> Expression {
+    fn parse_comparison_expression(&mut self) -> ExpressionId {
         let mut left = self.parse_additive_expression();
         while self.token_matches(&[
             TokenKind::Greater,
@@ -169,115 +249,211 @@ impl Parser {
         ]) {
             let operator = self.next_token();
             let right = self.parse_additive_expression();
-            left = Expression::Binary(BinaryExpression::new(left, operator, right));
+            let position = Position::from(self.position(left), self.position(right));
+            left = self.expression(Expression::Binary(BinaryExpression::new(
+                left, operator, right, position,
+            )));
         }
         left
     }
 
-    fn parse_additive_expression(&mut self) -> Expression {
+    fn parse_additive_expression(&mut self) -> ExpressionId {
         let mut left = self.parse_multiplicative_expression();
         while self.token_matches(&[TokenKind::Plus, TokenKind::Minus]) {
             let operator = self.next_token();
             let right = self.parse_multiplicative_expression();
-            left = Expression::Binary(BinaryExpression::new(left, operator, right));
+            let position = Position::from(self.position(left), self.position(right));
+            left = self.expression(Expression::Binary(BinaryExpression::new(
+                left, operator, right, position,
+            )));
         }
         left
     }
 
-    fn parse_multiplicative_expression(&mut self) -> Expression {
+    fn parse_multiplicative_expression(&mut self) -> ExpressionId {
         let mut left = self.parse_unary_expression();
         while self.token_matches(&[TokenKind::Star, TokenKind::Slash]) {
             let operator = self.next_token();
             let right = self.parse_unary_expression();
-            left = Expression::Binary(BinaryExpression::new(left, operator, right));
+            let position = Position::from(self.position(left), self.position(right));
+            left = self.expression(Expression::Binary(BinaryExpression::new(
+                left, operator, right, position,
+            )));
         }
         left
     }
 
-    fn parse_unary_expression(&mut self) -> Expression {
+    fn parse_unary_expression(&mut self) -> ExpressionId {
         if self.token_matches(&[TokenKind::Plus, TokenKind::Minus, TokenKind::Bang]) {
             let operator = self.next_token();
+            if !self.enter() {
+                return self.placeholder();
+            }
             let right = self.parse_unary_expression();
-            Expression::Unary(UnaryExpression::new(operator, right))
+            self.depth -= 1;
+            let position = Position::from(operator.position.clone(), self.position(right));
+            self.expression(Expression::Unary(UnaryExpression::new(
+                operator, right, position,
+            )))
         } else {
             self.parse_primary_expression()
         }
     }
 
-    fn parse_primary_expression(&mut self) -> Expression {
+    fn parse_primary_expression(&mut self) -> ExpressionId {
         match self.peek(0).kind {
             TokenKind::OpenParen => {
                 let open_paren = self.next_token();
                 let expression = self.parse_expression();
                 let close_paren = self.match_token(TokenKind::CloseParen);
-                Expression::Parenthesized(ParenthesizedExpression::new(
+                self.expression(Expression::Parenthesized(ParenthesizedExpression::new(
                     open_paren,
                     expression,
                     close_paren,
-                ))
+                )))
             }
             TokenKind::True | TokenKind::False => {
                 let token = self.next_token();
                 let value = token.lexeme.parse().unwrap();
-                Expression::Literal(LiteralExpression::new(
+                self.expression(Expression::Literal(LiteralExpression::new(
                     Object::Boolean(value),
                     token.position,
-                ))
+                )))
             }
             TokenKind::Number => {
                 let token = self.next_token();
-                let value = token.lexeme.parse().unwrap();
-                Expression::Literal(LiteralExpression::new(
+                let value = token.lexeme.parse().unwrap_or_else(|_| {
+                    self.diagnostic_bag
+                        .borrow_mut()
+                        .number_out_of_range(token.position.clone(), token.lexeme);
+                    0
+                });
+                self.expression(Expression::Literal(LiteralExpression::new(
                     Object::Number(value),
                     token.position,
-                ))
+                )))
             }
             TokenKind::String => {
                 let token = self.next_token();
-                Expression::Literal(LiteralExpression::new(
-                    Object::String(token.lexeme),
+                let value = {
+                    let mut interner = self.interner.borrow_mut();
+                    let symbol = interner.intern(token.lexeme);
+                    interner.shared(symbol)
+                };
+                self.expression(Expression::Literal(LiteralExpression::new(
+                    Object::String(value),
                     token.position,
-                ))
+                )))
             }
             _ => {
                 let identifier = self.match_token(TokenKind::Identifier);
-                Expression::Name(NameExpression::new(identifier))
+                self.expression(Expression::Name(NameExpression::new(identifier)))
             }
         }
     }
 
-    fn peek(&self, offset: usize) -> Token {
-        let index = offset + self.current;
-        if index < self.tokens.len() {
-            self.tokens[index].clone()
-        } else {
-            Token::new(TokenKind::Eof, "EOF".to_string(), Position::new(0, 0))
+    /// Enters a node nested in the current one, unless that goes past `max_depth`. A caller
+    /// that entered leaves again by decrementing `depth`.
+    fn enter(&mut self) -> bool {
+        if self.depth + 1 > self.max_depth {
+            self.report_too_deep();
+        }
+        if self.too_deep {
+            return false;
         }
+        self.depth += 1;
+        true
+    }
+
+    fn report_too_deep(&mut self) {
+        if self.too_deep {
+            return;
+        }
+        self.too_deep = true;
+        let position = self.peek(0).position.clone();
+        self.diagnostic_bag
+            .borrow_mut()
+            .too_deeply_nested(position, self.max_depth);
+        while self.peek(0).kind != TokenKind::Eof {
+            self.advance();
+        }
+    }
+
+    /// Stands in for what was not parsed because the program was nested too deeply.
+    fn placeholder(&mut self) -> ExpressionId {
+        let position = self.peek(0).position.clone();
+        self.expression(Expression::Literal(LiteralExpression::new(
+            Object::Unit,
+            position,
+        )))
+    }
+
+    fn expression(&mut self, expression: Expression<'a>) -> ExpressionId {
+        let height = |id: ExpressionId| self.heights[id.index()];
+        let height = 1 + match &expression {
+            Expression::Literal(_) | Expression::Name(_) => 0,
+            Expression::Parenthesized(expression) => height(expression.expression),
+            Expression::Unary(expression) => height(expression.right),
+            Expression::Binary(expression) => height(expression.left).max(height(expression.right)),
+            Expression::Assignment(expression) => height(expression.expression),
+        };
+        if self.depth + height > self.max_depth {
+            self.report_too_deep();
+        }
+        self.heights.push(height);
+        self.tree.alloc_expression(expression)
+    }
+
+    fn statement(&mut self, statement: Statement<'a>) -> StatementId {
+        self.tree.alloc_statement(statement)
+    }
+
+    fn position(&self, expression: ExpressionId) -> Position {
+        self.tree.expression(expression).get_position()
+    }
+
+    fn fill(&mut self) {
+        while self.tokens.len() < LOOKAHEAD {
+            match self.lexer.next() {
+                Some(token) => self.tokens.push_back(token),
+                None => break,
+            }
+        }
+    }
+
+    fn peek(&self, offset: usize) -> &Token<'a> {
+        &self.tokens[offset.min(self.tokens.len() - 1)]
     }
 
     fn advance(&mut self) {
-        self.current += 1;
+        self.next_token();
     }
 
-    fn next_token(&mut self) -> Token {
-        let token = self.peek(0);
-        self.advance();
+    fn next_token(&mut self) -> Token<'a> {
+        let token = if self.tokens.len() == 1 {
+            self.tokens[0].clone()
+        } else {
+            self.tokens.pop_front().unwrap()
+        };
+        self.fill();
         token
     }
 
-    fn match_token(&mut self, kind: TokenKind) -> Token {
+    fn match_token(&mut self, kind: TokenKind) -> Token<'a> {
         let token = self.peek(0);
         if kind == token.kind {
             self.next_token()
+        } else if self.too_deep {
+            Token::new(kind, "%GENERATED%", token.position.clone())
         } else {
+            let position = token.position.clone();
             self.diagnostic_bag.borrow_mut().unexpected_token(
-                token.position,
+                position.clone(),
                 kind.clone(),
-                token.kind,
+                token.kind.clone(),
             );
-            let token = Token::new(kind, "%GENERATED%".to_string(), self.peek(0).position);
             self.advance();
-            token
+            Token::new(kind, "%GENERATED%", position)
         }
     }
 
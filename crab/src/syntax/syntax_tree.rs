@@ -1,19 +1,70 @@
-use crate::common::{diagnostic::Position, types::Object};
+use crate::common::{
+    arena::{node_id, Arena},
+    diagnostic::Position,
+    types::Object,
+};
+
+use super::token::{Comment, Token};
+
+node_id!(ExpressionId);
+node_id!(StatementId);
+
+/// A parsed program. Its nodes live in arenas and refer to their children by id; `root` holds
+/// the top-level statements in source order, and `comments` every comment in the source.
+#[derive(Debug, Default)]
+pub struct SyntaxTree<'a> {
+    expressions: Arena<ExpressionId, Expression<'a>>,
+    statements: Arena<StatementId, Statement<'a>>,
+    root: Vec<StatementId>,
+    comments: Vec<Comment<'a>>,
+}
+
+impl<'a> SyntaxTree<'a> {
+    pub fn root(&self) -> &[StatementId] {
+        &self.root
+    }
 
-use super::token::Token;
+    pub fn comments(&self) -> &[Comment<'a>] {
+        &self.comments
+    }
+
+    pub fn expression(&self, id: ExpressionId) -> &Expression<'a> {
+        &self.expressions[id]
+    }
+
+    pub fn statement(&self, id: StatementId) -> &Statement<'a> {
+        &self.statements[id]
+    }
+
+    pub(super) fn alloc_expression(&mut self, expression: Expression<'a>) -> ExpressionId {
+        self.expressions.alloc(expression)
+    }
+
+    pub(super) fn alloc_statement(&mut self, statement: Statement<'a>) -> StatementId {
+        self.statements.alloc(statement)
+    }
+
+    pub(super) fn push_root(&mut self, statement: StatementId) {
+        self.root.push(statement);
+    }
+
+    pub(super) fn set_comments(&mut self, comments: Vec<Comment<'a>>) {
+        self.comments = comments;
+    }
+}
 
 #[derive(Debug, Clone)]
-pub enum Expression {
+pub enum Expression<'a> {
     Literal(LiteralExpression),
-    Name(NameExpression),
-    Parenthesized(ParenthesizedExpression),
-    Unary(UnaryExpression),
-    Binary(BinaryExpression),
-    Assignment(AssignmentExpression),
+    Name(NameExpression<'a>),
+    Parenthesized(ParenthesizedExpression<'a>),
+    Unary(UnaryExpression<'a>),
+    Binary(BinaryExpression<'a>),
+    Assignment(AssignmentExpression<'a>),
 }
 
-impl Expression {
-    pub(crate) fn get_position(&self) -> Position {
+impl Expression<'_> {
+    pub fn get_position(&self) -> Position {
         match self {
             Expression::Literal(expression) => expression.get_position(),
             Expression::Name(expression) => expression.get_position(),
@@ -27,7 +78,7 @@ impl Expression {
 
 #[derive(Debug, Clone)]
 pub struct LiteralExpression {
-    pub(crate) value: Object,
+    pub value: Object,
     position: Position,
 }
 
@@ -36,43 +87,47 @@ impl LiteralExpression {
         Self { value, position }
     }
 
-    pub(crate) fn get_position(&self) -> Position {
+    pub fn get_position(&self) -> Position {
         self.position.clone()
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct NameExpression {
-    pub(crate) identifier: Token,
+pub struct NameExpression<'a> {
+    pub identifier: Token<'a>,
 }
 
-impl NameExpression {
-    pub(super) fn new(identifier: Token) -> Self {
+impl<'a> NameExpression<'a> {
+    pub(super) fn new(identifier: Token<'a>) -> Self {
         Self { identifier }
     }
 
-    pub(crate) fn get_position(&self) -> Position {
+    pub fn get_position(&self) -> Position {
         self.identifier.position.clone()
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct ParenthesizedExpression {
-    open_paren: Token,
-    pub(crate) expression: Box<Expression>,
-    close_paren: Token,
+pub struct ParenthesizedExpression<'a> {
+    open_paren: Token<'a>,
+    pub expression: ExpressionId,
+    close_paren: Token<'a>,
 }
 
-impl ParenthesizedExpression {
-    pub(super) fn new(open_paren: Token, expression: Expression, close_paren: Token) -> Self {
+impl<'a> ParenthesizedExpression<'a> {
+    pub(super) fn new(
+        open_paren: Token<'a>,
+        expression: ExpressionId,
+        close_paren: Token<'a>,
+    ) -> Self {
         Self {
             open_paren,
-            expression: Box::new(expression),
+            expression,
             close_paren,
         }
     }
 
-    pub(crate) fn get_position(&self) -> Position {
+    pub fn get_position(&self) -> Position {
         Position::from(
             self.open_paren.position.clone(),
             self.close_paren.position.clone(),
@@ -81,108 +136,116 @@ impl ParenthesizedExpression {
 }
 
 #[derive(Debug, Clone)]
-pub struct UnaryExpression {
-    pub(crate) operator: Token,
-    pub(crate) right: Box<Expression>,
+pub struct UnaryExpression<'a> {
+    pub operator: Token<'a>,
+    pub right: ExpressionId,
+    position: Position,
 }
 
-impl UnaryExpression {
-    pub(super) fn new(operator: Token, right: Expression) -> Self {
+impl<'a> UnaryExpression<'a> {
+    pub(super) fn new(operator: Token<'a>, right: ExpressionId, position: Position) -> Self {
         Self {
             operator,
-            right: Box::new(right),
+            right,
+            position,
         }
     }
 
-    pub(crate) fn get_position(&self) -> Position {
-        Position::from(self.operator.position.clone(), self.right.get_position())
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct BinaryExpression {
-    pub(crate) left: Box<Expression>,
-    pub(crate) operator: Token,
-    pub(crate) right: Box<Expression>,
+pub struct BinaryExpression<'a> {
+    pub left: ExpressionId,
+    pub operator: Token<'a>,
+    pub right: ExpressionId,
+    position: Position,
 }
 
-impl BinaryExpression {
-    pub(super) fn new(left: Expression, operator: Token, right: Expression) -> Self {
+impl<'a> BinaryExpression<'a> {
+    pub(super) fn new(
+        left: ExpressionId,
+        operator: Token<'a>,
+        right: ExpressionId,
+        position: Position,
+    ) -> Self {
         Self {
-            left: Box::new(left),
+            left,
             operator,
-            right: Box::new(right),
+            right,
+            position,
         }
     }
 
-    pub(crate) fn get_position(&self) -> Position {
-        Position::from(self.left.get_position(), self.right.get_position())
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct AssignmentExpression {
-    pub(crate) identifier: Token,
-    pub(crate) expression: Box<Expression>,
+pub struct AssignmentExpression<'a> {
+    pub identifier: Token<'a>,
+    pub expression: ExpressionId,
+    position: Position,
 }
 
-impl AssignmentExpression {
-    pub(super) fn new(identifier: Token, expression: Expression) -> Self {
+impl<'a> AssignmentExpression<'a> {
+    pub(super) fn new(identifier: Token<'a>, expression: ExpressionId, position: Position) -> Self {
         Self {
             identifier,
-            expression: Box::new(expression),
+            expression,
+            position,
         }
     }
 
-    pub(crate) fn get_position(&self) -> Position {
-        Position::from(
-            self.identifier.position.clone(),
-            self.expression.get_position(),
-        )
+    pub fn get_position(&self) -> Position {
+        self.position.clone()
     }
 }
 
 #[derive(Debug, Clone)]
-pub enum Statement {
+pub enum Statement<'a> {
     Expression(ExpressionStatement),
     Print(PrintStatement),
-    Var(VarStatement),
-    Block(BlockStatement),
+    Var(VarStatement<'a>),
+    Block(BlockStatement<'a>),
     If(IfStatement),
     While(WhileStatement),
-    For(ForStatement),
+    For(ForStatement<'a>),
 }
 
 #[derive(Debug, Clone)]
 pub struct ExpressionStatement {
-    pub(crate) expression: Expression,
+    pub expression: ExpressionId,
 }
 
 impl ExpressionStatement {
-    pub(super) fn new(expression: Expression) -> Self {
+    pub(super) fn new(expression: ExpressionId) -> Self {
         Self { expression }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct PrintStatement {
-    pub(crate) expression: Expression,
+    pub expression: ExpressionId,
 }
 
 impl PrintStatement {
-    pub(super) fn new(expression: Expression) -> Self {
+    pub(super) fn new(expression: ExpressionId) -> Self {
         Self { expression }
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct VarStatement {
-    pub(crate) identifier: Token,
-    pub(crate) expression: Expression,
+pub struct VarStatement<'a> {
+    pub identifier: Token<'a>,
+    pub expression: ExpressionId,
 }
 
-impl VarStatement {
-    pub(super) fn new(identifier: Token, expression: Expression) -> Self {
+impl<'a> VarStatement<'a> {
+    pub(super) fn new(identifier: Token<'a>, expression: ExpressionId) -> Self {
         Self {
             identifier,
             expression,
@@ -191,72 +254,86 @@ impl VarStatement {
 }
 
 #[derive(Debug, Clone)]
-pub struct BlockStatement {
-    pub(crate) statements: Vec<Statement>,
+pub struct BlockStatement<'a> {
+    open_brace: Token<'a>,
+    pub statements: Vec<StatementId>,
+    close_brace: Token<'a>,
 }
 
-impl BlockStatement {
-    pub(super) fn new(statements: Vec<Statement>) -> Self {
-        Self { statements }
+impl<'a> BlockStatement<'a> {
+    pub(super) fn new(
+        open_brace: Token<'a>,
+        statements: Vec<StatementId>,
+        close_brace: Token<'a>,
+    ) -> Self {
+        Self {
+            open_brace,
+            statements,
+            close_brace,
+        }
+    }
+
+    pub fn get_position(&self) -> Position {
+        Position::from(
+            self.open_brace.position.clone(),
+            self.close_brace.position.clone(),
+        )
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct IfStatement {
-    pub(crate) condition: Expression,
-    pub(crate) consequence: Box<Statement>,
-    pub(crate) else_clause: Box<Option<Statement>>,
+    pub condition: ExpressionId,
+    pub consequence: StatementId,
+    pub else_clause: Option<StatementId>,
 }
 
 impl IfStatement {
     pub(super) fn new(
-        condition: Expression,
-        consequence: Statement,
-        else_clause: Option<Statement>,
+        condition: ExpressionId,
+        consequence: StatementId,
+        else_clause: Option<StatementId>,
     ) -> Self {
         Self {
             condition,
-            consequence: Box::new(consequence),
-            else_clause: Box::new(else_clause),
+            consequence,
+            else_clause,
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct WhileStatement {
-    pub(crate) condition: Expression,
-    pub(crate) body: Box<Statement>,
+    pub condition: ExpressionId,
+    pub body: StatementId,
 }
 
 impl WhileStatement {
-    pub(super) fn new(condition: Expression, body: Statement) -> Self {
-        Self {
-            condition,
-            body: Box::new(body),
-        }
+    pub(super) fn new(condition: ExpressionId, body: StatementId) -> Self {
+        Self { condition, body }
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct ForStatement {
-    pub(crate) identifier: Token,
-    pub(crate) lower_bound: Expression,
-    pub(crate) upper_bound: Expression,
-    pub(crate) body: Box<Statement>,
+pub struct ForStatement<'a> {
+    pub identifier: Token<'a>,
+    pub lower_bound: ExpressionId,
+    pub upper_bound: ExpressionId,
+    pub body: StatementId,
 }
 
-impl ForStatement {
+impl<'a> ForStatement<'a> {
     pub(super) fn new(
-        identifier: Token,
-        lower_bound: Expression,
-        upper_bound: Expression,
-        body: Statement,
+        identifier: Token<'a>,
+        lower_bound: ExpressionId,
+        upper_bound: ExpressionId,
+        body: StatementId,
     ) -> Self {
         Self {
             identifier,
             lower_bound,
             upper_bound,
-            body: Box::new(body),
+            body,
         }
     }
 }
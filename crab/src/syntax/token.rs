@@ -43,25 +43,36 @@ pub enum TokenKind {
     To,
 
     Whitespace,
+    Comment,
 
     Invalid,
     Eof,
 }
 
+/// Every keyword with the token it lexes to; anything else that looks like a name is an
+/// identifier.
+const KEYWORDS: [(&str, TokenKind); 9] = [
+    ("true", TokenKind::True),
+    ("false", TokenKind::False),
+    ("print", TokenKind::Print),
+    ("var", TokenKind::Var),
+    ("if", TokenKind::If),
+    ("else", TokenKind::Else),
+    ("while", TokenKind::While),
+    ("for", TokenKind::For),
+    ("to", TokenKind::To),
+];
+
 impl TokenKind {
+    pub(crate) fn keywords() -> impl Iterator<Item = &'static str> {
+        KEYWORDS.iter().map(|(keyword, _)| *keyword)
+    }
+
     pub(super) fn get_lexeme_type(lexeme: &str) -> Self {
-        match lexeme {
-            "true" => TokenKind::True,
-            "false" => TokenKind::False,
-            "print" => TokenKind::Print,
-            "var" => TokenKind::Var,
-            "if" => TokenKind::If,
-            "else" => TokenKind::Else,
-            "while" => TokenKind::While,
-            "for" => TokenKind::For,
-            "to" => TokenKind::To,
-            _ => TokenKind::Identifier,
-        }
+        KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == lexeme)
+            .map_or(TokenKind::Identifier, |(_, kind)| kind.clone())
     }
 }
 
@@ -107,6 +118,7 @@ impl Display for TokenKind {
             TokenKind::To => write!(f, "to"),
 
             TokenKind::Whitespace => write!(f, "WHITESPACE"),
+            TokenKind::Comment => write!(f, "COMMENT"),
 
             TokenKind::Invalid => write!(f, "INVALID"),
             TokenKind::Eof => write!(f, "EOF"),
@@ -115,14 +127,14 @@ impl Display for TokenKind {
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct Token {
-    pub(crate) kind: TokenKind,
-    pub(crate) lexeme: String,
-    pub(crate) position: Position,
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub lexeme: &'a str,
+    pub position: Position,
 }
 
-impl Token {
-    pub(super) fn new(kind: TokenKind, lexeme: String, position: Position) -> Self {
+impl<'a> Token<'a> {
+    pub(super) fn new(kind: TokenKind, lexeme: &'a str, position: Position) -> Self {
         Self {
             kind,
             lexeme,
@@ -130,3 +142,12 @@ impl Token {
         }
     }
 }
+
+/// A `//` comment, running to the end of its line. The parser never sees comments; the lexer
+/// sets them aside so that tools emitting source code can carry them over.
+#[derive(Debug, Clone)]
+pub struct Comment<'a> {
+    /// The comment including its leading `//`.
+    pub text: &'a str,
+    pub position: Position,
+}
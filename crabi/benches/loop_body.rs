@@ -0,0 +1,76 @@
+//! Times a `while` loop whose body holds a branch that is never taken, for growing branch sizes.
+//! The statements executed per iteration stay the same, so the time per iteration should too.
+//!
+//! The program is bound, and compiled to bytecode, before the clock starts, so only the
+//! engines are timed and not reading, parsing or binding the source.
+//!
+//! Run with `cargo bench -p crabi`.
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crab::{binding::bindings::Bindings, common::intern::Interner, compilation::Compilation};
+use crabi::{BytecodeCompiler, Environment, Evaluator, VirtualMachine};
+
+const ITERATIONS: usize = 50_000;
+const BODY_SIZES: [usize; 4] = [1, 10, 100, 1_000];
+const RUNS: usize = 5;
+
+fn program(body_size: usize) -> String {
+    let mut source =
+        format!("var n = 0\nwhile n < {ITERATIONS} {{\n    n = n + 1\n    if n < 0 {{\n");
+    for _ in 0..body_size {
+        source.push_str("        print n\n");
+    }
+    source.push_str("    }\n}\n");
+    source
+}
+
+/// The fastest of a few runs, which is the one least disturbed by the rest of the system.
+fn time(mut run: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    println!(
+        "{:>10} {:>16} {:>16}",
+        "body size", "tree ns/iter", "vm ns/iter"
+    );
+    for body_size in BODY_SIZES {
+        let source = program(body_size);
+        let compilation = Compilation::compile(
+            &source,
+            Rc::new(RefCell::new(Bindings::default())),
+            Rc::new(RefCell::new(Interner::default())),
+        );
+        assert!(!compilation.diagnostic_bag.borrow().has_errors());
+        let interner = compilation.interner.borrow();
+        let chunk = BytecodeCompiler::new(&compilation.bound_program).compile();
+
+        let tree = time(|| {
+            Evaluator::new(
+                &compilation.bound_program,
+                &mut Environment::default(),
+                &interner,
+            )
+            .evaluate()
+            .unwrap();
+        });
+        let vm = time(|| {
+            VirtualMachine::default().run(&chunk, &interner).unwrap();
+        });
+        let tree = tree.as_nanos() / ITERATIONS as u128;
+        let vm = vm.as_nanos() / ITERATIONS as u128;
+        println!("{body_size:>10} {tree:>16} {vm:>16}");
+    }
+}
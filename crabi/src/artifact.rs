@@ -0,0 +1,502 @@
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crab::{
+    binding::bound_tree::{BoundBinaryOperationKind, BoundUnaryOperationKind},
+    common::{
+        diagnostic::Position,
+        intern::{Interner, Symbol},
+        types::Object,
+    },
+};
+
+use super::bytecode::{Chunk, Instruction};
+
+const MAGIC: &[u8; 4] = b"CRBC";
+/// Bumped whenever the layout or the meaning of an instruction changes, so artifacts written by
+/// another version are compiled again instead of misread.
+const VERSION: u16 = 2;
+const OPTIMIZED: u8 = 1;
+/// The crabi that wrote an artifact. The same source can compile to other bytecode in another
+/// release without the format changing, so an artifact is only read by the crabi that wrote it.
+const COMPILER: &str = concat!("crabi ", env!("CARGO_PKG_VERSION"));
+
+/// A compiled program as stored in a `.crabc` file, next to the source it was compiled from.
+///
+/// The layout is little-endian: the magic `CRBC`, the version (`u16`), the compiler (a string),
+/// flags (`u8`), the hash of the source (`u64`), the payload length (`u32`) and the payload,
+/// followed by a checksum
+/// (`u64`) of everything before it. The payload holds the constant pool, the names of the
+/// globals, and every instruction with its source position. Names are stored as strings, since
+/// symbols are only meaningful to the interner that made them. Strings are a `u32` length and
+/// as many bytes of UTF-8.
+pub struct Artifact {
+    pub source_hash: u64,
+    pub optimized: bool,
+    pub chunk: Chunk,
+}
+
+#[derive(Debug)]
+pub enum ArtifactError {
+    NotAnArtifact,
+    UnsupportedVersion(u16),
+    OtherCompiler(String),
+    Corrupted,
+}
+
+impl Display for ArtifactError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArtifactError::NotAnArtifact => write!(f, "Not a compiled crab program"),
+            ArtifactError::UnsupportedVersion(version) => write!(
+                f,
+                "Compiled with format version {version}, expected version {VERSION}"
+            ),
+            ArtifactError::OtherCompiler(compiler) => {
+                write!(f, "Compiled by {compiler}, expected {COMPILER}")
+            }
+            ArtifactError::Corrupted => write!(f, "The compiled program is corrupted"),
+        }
+    }
+}
+
+/// The path of the artifact for a source file: the same path with the extension `crabc`.
+pub fn path_for(source: &Path) -> PathBuf {
+    source.with_extension("crabc")
+}
+
+/// A 64-bit FNV-1a hash. It is used both for the source hash and the checksum, and unlike the
+/// hasher of the standard library it is the same in every build.
+pub fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl Artifact {
+    pub fn encode(&self, interner: &Interner) -> Vec<u8> {
+        let mut payload = Writer::default();
+        payload.u32(self.chunk.constants.len() as u32);
+        for constant in &self.chunk.constants {
+            match constant {
+                Object::Unit => payload.u8(0),
+                Object::Number(number) => {
+                    payload.u8(1);
+                    payload.u32(*number as u32);
+                }
+                Object::Boolean(boolean) => {
+                    payload.u8(2);
+                    payload.u8(*boolean as u8);
+                }
+                Object::String(string) => {
+                    payload.u8(3);
+                    payload.string(string);
+                }
+            }
+        }
+
+        let mut names: Vec<Symbol> = vec![];
+        let mut name_index = |name: Symbol| match names.iter().position(|&known| known == name) {
+            Some(index) => index as u32,
+            None => {
+                names.push(name);
+                names.len() as u32 - 1
+            }
+        };
+        let mut code = Writer::default();
+        code.u32(self.chunk.instructions.len() as u32);
+        for (instruction, position) in self.chunk.instructions.iter().zip(&self.chunk.positions) {
+            let (opcode, operand) = match instruction {
+                Instruction::Constant(index) => (0, *index as u32),
+                Instruction::Pop => (1, 0),
+                Instruction::DefineGlobal(name) => (2, name_index(*name)),
+                Instruction::GetGlobal(name) => (3, name_index(*name)),
+                Instruction::SetGlobal(name) => (4, name_index(*name)),
+                Instruction::GetLocal(slot) => (5, *slot as u32),
+                Instruction::SetLocal(slot) => (6, *slot as u32),
+                Instruction::Unary(operator) => (7, unary_code(operator)),
+                Instruction::Binary(operator) => (8, binary_code(operator)),
+                Instruction::Print => (9, 0),
+                Instruction::Jump(target) => (10, *target as u32),
+                Instruction::JumpIfFalse(target) => (11, *target as u32),
+                Instruction::Return => (12, 0),
+            };
+            code.u8(opcode);
+            code.u32(operand);
+            code.u32(position.start as u32);
+            code.u32(position.end as u32);
+        }
+        payload.u32(names.len() as u32);
+        for name in names {
+            payload.string(interner.resolve(name));
+        }
+        payload.bytes.extend(code.bytes);
+
+        let mut artifact = Writer::default();
+        artifact.bytes.extend(MAGIC);
+        artifact.u16(VERSION);
+        artifact.string(COMPILER);
+        artifact.u8(if self.optimized { OPTIMIZED } else { 0 });
+        artifact.u64(self.source_hash);
+        artifact.u32(payload.bytes.len() as u32);
+        artifact.bytes.extend(payload.bytes);
+        let checksum = hash(&artifact.bytes);
+        artifact.u64(checksum);
+        artifact.bytes
+    }
+
+    /// Reads an artifact back, interning the names of its globals.
+    pub fn decode(bytes: &[u8], interner: &mut Interner) -> Result<Self, ArtifactError> {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(ArtifactError::NotAnArtifact);
+        }
+        let mut reader = Reader::new(&bytes[MAGIC.len()..]);
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(ArtifactError::UnsupportedVersion(version));
+        }
+        let (contents, checksum) = bytes.split_at(bytes.len().saturating_sub(8));
+        if checksum.len() != 8 || hash(contents) != Reader::new(checksum).u64()? {
+            return Err(ArtifactError::Corrupted);
+        }
+        let compiler = reader.string()?;
+        if compiler != COMPILER {
+            return Err(ArtifactError::OtherCompiler(compiler.to_string()));
+        }
+        let optimized = reader.u8()? & OPTIMIZED != 0;
+        let source_hash = reader.u64()?;
+        let length = reader.u32()? as usize;
+        if MAGIC.len() + reader.offset + length != contents.len() {
+            return Err(ArtifactError::Corrupted);
+        }
+
+        let mut chunk = Chunk::default();
+        for _ in 0..reader.u32()? {
+            let constant = match reader.u8()? {
+                0 => Object::Unit,
+                1 => Object::Number(reader.u32()? as i32),
+                2 => Object::Boolean(reader.u8()? != 0),
+                3 => Object::String(Rc::from(reader.string()?)),
+                _ => return Err(ArtifactError::Corrupted),
+            };
+            chunk.constants.push(constant);
+        }
+        let names = (0..reader.u32()?)
+            .map(|_| Ok(interner.intern(reader.string()?)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let name = |index: u32| names.get(index as usize).copied();
+        for _ in 0..reader.u32()? {
+            let opcode = reader.u8()?;
+            let operand = reader.u32()?;
+            let instruction = match opcode {
+                0 if (operand as usize) < chunk.constants.len() => {
+                    Some(Instruction::Constant(operand as usize))
+                }
+                1 => Some(Instruction::Pop),
+                2 => name(operand).map(Instruction::DefineGlobal),
+                3 => name(operand).map(Instruction::GetGlobal),
+                4 => name(operand).map(Instruction::SetGlobal),
+                5 => Some(Instruction::GetLocal(operand as usize)),
+                6 => Some(Instruction::SetLocal(operand as usize)),
+                7 => unary_operation(operand).map(Instruction::Unary),
+                8 => binary_operation(operand).map(Instruction::Binary),
+                9 => Some(Instruction::Print),
+                10 => Some(Instruction::Jump(operand as usize)),
+                11 => Some(Instruction::JumpIfFalse(operand as usize)),
+                12 => Some(Instruction::Return),
+                _ => None,
+            }
+            .ok_or(ArtifactError::Corrupted)?;
+            let position = Position {
+                start: reader.u32()? as usize,
+                end: reader.u32()? as usize,
+            };
+            chunk.emit(instruction, position);
+        }
+        if !is_well_formed(&chunk) || reader.offset != contents.len() - MAGIC.len() {
+            return Err(ArtifactError::Corrupted);
+        }
+        Ok(Self {
+            source_hash,
+            optimized,
+            chunk,
+        })
+    }
+
+    pub fn disassemble(&self, interner: &Interner) -> String {
+        format!(
+            "; crabc version {VERSION} by {COMPILER}, source hash {:016x}{}\n{}",
+            self.source_hash,
+            if self.optimized { ", optimized" } else { "" },
+            self.chunk.disassemble(interner)
+        )
+    }
+}
+
+/// Whether the virtual machine can run a chunk without leaving it or its stack: every path
+/// reaches an instruction with the same number of values on the stack, finds the values it pops
+/// and only addresses locals below them, and no path runs past the end. The VM trusts the
+/// compiler on all of this, so a chunk read from a file has to be checked.
+fn is_well_formed(chunk: &Chunk) -> bool {
+    let instructions = &chunk.instructions;
+    let mut heights = vec![None; instructions.len()];
+    let mut pending = vec![(0, 0)];
+    while let Some((ip, height)) = pending.pop() {
+        let Some(instruction) = instructions.get(ip) else {
+            return false;
+        };
+        match heights[ip] {
+            Some(known) if known == height => continue,
+            Some(_) => return false,
+            None => heights[ip] = Some(height),
+        }
+        let (pops, pushes) = match instruction {
+            Instruction::Constant(_) | Instruction::GetGlobal(_) => (0, 1),
+            Instruction::GetLocal(slot) if *slot < height => (0, 1),
+            Instruction::SetLocal(slot) if *slot < height => (1, 1),
+            Instruction::GetLocal(_) | Instruction::SetLocal(_) => return false,
+            Instruction::SetGlobal(_) | Instruction::Unary(_) => (1, 1),
+            Instruction::Binary(_) => (2, 1),
+            Instruction::Pop
+            | Instruction::DefineGlobal(_)
+            | Instruction::Print
+            | Instruction::JumpIfFalse(_)
+            | Instruction::Return => (1, 0),
+            Instruction::Jump(_) => (0, 0),
+        };
+        if height < pops {
+            return false;
+        }
+        let height = height - pops + pushes;
+        match instruction {
+            Instruction::Return => {}
+            Instruction::Jump(target) => pending.push((*target, height)),
+            Instruction::JumpIfFalse(target) => {
+                pending.push((*target, height));
+                pending.push((ip + 1, height));
+            }
+            _ => pending.push((ip + 1, height)),
+        }
+    }
+    true
+}
+
+const UNARY_OPERATIONS: [BoundUnaryOperationKind; 3] = [
+    BoundUnaryOperationKind::Identity,
+    BoundUnaryOperationKind::Negation,
+    BoundUnaryOperationKind::LogicalNegation,
+];
+
+const BINARY_OPERATIONS: [BoundBinaryOperationKind; 12] = [
+    BoundBinaryOperationKind::Addition,
+    BoundBinaryOperationKind::Subtraction,
+    BoundBinaryOperationKind::Multiplication,
+    BoundBinaryOperationKind::Division,
+    BoundBinaryOperationKind::Greater,
+    BoundBinaryOperationKind::Lesser,
+    BoundBinaryOperationKind::GreaterEqual,
+    BoundBinaryOperationKind::LesserEqual,
+    BoundBinaryOperationKind::LogicalAnd,
+    BoundBinaryOperationKind::LogicalOr,
+    BoundBinaryOperationKind::NotEqual,
+    BoundBinaryOperationKind::Equal,
+];
+
+fn unary_code(operation: &BoundUnaryOperationKind) -> u32 {
+    match operation {
+        BoundUnaryOperationKind::Identity => 0,
+        BoundUnaryOperationKind::Negation => 1,
+        BoundUnaryOperationKind::LogicalNegation => 2,
+    }
+}
+
+fn unary_operation(code: u32) -> Option<BoundUnaryOperationKind> {
+    UNARY_OPERATIONS.get(code as usize).cloned()
+}
+
+fn binary_code(operation: &BoundBinaryOperationKind) -> u32 {
+    match operation {
+        BoundBinaryOperationKind::Addition => 0,
+        BoundBinaryOperationKind::Subtraction => 1,
+        BoundBinaryOperationKind::Multiplication => 2,
+        BoundBinaryOperationKind::Division => 3,
+        BoundBinaryOperationKind::Greater => 4,
+        BoundBinaryOperationKind::Lesser => 5,
+        BoundBinaryOperationKind::GreaterEqual => 6,
+        BoundBinaryOperationKind::LesserEqual => 7,
+        BoundBinaryOperationKind::LogicalAnd => 8,
+        BoundBinaryOperationKind::LogicalOr => 9,
+        BoundBinaryOperationKind::NotEqual => 10,
+        BoundBinaryOperationKind::Equal => 11,
+    }
+}
+
+fn binary_operation(code: u32) -> Option<BoundBinaryOperationKind> {
+    BINARY_OPERATIONS.get(code as usize).cloned()
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend(value.as_bytes());
+    }
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    offset: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn new(bytes: &'b [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ArtifactError> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + N)
+            .ok_or(ArtifactError::Corrupted)?;
+        self.offset += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, ArtifactError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ArtifactError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, ArtifactError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, ArtifactError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn string(&mut self) -> Result<&'b str, ArtifactError> {
+        let length = self.u32()? as usize;
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + length)
+            .ok_or(ArtifactError::Corrupted)?;
+        self.offset += length;
+        std::str::from_utf8(bytes).map_err(|_| ArtifactError::Corrupted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(instructions: Vec<Instruction>) -> Vec<u8> {
+        let mut chunk = Chunk::default();
+        chunk.add_constant(Object::Number(1));
+        for instruction in instructions {
+            chunk.emit(instruction, Position { start: 0, end: 0 });
+        }
+        Artifact {
+            source_hash: 7,
+            optimized: false,
+            chunk,
+        }
+        .encode(&Interner::default())
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Artifact, ArtifactError> {
+        Artifact::decode(bytes, &mut Interner::default())
+    }
+
+    #[test]
+    fn well_formed_chunks_round_trip() {
+        let bytes = artifact(vec![
+            Instruction::Constant(0),
+            Instruction::GetLocal(0),
+            Instruction::SetLocal(0),
+            Instruction::Pop,
+            Instruction::Print,
+            Instruction::Constant(0),
+            Instruction::Return,
+        ]);
+        let artifact = decode(&bytes).unwrap();
+        assert_eq!(artifact.source_hash, 7);
+        assert_eq!(artifact.chunk.instructions.len(), 7);
+    }
+
+    #[test]
+    fn locals_above_the_stack_are_rejected() {
+        for instruction in [Instruction::GetLocal(1), Instruction::SetLocal(3)] {
+            let bytes = artifact(vec![
+                Instruction::Constant(0),
+                instruction,
+                Instruction::Print,
+                Instruction::Return,
+            ]);
+            assert!(matches!(decode(&bytes), Err(ArtifactError::Corrupted)));
+        }
+    }
+
+    #[test]
+    fn unbalanced_stacks_and_runaway_paths_are_rejected() {
+        for instructions in [
+            vec![Instruction::Print, Instruction::Return],
+            vec![Instruction::Constant(0), Instruction::Print],
+            vec![Instruction::Jump(5), Instruction::Return],
+            vec![
+                Instruction::Constant(0),
+                Instruction::Constant(0),
+                Instruction::JumpIfFalse(4),
+                Instruction::Constant(0),
+                Instruction::Pop,
+                Instruction::Pop,
+                Instruction::Return,
+            ],
+        ] {
+            let bytes = artifact(instructions);
+            assert!(matches!(decode(&bytes), Err(ArtifactError::Corrupted)));
+        }
+    }
+
+    #[test]
+    fn artifacts_of_another_compiler_are_rejected() {
+        let bytes = artifact(vec![Instruction::Constant(0), Instruction::Return]);
+        let mut other = bytes[..MAGIC.len() + 2].to_vec();
+        let mut writer = Writer::default();
+        writer.string("crabi 0.0.0");
+        other.extend(writer.bytes);
+        other.extend(&bytes[MAGIC.len() + 2 + 4 + COMPILER.len()..bytes.len() - 8]);
+        let checksum = hash(&other);
+        other.extend(checksum.to_le_bytes());
+        assert!(matches!(
+            decode(&other),
+            Err(ArtifactError::OtherCompiler(compiler)) if compiler == "crabi 0.0.0"
+        ));
+    }
+}
@@ -0,0 +1,111 @@
+use crab::{
+    binding::bound_tree::{BoundBinaryOperationKind, BoundUnaryOperationKind},
+    common::{
+        diagnostic::Position,
+        intern::{Interner, Symbol},
+        types::Object,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    Constant(usize),
+    Pop,
+
+    DefineGlobal(Symbol),
+    GetGlobal(Symbol),
+    SetGlobal(Symbol),
+    GetLocal(usize),
+    SetLocal(usize),
+
+    Unary(BoundUnaryOperationKind),
+    Binary(BoundBinaryOperationKind),
+
+    Print,
+    Jump(usize),
+    JumpIfFalse(usize),
+    Return,
+}
+
+/// A compiled program: instructions with the source position each one came from, and the
+/// constant pool that `Constant` indexes into.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub(crate) instructions: Vec<Instruction>,
+    pub(crate) positions: Vec<Position>,
+    pub(crate) constants: Vec<Object>,
+}
+
+impl Chunk {
+    pub(crate) fn emit(&mut self, instruction: Instruction, position: Position) -> usize {
+        self.instructions.push(instruction);
+        self.positions.push(position);
+        self.instructions.len() - 1
+    }
+
+    pub(crate) fn add_constant(&mut self, object: Object) -> usize {
+        if let Some(index) = self
+            .constants
+            .iter()
+            .position(|constant| *constant == object)
+        {
+            index
+        } else {
+            self.constants.push(object);
+            self.constants.len() - 1
+        }
+    }
+
+    pub(crate) fn patch_jump(&mut self, jump: usize) {
+        let target = self.instructions.len();
+        match &mut self.instructions[jump] {
+            Instruction::Jump(offset) | Instruction::JumpIfFalse(offset) => *offset = target,
+            instruction => unreachable!("Can not patch {instruction:?}"),
+        }
+    }
+
+    /// Lists the constant pool and one line per instruction, with the byte range of the source
+    /// it came from.
+    pub(crate) fn disassemble(&self, interner: &Interner) -> String {
+        let mut listing = String::from("constants:\n");
+        for (index, constant) in self.constants.iter().enumerate() {
+            listing.push_str(&format!("    {index:>4}  {}\n", quote(constant)));
+        }
+        listing.push_str("code:\n");
+        for (offset, (instruction, position)) in
+            self.instructions.iter().zip(&self.positions).enumerate()
+        {
+            let text = match instruction {
+                Instruction::Constant(index) => {
+                    format!("constant {index} ({})", quote(&self.constants[*index]))
+                }
+                Instruction::Pop => "pop".to_string(),
+                Instruction::DefineGlobal(name) => {
+                    format!("define_global {}", interner.resolve(*name))
+                }
+                Instruction::GetGlobal(name) => format!("get_global {}", interner.resolve(*name)),
+                Instruction::SetGlobal(name) => format!("set_global {}", interner.resolve(*name)),
+                Instruction::GetLocal(slot) => format!("get_local {slot}"),
+                Instruction::SetLocal(slot) => format!("set_local {slot}"),
+                Instruction::Unary(operator) => format!("unary {operator}"),
+                Instruction::Binary(operator) => format!("binary {operator}"),
+                Instruction::Print => "print".to_string(),
+                Instruction::Jump(target) => format!("jump {target:04}"),
+                Instruction::JumpIfFalse(target) => format!("jump_if_false {target:04}"),
+                Instruction::Return => "return".to_string(),
+            };
+            listing.push_str(&format!(
+                "    {offset:04}  {text:<32} ; {}..{}\n",
+                position.start, position.end
+            ));
+        }
+        listing
+    }
+}
+
+fn quote(object: &Object) -> String {
+    match object {
+        Object::String(string) => format!("{string:?}"),
+        object => object.to_string(),
+    }
+}
@@ -0,0 +1,264 @@
+use crab::{
+    binding::{
+        bound_tree::{
+            BoundBinaryOperationKind, BoundExpression, BoundExpressionId, BoundForStatement,
+            BoundProgram, BoundStatement, BoundStatementId,
+        },
+        symbol::VariableSymbol,
+    },
+    common::{diagnostic::Position, types::Object},
+};
+
+use super::bytecode::{Chunk, Instruction};
+
+/// A stack slot. Hidden locals, like the counter of a `for` loop, have no symbol.
+struct Local {
+    id: Option<usize>,
+    depth: usize,
+}
+
+/// Compiles a bound program to bytecode. Top-level variables become globals looked up by symbol,
+/// so an interactive session can keep them between chunks; everything declared in a nested scope
+/// lives in a slot of the value stack.
+pub struct BytecodeCompiler<'a> {
+    program: &'a BoundProgram,
+    chunk: Chunk,
+    locals: Vec<Local>,
+    scope_depth: usize,
+}
+
+impl<'a> BytecodeCompiler<'a> {
+    pub fn new(program: &'a BoundProgram) -> Self {
+        Self {
+            program,
+            chunk: Chunk::default(),
+            locals: Vec::new(),
+            scope_depth: 0,
+        }
+    }
+
+    pub fn compile(mut self) -> Chunk {
+        match self.program.root().split_last() {
+            Some((&last, statements)) => {
+                for &statement in statements {
+                    self.compile_statement(statement);
+                }
+                self.compile_result(last);
+            }
+            None => {
+                let unit = self.chunk.add_constant(Object::Unit);
+                self.chunk
+                    .emit(Instruction::Constant(unit), Position::default());
+                self.chunk.emit(Instruction::Return, Position::default());
+            }
+        }
+        self.chunk
+    }
+
+    fn compile_result(&mut self, id: BoundStatementId) {
+        let statement = self.program.statement(id);
+        let position = statement.get_position();
+        match statement {
+            BoundStatement::Expression(statement) => self.compile_expression(statement.expression),
+            BoundStatement::Var(var_statement) => {
+                self.compile_statement(id);
+                self.chunk.emit(
+                    Instruction::GetGlobal(var_statement.variable.name),
+                    position.clone(),
+                );
+            }
+            _ => {
+                self.compile_statement(id);
+                let unit = self.chunk.add_constant(Object::Unit);
+                self.chunk
+                    .emit(Instruction::Constant(unit), position.clone());
+            }
+        }
+        self.chunk.emit(Instruction::Return, position);
+    }
+
+    fn compile_statement(&mut self, statement: BoundStatementId) {
+        let statement = self.program.statement(statement);
+        let position = statement.get_position();
+        match statement {
+            BoundStatement::Expression(statement) => {
+                self.compile_expression(statement.expression);
+                self.chunk.emit(Instruction::Pop, position);
+            }
+            BoundStatement::Print(statement) => {
+                self.compile_expression(statement.expression);
+                self.chunk.emit(Instruction::Print, position);
+            }
+            BoundStatement::Var(statement) => {
+                self.compile_expression(statement.expression);
+                self.declare(Some(&statement.variable), position);
+            }
+            BoundStatement::Block(statement) => {
+                self.begin_scope();
+                for &statement in &statement.statements {
+                    self.compile_statement(statement);
+                }
+                self.end_scope(position);
+            }
+            BoundStatement::If(statement) => {
+                self.compile_expression(statement.condition);
+                let else_jump = self
+                    .chunk
+                    .emit(Instruction::JumpIfFalse(0), position.clone());
+                self.compile_nested(statement.consequence);
+                match statement.else_clause {
+                    Some(else_clause) => {
+                        let end_jump = self.chunk.emit(Instruction::Jump(0), position);
+                        self.chunk.patch_jump(else_jump);
+                        self.compile_nested(else_clause);
+                        self.chunk.patch_jump(end_jump);
+                    }
+                    None => self.chunk.patch_jump(else_jump),
+                }
+            }
+            BoundStatement::While(statement) => {
+                let head = self.chunk.instructions.len();
+                self.compile_expression(statement.condition);
+                let exit_jump = self
+                    .chunk
+                    .emit(Instruction::JumpIfFalse(0), position.clone());
+                self.compile_nested(statement.body);
+                self.chunk.emit(Instruction::Jump(head), position);
+                self.chunk.patch_jump(exit_jump);
+            }
+            BoundStatement::For(statement) => self.compile_for_statement(statement),
+        }
+    }
+
+    /// Mirrors the tree-walking evaluator: the bounds are evaluated once into hidden locals,
+    /// and after each iteration the counter is reloaded from the loop variable, so a body that
+    /// assigns to the loop variable moves the loop along with it.
+    fn compile_for_statement(&mut self, statement: &BoundForStatement) {
+        let position = statement.get_position();
+        self.begin_scope();
+        self.compile_expression(statement.lower_bound);
+        let counter = self.declare(None, position.clone());
+        self.compile_expression(statement.upper_bound);
+        let upper_bound = self.declare(None, position.clone());
+        self.chunk
+            .emit(Instruction::GetLocal(counter), position.clone());
+        let variable = self.declare(Some(&statement.variable), position.clone());
+
+        let head = self.chunk.instructions.len();
+        self.chunk
+            .emit(Instruction::GetLocal(counter), position.clone());
+        self.chunk
+            .emit(Instruction::GetLocal(upper_bound), position.clone());
+        self.chunk.emit(
+            Instruction::Binary(BoundBinaryOperationKind::Lesser),
+            position.clone(),
+        );
+        let exit_jump = self
+            .chunk
+            .emit(Instruction::JumpIfFalse(0), position.clone());
+        self.compile_nested(statement.body);
+
+        let one = self.chunk.add_constant(Object::Number(1));
+        for instruction in [
+            Instruction::GetLocal(variable),
+            Instruction::SetLocal(counter),
+            Instruction::Pop,
+            Instruction::GetLocal(counter),
+            Instruction::Constant(one),
+            Instruction::Binary(BoundBinaryOperationKind::Addition),
+            Instruction::SetLocal(variable),
+            Instruction::Pop,
+            Instruction::Jump(head),
+        ] {
+            self.chunk.emit(instruction, position.clone());
+        }
+        self.chunk.patch_jump(exit_jump);
+        self.end_scope(position);
+    }
+
+    fn compile_nested(&mut self, statement: BoundStatementId) {
+        self.begin_scope();
+        self.compile_statement(statement);
+        self.end_scope(self.program.statement(statement).get_position());
+    }
+
+    /// The last instruction of an expression carries its position, which the VM relies on to
+    /// point a division by zero at the divisor.
+    fn compile_expression(&mut self, expression: BoundExpressionId) {
+        let expression = self.program.expression(expression);
+        let position = expression.get_position();
+        match expression {
+            BoundExpression::Literal(expression) => {
+                let constant = self.chunk.add_constant(expression.value.clone());
+                self.chunk.emit(Instruction::Constant(constant), position);
+            }
+            BoundExpression::Variable(expression) => {
+                let instruction = match self.resolve_local(&expression.variable) {
+                    Some(slot) => Instruction::GetLocal(slot),
+                    None => Instruction::GetGlobal(expression.variable.name),
+                };
+                self.chunk.emit(instruction, position);
+            }
+            BoundExpression::Unary(expression) => {
+                self.compile_expression(expression.right);
+                self.chunk.emit(
+                    Instruction::Unary(expression.operator.operation_kind.clone()),
+                    position,
+                );
+            }
+            BoundExpression::Binary(expression) => {
+                self.compile_expression(expression.left);
+                self.compile_expression(expression.right);
+                self.chunk.emit(
+                    Instruction::Binary(expression.operator.operation_kind.clone()),
+                    position,
+                );
+            }
+            BoundExpression::Assignment(expression) => {
+                self.compile_expression(expression.expression);
+                let instruction = match self.resolve_local(&expression.variable) {
+                    Some(slot) => Instruction::SetLocal(slot),
+                    None => Instruction::SetGlobal(expression.variable.name),
+                };
+                self.chunk.emit(instruction, position);
+            }
+        }
+    }
+
+    fn declare(&mut self, variable: Option<&VariableSymbol>, position: Position) -> usize {
+        match variable {
+            Some(variable) if self.scope_depth == 0 => self
+                .chunk
+                .emit(Instruction::DefineGlobal(variable.name), position),
+            _ => {
+                self.locals.push(Local {
+                    id: variable.map(|variable| variable.id),
+                    depth: self.scope_depth,
+                });
+                self.locals.len() - 1
+            }
+        }
+    }
+
+    fn resolve_local(&self, variable: &VariableSymbol) -> Option<usize> {
+        self.locals
+            .iter()
+            .rposition(|local| local.id == Some(variable.id))
+    }
+
+    fn begin_scope(&mut self) {
+        self.scope_depth += 1;
+    }
+
+    fn end_scope(&mut self, position: Position) {
+        self.scope_depth -= 1;
+        while self
+            .locals
+            .last()
+            .is_some_and(|local| local.depth > self.scope_depth)
+        {
+            self.locals.pop();
+            self.chunk.emit(Instruction::Pop, position.clone());
+        }
+    }
+}
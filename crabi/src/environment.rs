@@ -1,42 +1,39 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use crab::{binding::symbol::VariableSymbol, common::types::Object};
 
-use crab::common::types::Object;
-
-#[derive(Debug, Default)]
-pub(crate) struct Environment {
-    pub(crate) outer: Option<Rc<RefCell<Environment>>>,
-    bindings: HashMap<String, Object>,
+/// One frame per scope the evaluator is currently in, indexed by a symbol's depth; each frame
+/// holds its scope's variables by slot. The global frame is never popped, so an interactive
+/// session can keep its environment between chunks.
+#[derive(Debug)]
+pub struct Environment {
+    frames: Vec<Vec<Option<Object>>>,
 }
 
-impl Environment {
-    pub(crate) fn extend(with: Rc<RefCell<Environment>>) -> Self {
+impl Default for Environment {
+    fn default() -> Self {
         Self {
-            outer: Some(with),
-            bindings: HashMap::new(),
+            frames: vec![Vec::new()],
         }
     }
+}
 
-    pub(crate) fn get(&self, name: &str) -> Option<Object> {
-        if let Some(object) = self.bindings.get(name) {
-            Some(object.clone())
-        } else if let Some(outer) = &self.outer {
-            outer.borrow().get(name)
-        } else {
-            None
-        }
+impl Environment {
+    pub(crate) fn push_frame(&mut self) {
+        self.frames.push(Vec::new());
     }
 
-    pub(crate) fn reset(&mut self, name: String, object: Object) {
-        if self.bindings.get(&name).is_some() {
-            self.bindings.insert(name, object);
-        } else if let Some(outer) = &self.outer {
-            outer.borrow_mut().reset(name, object)
-        } else {
-            return;
-        }
+    pub(crate) fn pop_frame(&mut self) {
+        self.frames.pop();
+    }
+
+    pub(crate) fn get(&self, variable: &VariableSymbol) -> Option<Object> {
+        self.frames.get(variable.depth)?.get(variable.slot)?.clone()
     }
 
-    pub(crate) fn set(&mut self, name: String, object: Object) {
-        self.bindings.insert(name, object);
+    pub(crate) fn set(&mut self, variable: &VariableSymbol, object: Object) {
+        let frame = &mut self.frames[variable.depth];
+        if frame.len() <= variable.slot {
+            frame.resize(variable.slot + 1, None);
+        }
+        frame[variable.slot] = Some(object);
     }
 }
@@ -1,182 +1,190 @@
-use std::{cell::RefCell, rc::Rc};
-
 use crab::{
     binding::bound_tree::{
-        BoundBinaryOperationKind, BoundExpression, BoundStatement, BoundUnaryOperationKind,
+        BoundExpression, BoundExpressionId, BoundForStatement, BoundProgram, BoundStatement,
+        BoundStatementId,
     },
-    common::types::{Object, Type},
+    common::{arithmetic::add, intern::Interner, types::Object},
 };
 
-use super::environment::Environment;
+use super::{
+    environment::Environment,
+    operations::{binary_operation, expect_boolean, expect_number, unary_operation},
+    runtime_error::RuntimeError,
+};
 
-pub(crate) struct Evaluator {
-    bound_statements: Vec<BoundStatement>,
-    pub(super) bindings: Rc<RefCell<Environment>>,
+/// Walks the bound program in place; nothing in it is cloned while it runs, so the cost of a
+/// loop iteration depends on the statements it executes, not on how large its body is.
+///
+/// The evaluator recurses once per level of nesting in the program, which the parser keeps
+/// below the limit in `CompileOptions`, and crabi runs it on a stack sized for that limit.
+/// Crab has no functions yet, so there are no calls to eliminate or count; tail calls and a
+/// call depth limit belong with the call frames functions will bring, in this evaluator, the
+/// virtual machine and crabtgo alike.
+pub struct Evaluator<'a> {
+    program: &'a BoundProgram,
+    environment: &'a mut Environment,
+    interner: &'a Interner,
 }
 
-impl Evaluator {
-    pub(crate) fn new(
-        bound_statements: Vec<BoundStatement>,
-        bindings: Rc<RefCell<Environment>>,
+impl<'a> Evaluator<'a> {
+    pub fn new(
+        program: &'a BoundProgram,
+        environment: &'a mut Environment,
+        interner: &'a Interner,
     ) -> Self {
         Self {
-            bound_statements,
-            bindings,
+            program,
+            environment,
+            interner,
         }
     }
 
-    pub(crate) fn evaluate(&mut self) -> Object {
+    pub fn evaluate(&mut self) -> Result<Object, RuntimeError> {
         let mut object = Object::Unit;
-        for statement in self.bound_statements.clone() {
-            object = self.evaluate_statement(statement);
+        for &statement in self.program.root() {
+            object = self.evaluate_statement(statement)?;
         }
-        object
+        Ok(object)
     }
 
-    fn evaluate_statement(&mut self, statement: BoundStatement) -> Object {
-        match statement {
-            BoundStatement::Expression(statement) => {
-                self.evaluate_expression(&statement.expression)
-            }
+    fn evaluate_statement(&mut self, statement: BoundStatementId) -> Result<Object, RuntimeError> {
+        match self.program.statement(statement) {
+            BoundStatement::Expression(statement) => self.evaluate_expression(statement.expression),
             BoundStatement::Print(statement) => {
-                println!("{}", self.evaluate_expression(&statement.expression));
-                Object::Unit
+                println!("{}", self.evaluate_expression(statement.expression)?);
+                Ok(Object::Unit)
             }
             BoundStatement::Var(statement) => {
-                let object = self.evaluate_expression(&statement.expression);
-                self.bindings
-                    .borrow_mut()
-                    .set(statement.name, object.clone());
-                object
+                let object = self.evaluate_expression(statement.expression)?;
+                self.environment.set(&statement.variable, object.clone());
+                Ok(object)
             }
             BoundStatement::Block(statement) => {
-                self.bindings =
-                    Rc::new(RefCell::new(Environment::extend(Rc::clone(&self.bindings))));
-                for statement in statement.statements {
-                    self.evaluate_statement(statement);
-                }
-                let old_bindings = self
-                    .bindings
-                    .borrow()
-                    .outer
-                    .clone()
-                    .unwrap_or(Rc::new(RefCell::new(Environment::default())));
-                self.bindings = old_bindings;
-                Object::Unit
+                self.environment.push_frame();
+                let result = statement
+                    .statements
+                    .iter()
+                    .try_for_each(|&statement| self.evaluate_statement(statement).map(|_| ()));
+                self.environment.pop_frame();
+                result.map(|_| Object::Unit)
             }
             BoundStatement::If(statement) => {
-                if self.evaluate_expression(&statement.condition).as_boolean() {
-                    self.evaluate_statement(*statement.consequence)
+                if self.evaluate_condition(statement.condition)? {
+                    self.evaluate_nested_statement(statement.consequence)
                 } else {
-                    match *statement.else_clause {
-                        Some(statement) => self.evaluate_statement(statement),
-                        None => Object::Unit,
+                    match statement.else_clause {
+                        Some(statement) => self.evaluate_nested_statement(statement),
+                        None => Ok(Object::Unit),
                     }
                 }
             }
             BoundStatement::While(statement) => {
-                loop {
-                    let value = self.evaluate_expression(&statement.condition).as_boolean();
-                    if !value {
-                        break;
-                    }
-                    self.evaluate_statement(*statement.body.clone());
+                while self.evaluate_condition(statement.condition)? {
+                    self.evaluate_nested_statement(statement.body)?;
                 }
-                Object::Unit
+                Ok(Object::Unit)
             }
             BoundStatement::For(statement) => {
-                let mut lower_bound = self.evaluate_expression(&statement.lower_bound).as_number();
-                let upper_bound = self.evaluate_expression(&statement.upper_bound).as_number();
-                self.bindings
-                    .borrow_mut()
-                    .set(statement.identifier.clone(), Object::Number(lower_bound));
-                while lower_bound < upper_bound {
-                    self.evaluate_statement(*statement.body.clone());
-                    lower_bound = self
-                        .bindings
-                        .borrow()
-                        .get(&statement.identifier)
-                        .unwrap()
-                        .as_number();
-                    self.bindings.borrow_mut().reset(
-                        statement.identifier.clone(),
-                        Object::Number(lower_bound + 1),
-                    );
-                }
-                Object::Unit
+                let lower_bound = self.evaluate_number(statement.lower_bound)?;
+                let upper_bound = self.evaluate_number(statement.upper_bound)?;
+                self.environment.push_frame();
+                self.environment
+                    .set(&statement.variable, Object::Number(lower_bound));
+                let result = self.evaluate_for_loop(statement, lower_bound, upper_bound);
+                self.environment.pop_frame();
+                result
             }
         }
     }
 
-    fn evaluate_expression(&mut self, bound_expression: &BoundExpression) -> Object {
+    /// The binder gives the body of an `if` or `while` a scope of its own, so it gets a frame.
+    fn evaluate_nested_statement(
+        &mut self,
+        statement: BoundStatementId,
+    ) -> Result<Object, RuntimeError> {
+        if let BoundStatement::Block(_) = self.program.statement(statement) {
+            return self.evaluate_statement(statement);
+        }
+        self.environment.push_frame();
+        let result = self.evaluate_statement(statement);
+        self.environment.pop_frame();
+        result
+    }
+
+    fn evaluate_for_loop(
+        &mut self,
+        statement: &BoundForStatement,
+        mut lower_bound: i32,
+        upper_bound: i32,
+    ) -> Result<Object, RuntimeError> {
+        let position = statement.get_position();
+        while lower_bound < upper_bound {
+            self.evaluate_statement(statement.body)?;
+            let object = self.environment.get(&statement.variable).ok_or_else(|| {
+                RuntimeError::undefined_name(
+                    position.clone(),
+                    self.interner.resolve(statement.variable.name),
+                )
+            })?;
+            lower_bound = expect_number(object, &position)?;
+            let next = add(lower_bound, 1)
+                .map_err(|error| RuntimeError::arithmetic(position.clone(), error, "+"))?;
+            self.environment
+                .set(&statement.variable, Object::Number(next));
+        }
+        Ok(Object::Unit)
+    }
+
+    fn evaluate_condition(&mut self, expression: BoundExpressionId) -> Result<bool, RuntimeError> {
+        let object = self.evaluate_expression(expression)?;
+        expect_boolean(object, &self.program.expression(expression).get_position())
+    }
+
+    fn evaluate_number(&mut self, expression: BoundExpressionId) -> Result<i32, RuntimeError> {
+        let object = self.evaluate_expression(expression)?;
+        expect_number(object, &self.program.expression(expression).get_position())
+    }
+
+    fn evaluate_expression(
+        &mut self,
+        expression: BoundExpressionId,
+    ) -> Result<Object, RuntimeError> {
+        let bound_expression = self.program.expression(expression);
+        let position = bound_expression.get_position();
         match bound_expression {
-            BoundExpression::Literal(expression) => expression.value.clone(),
+            BoundExpression::Literal(expression) => Ok(expression.value.clone()),
 
             BoundExpression::Variable(expression) => {
-                self.bindings.borrow().get(&expression.name).unwrap()
+                self.environment.get(&expression.variable).ok_or_else(|| {
+                    RuntimeError::undefined_name(
+                        position,
+                        self.interner.resolve(expression.variable.name),
+                    )
+                })
             }
 
             BoundExpression::Unary(expression) => {
-                let right = self.evaluate_expression(&expression.right);
-                match expression.operator.operation_kind {
-                    BoundUnaryOperationKind::Identity => Object::Number(right.as_number()),
-                    BoundUnaryOperationKind::Negation => Object::Number(-right.as_number()),
-                    BoundUnaryOperationKind::LogicalNegation => {
-                        Object::Boolean(!right.as_boolean())
-                    }
-                }
+                let right = self.evaluate_expression(expression.right)?;
+                unary_operation(&expression.operator.operation_kind, right, &position)
             }
 
             BoundExpression::Binary(expression) => {
-                let left = self.evaluate_expression(&expression.left);
-                let right = self.evaluate_expression(&expression.right);
-                match expression.operator.operation_kind {
-                    BoundBinaryOperationKind::Addition => match left.get_type() {
-                        Type::Number => Object::Number(left.as_number() + right.as_number()),
-                        Type::String => Object::String(format!("{}{}", left, right)),
-                        _ => unreachable!("Invalid Operation/Operand."),
-                    },
-                    BoundBinaryOperationKind::Subtraction => {
-                        Object::Number(left.as_number() - right.as_number())
-                    }
-                    BoundBinaryOperationKind::Multiplication => {
-                        Object::Number(left.as_number() * right.as_number())
-                    }
-                    BoundBinaryOperationKind::Division => {
-                        Object::Number(left.as_number() / right.as_number())
-                    }
-
-                    BoundBinaryOperationKind::Greater => {
-                        Object::Boolean(left.as_number() > right.as_number())
-                    }
-                    BoundBinaryOperationKind::Lesser => {
-                        Object::Boolean(left.as_number() < right.as_number())
-                    }
-                    BoundBinaryOperationKind::GreaterEqual => {
-                        Object::Boolean(left.as_number() >= right.as_number())
-                    }
-                    BoundBinaryOperationKind::LesserEqual => {
-                        Object::Boolean(left.as_number() <= right.as_number())
-                    }
-
-                    BoundBinaryOperationKind::LogicalAnd => {
-                        Object::Boolean(left.as_boolean() && right.as_boolean())
-                    }
-                    BoundBinaryOperationKind::LogicalOr => {
-                        Object::Boolean(left.as_boolean() || right.as_boolean())
-                    }
-
-                    BoundBinaryOperationKind::NotEqual => Object::Boolean(left != right),
-                    BoundBinaryOperationKind::Equal => Object::Boolean(left == right),
-                }
+                let left = self.evaluate_expression(expression.left)?;
+                let right = self.evaluate_expression(expression.right)?;
+                let right_position = self.program.expression(expression.right).get_position();
+                binary_operation(
+                    &expression.operator.operation_kind,
+                    left,
+                    right,
+                    &position,
+                    &right_position,
+                )
             }
 
             BoundExpression::Assignment(expression) => {
-                let object = self.evaluate_expression(&expression.expression);
-                self.bindings
-                    .borrow_mut()
-                    .reset(expression.name.clone(), object.clone());
-                object
+                let object = self.evaluate_expression(expression.expression)?;
+                self.environment.set(&expression.variable, object.clone());
+                Ok(object)
             }
         }
     }
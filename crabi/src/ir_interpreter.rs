@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use crab::{
+    common::{intern::Symbol, types::Object},
+    ir::function::{Function, Instruction, Operand, Terminator, Variable},
+};
+
+use super::{
+    operations::{binary_operation, unary_operation},
+    runtime_error::RuntimeError,
+};
+
+/// Runs a program lowered to the IR. Top-level variables are kept by name, so they outlive the
+/// run like the globals of the virtual machine; every other variable lives for one run only.
+#[derive(Default)]
+pub struct IrInterpreter {
+    globals: HashMap<Symbol, Object>,
+}
+
+impl IrInterpreter {
+    pub fn run(&mut self, function: &Function) -> Result<Object, RuntimeError> {
+        let mut temps = vec![Object::Unit; function.temps() as usize];
+        let mut locals = vec![Object::Unit; function.variables.len()];
+        let operand = |temps: &[Object], operand: &Operand| match operand {
+            Operand::Constant(value) => value.clone(),
+            Operand::Temp(temp) => temps[temp.0 as usize].clone(),
+        };
+        let global = |variable: Variable| {
+            function
+                .variable(variable)
+                .symbol
+                .as_ref()
+                .filter(|symbol| symbol.depth == 0)
+                .map(|symbol| symbol.name)
+        };
+
+        let mut block = Function::ENTRY;
+        loop {
+            for instruction in &function.blocks[block].instructions {
+                match instruction {
+                    Instruction::Copy {
+                        destination,
+                        source,
+                    } => temps[destination.0 as usize] = operand(&temps, source),
+                    Instruction::Unary {
+                        destination,
+                        operation,
+                        operand: right,
+                        position,
+                    } => {
+                        let right = operand(&temps, right);
+                        temps[destination.0 as usize] =
+                            unary_operation(operation, right, position)?;
+                    }
+                    Instruction::Binary {
+                        destination,
+                        operation,
+                        left,
+                        right,
+                        position,
+                        right_position,
+                    } => {
+                        let (left, right) = (operand(&temps, left), operand(&temps, right));
+                        temps[destination.0 as usize] =
+                            binary_operation(operation, left, right, position, right_position)?;
+                    }
+                    Instruction::Load {
+                        destination,
+                        variable,
+                    } => {
+                        temps[destination.0 as usize] = match global(*variable) {
+                            Some(name) => self.globals.get(&name).cloned().unwrap_or(Object::Unit),
+                            None => locals[variable.0 as usize].clone(),
+                        }
+                    }
+                    Instruction::Store { variable, value } => {
+                        let value = operand(&temps, value);
+                        match global(*variable) {
+                            Some(name) => {
+                                self.globals.insert(name, value);
+                            }
+                            None => locals[variable.0 as usize] = value,
+                        }
+                    }
+                    Instruction::Print { value } => println!("{}", operand(&temps, value)),
+                }
+            }
+            match &function.blocks[block].terminator {
+                Terminator::Jump(target) => block = *target,
+                Terminator::Branch {
+                    condition,
+                    when_true,
+                    when_false,
+                } => {
+                    block = match operand(&temps, condition) {
+                        Object::Boolean(true) => *when_true,
+                        _ => *when_false,
+                    }
+                }
+                Terminator::Return(value) => return Ok(operand(&temps, value)),
+            }
+        }
+    }
+}
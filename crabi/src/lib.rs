@@ -0,0 +1,18 @@
+mod artifact;
+mod bytecode;
+mod bytecode_compiler;
+mod environment;
+mod evaluator;
+mod ir_interpreter;
+mod operations;
+mod runtime_error;
+mod vm;
+
+pub use artifact::{hash, path_for, Artifact, ArtifactError};
+pub use bytecode::Chunk;
+pub use bytecode_compiler::BytecodeCompiler;
+pub use environment::Environment;
+pub use evaluator::Evaluator;
+pub use ir_interpreter::IrInterpreter;
+pub use runtime_error::RuntimeError;
+pub use vm::VirtualMachine;
@@ -1,38 +1,190 @@
-mod environment;
-mod evaluator;
-
 use std::{
     cell::RefCell,
     env::args,
-    fs::read_to_string,
+    fs::{read, read_to_string, write},
     io::{stdin, stdout, Write},
+    path::Path,
     process::exit,
     rc::Rc,
+    thread::Builder,
 };
 
 use colored::Colorize;
 
-use crate::{environment::Environment, evaluator::Evaluator};
-use crab::{binding::bindings::Bindings, compilation::Compilation};
+use crab::{
+    backend::{Backend, BackendError, Input, Registry},
+    binding::bindings::Bindings,
+    common::{
+        intern::Interner,
+        report::{report_diagnostics, report_runtime_error},
+    },
+    compilation::{Compilation, CompileOptions},
+};
+use crabi::{
+    hash, path_for, Artifact, BytecodeCompiler, Chunk, Environment, Evaluator, IrInterpreter,
+    VirtualMachine,
+};
+use crabtc::CBackend;
+use crabtgo::{GoBackend, GoOptions};
+
+#[derive(Clone, Copy)]
+enum Engine {
+    Tree,
+    Vm,
+    Ir,
+}
+
+/// Runs programs on one of the engines, which is what crabi does unless another backend is
+/// selected with `--target`.
+struct Interpreter {
+    engine: Engine,
+    /// Whether the VM writes compiled programs to a `.crabc` file beside their source.
+    cache: bool,
+}
+
+impl Backend for Interpreter {
+    fn name(&self) -> &'static str {
+        "interp"
+    }
+
+    fn run(&self, input: &Input) -> Result<(), BackendError> {
+        let compilation = input.compilation;
+        let interner = compilation.interner.borrow();
+        let result = match self.engine {
+            Engine::Tree => Evaluator::new(
+                &compilation.bound_program,
+                &mut Environment::default(),
+                &interner,
+            )
+            .evaluate(),
+            Engine::Vm => {
+                let chunk = BytecodeCompiler::new(&compilation.bound_program).compile();
+                let artifact = Artifact {
+                    source_hash: hash(input.source.as_bytes()),
+                    optimized: input.optimized,
+                    chunk,
+                };
+                // A cached run reports nothing, so only programs without warnings are cached.
+                // The cache is an optimization: failing to write it is not an error.
+                if self.cache && compilation.diagnostic_bag.borrow().diagnostics.is_empty() {
+                    let _ = write(path_for(input.path), artifact.encode(&interner));
+                }
+                VirtualMachine::default().run(&artifact.chunk, &interner)
+            }
+            Engine::Ir => IrInterpreter::default().run(&compilation.lower(input.optimized)),
+        };
+        result.map(|_| ()).map_err(|error| {
+            report_runtime_error(input.source, &error.position, &error.message);
+            BackendError { exit_code: 70 }
+        })
+    }
+}
+
+/// The backends `--target` selects from.
+fn registry(engine: Engine, cache: bool) -> Registry {
+    let mut registry = Registry::default();
+    registry.register(Box::new(Interpreter { engine, cache }));
+    registry.register(Box::new(GoBackend::new(GoOptions::default())));
+    registry.register(Box::new(CBackend));
+    registry
+}
 
 fn main() {
-    let args: Vec<String> = args().collect();
-    match args.len() {
-        1 => run_repl(),
-        2 => run_file(&args[1]),
-        _ => {
-            eprintln!("Invalid number of arguments.");
+    let mut engine = Engine::Tree;
+    let mut target = "interp".to_string();
+    let mut optimize = false;
+    let mut dump_ir = false;
+    let mut disassemble = false;
+    let mut cache = true;
+    let mut options = CompileOptions::default();
+    let mut paths = vec![];
+    for arg in args().skip(1) {
+        if arg == "-O" {
+            optimize = true;
+        } else if arg == "--dump-ir" {
+            dump_ir = true;
+        } else if arg == "--disassemble" {
+            disassemble = true;
+        } else if arg == "--no-cache" {
+            cache = false;
+        } else if let Some(limit) = arg.strip_prefix("--max-depth=") {
+            options.max_depth = match limit.parse() {
+                Ok(limit) if limit > 0 => limit,
+                _ => {
+                    eprintln!("Invalid nesting limit '{limit}', expected a positive number.");
+                    exit(65);
+                }
+            };
+        } else if let Some(name) = arg.strip_prefix("--target=") {
+            target = name.to_string();
+        } else if let Some(name) = arg.strip_prefix("--engine=") {
+            engine = match name {
+                "tree" => Engine::Tree,
+                "vm" => Engine::Vm,
+                "ir" => Engine::Ir,
+                _ => {
+                    eprintln!("Unknown engine '{name}', expected 'tree', 'vm' or 'ir'.");
+                    exit(65);
+                }
+            };
+        } else {
+            paths.push(arg);
+        }
+    }
+    // Every pass recurses once per level of nesting, so the stack grows with the limit.
+    let work = move || {
+        let registry = registry(engine, cache);
+        let Some(backend) = registry.get(&target) else {
+            let names = registry
+                .names()
+                .iter()
+                .map(|name| format!("'{name}'"))
+                .collect::<Vec<_>>();
+            eprintln!("Unknown target '{target}', expected {}.", names.join(", "));
+            exit(65);
+        };
+        // The REPL, the cache and the IR all belong to the interpreter.
+        let interpreted = backend.name() == "interp";
+        if !interpreted && (dump_ir || disassemble || paths.is_empty()) {
+            eprintln!("Only the 'interp' target has a REPL, '--dump-ir' and '--disassemble'.");
             exit(65);
         }
+        match paths.len() {
+            1 if disassemble => disassemble_file(&paths[0]),
+            0 if !disassemble => run_repl(engine, optimize, options),
+            1 => run_file(
+                &paths[0],
+                backend,
+                cache && matches!(engine, Engine::Vm),
+                options,
+                optimize,
+                dump_ir,
+            ),
+            _ => {
+                eprintln!("Invalid number of arguments.");
+                exit(65);
+            }
+        }
+    };
+    let worker = Builder::new()
+        .stack_size(options.stack_size())
+        .spawn(work)
+        .unwrap();
+    if worker.join().is_err() {
+        exit(101);
     }
 }
 
-fn run_repl() {
+fn run_repl(engine: Engine, optimize: bool, options: CompileOptions) {
     let mut source = String::new();
     let mut bindings = Rc::new(RefCell::new(Bindings::default()));
-    let mut environment = Rc::new(RefCell::new(Environment::default()));
+    let interner = Rc::new(RefCell::new(Interner::default()));
+    let mut environment = Environment::default();
+    let mut vm = VirtualMachine::default();
+    let mut ir_interpreter = IrInterpreter::default();
     let mut show_syntax_tree = false;
     let mut show_bound_tree = false;
+    let mut show_ir = false;
     let mut stdout = stdout();
 
     loop {
@@ -52,21 +204,46 @@ fn run_repl() {
             "@exit" | "@e" => break,
             "@syntax_tree" | "@st" => show_syntax_tree = !show_syntax_tree,
             "@bound_tree" | "@bt" => show_bound_tree = !show_bound_tree,
+            "@ir" => show_ir = !show_ir,
 
             source => {
                 if !source.is_empty() {
-                    let compilation = Compilation::compile(source, Rc::clone(&bindings));
+                    let mut compilation = Compilation::compile_with_options(
+                        source,
+                        Rc::clone(&bindings),
+                        Rc::clone(&interner),
+                        CompileOptions {
+                            interactive: true,
+                            ..options
+                        },
+                    );
+                    if optimize {
+                        compilation.optimize();
+                    }
 
-                    if compilation.diagnostic_bag.borrow().diagnostics.is_empty() {
-                        let mut evaluator = Evaluator::new(
-                            compilation.bound_program.clone(),
-                            Rc::clone(&environment),
-                        );
-                        println!(
-                            "{}",
-                            format!("{}", evaluator.evaluate()).truecolor(255, 255, 255)
-                        );
-                        environment = evaluator.bindings;
+                    if !compilation.diagnostic_bag.borrow().has_errors() {
+                        let interner = interner.borrow();
+                        let result = match engine {
+                            Engine::Tree => Evaluator::new(
+                                &compilation.bound_program,
+                                &mut environment,
+                                &interner,
+                            )
+                            .evaluate(),
+                            Engine::Vm => vm.run(
+                                &BytecodeCompiler::new(&compilation.bound_program).compile(),
+                                &interner,
+                            ),
+                            Engine::Ir => ir_interpreter.run(&compilation.lower(optimize)),
+                        };
+                        match result {
+                            Ok(object) => {
+                                println!("{}", format!("{object}").truecolor(255, 255, 255))
+                            }
+                            Err(error) => {
+                                report_runtime_error(source, &error.position, &error.message)
+                            }
+                        }
                     }
 
                     if show_syntax_tree {
@@ -83,27 +260,22 @@ fn run_repl() {
                         );
                     }
 
-                    for diagnostic in &compilation.diagnostic_bag.borrow().diagnostics {
-                        let line = diagnostic.position.get_line(source);
-                        eprintln!(
-                            "{}",
-                            format!("[error in line: {line}]").truecolor(255, 255, 0)
-                        );
-                        eprintln!(
+                    if show_ir && !compilation.diagnostic_bag.borrow().has_errors() {
+                        print!(
                             "{}",
-                            format!("Error: {}.", diagnostic.message).truecolor(255, 0, 0)
+                            compilation
+                                .lower(optimize)
+                                .to_string()
+                                .truecolor(155, 155, 155)
                         );
-                        eprintln!(
-                            "\t{}",
-                            &source[diagnostic.position.start..diagnostic.position.end]
-                        );
-                        eprint!("\t");
-                        for _ in diagnostic.position.start..diagnostic.position.end {
-                            eprint!("{}", "^".truecolor(255, 255, 0));
-                        }
-                        eprintln!("{}", " --- here".truecolor(255, 255, 0));
                     }
 
+                    report_diagnostics(
+                        None,
+                        source,
+                        &compilation.diagnostic_bag.borrow().diagnostics,
+                    );
+
                     bindings = compilation.bindings;
                 }
             }
@@ -112,34 +284,90 @@ fn run_repl() {
     }
 }
 
-fn run_file(path: &str) {
+/// Runs a file on a backend. With `read_cache`, a program the VM compiled before is run from
+/// its cached bytecode instead.
+fn run_file(
+    path: &str,
+    backend: &dyn Backend,
+    read_cache: bool,
+    options: CompileOptions,
+    optimize: bool,
+    dump_ir: bool,
+) {
     let source = read_to_string(path).unwrap();
-    let compilation = Compilation::compile(&source, Rc::new(RefCell::new(Bindings::default())));
-    if compilation.diagnostic_bag.borrow().diagnostics.is_empty() {
-        let mut evaluator = Evaluator::new(
-            compilation.bound_program.clone(),
-            Rc::new(RefCell::new(Environment::default())),
-        );
-        evaluator.evaluate();
+    if backend.name() == "interp" && read_cache && !dump_ir {
+        let source_hash = hash(source.as_bytes());
+        let cache = path_for(Path::new(path));
+        let mut interner = Interner::default();
+        if let Some(chunk) = load_cached(&cache, source_hash, optimize, &mut interner) {
+            if let Err(error) = VirtualMachine::default().run(&chunk, &interner) {
+                report_runtime_error(&source, &error.position, &error.message);
+                exit(70);
+            }
+            return;
+        }
+    }
+    let mut compilation = Compilation::compile_with_options(
+        &source,
+        Rc::new(RefCell::new(Bindings::default())),
+        Rc::new(RefCell::new(Interner::default())),
+        options,
+    );
+    if optimize {
+        compilation.optimize();
     }
-    for diagnostic in &compilation.diagnostic_bag.borrow().diagnostics {
-        let line = diagnostic.position.get_line(&source);
-        eprintln!(
-            "{}",
-            format!("[error in line: {line}]").truecolor(255, 255, 0)
-        );
-        eprintln!(
-            "{}",
-            format!("Error: {}.", diagnostic.message).truecolor(255, 0, 0)
-        );
-        eprintln!(
-            "\t{}",
-            &source[diagnostic.position.start..diagnostic.position.end]
-        );
-        eprint!("\t");
-        for _ in diagnostic.position.start..diagnostic.position.end {
-            eprint!("{}", "^".truecolor(255, 255, 0));
+    let has_errors = compilation.diagnostic_bag.borrow().has_errors();
+    let result = if has_errors {
+        Err(BackendError { exit_code: 65 })
+    } else if dump_ir {
+        print!("{}", compilation.lower(optimize));
+        Ok(())
+    } else {
+        backend.run(&Input {
+            path: Path::new(path),
+            source: &source,
+            compilation: &compilation,
+            optimized: optimize,
+        })
+    };
+    // Warnings follow whatever the program printed, so they do not interleave with its output.
+    report_diagnostics(
+        None,
+        &source,
+        &compilation.diagnostic_bag.borrow().diagnostics,
+    );
+    if let Err(error) = result {
+        exit(error.exit_code);
+    }
+}
+
+/// The bytecode cached beside a source file, if it was compiled from the same source with the
+/// same options. Anything unreadable is ignored and compiled again.
+fn load_cached(
+    cache: &Path,
+    source_hash: u64,
+    optimize: bool,
+    interner: &mut Interner,
+) -> Option<Chunk> {
+    let artifact = Artifact::decode(&read(cache).ok()?, interner).ok()?;
+    (artifact.source_hash == source_hash && artifact.optimized == optimize)
+        .then_some(artifact.chunk)
+}
+
+fn disassemble_file(path: &str) {
+    let bytes = match read(path) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            eprintln!("Could not read '{path}': {error}.");
+            exit(65);
+        }
+    };
+    let mut interner = Interner::default();
+    match Artifact::decode(&bytes, &mut interner) {
+        Ok(artifact) => print!("{}", artifact.disassemble(&interner)),
+        Err(error) => {
+            eprintln!("{}", format!("{path}: {error}.").truecolor(255, 0, 0));
+            exit(65);
         }
-        eprintln!("{}", " --- here".truecolor(255, 255, 0));
     }
 }
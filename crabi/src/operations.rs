@@ -0,0 +1,119 @@
+use std::rc::Rc;
+
+use crab::{
+    binding::bound_tree::{BoundBinaryOperationKind, BoundUnaryOperationKind},
+    common::{
+        arithmetic::{add, divide, multiply, negate, subtract, ArithmeticError},
+        diagnostic::Position,
+        types::{Object, Type},
+    },
+};
+
+use super::runtime_error::RuntimeError;
+
+pub(crate) fn expect_number(object: Object, position: &Position) -> Result<i32, RuntimeError> {
+    match object {
+        Object::Number(n) => Ok(n),
+        object => Err(RuntimeError::type_mismatch(
+            position.clone(),
+            Type::Number,
+            object.get_type(),
+        )),
+    }
+}
+
+pub(crate) fn expect_boolean(object: Object, position: &Position) -> Result<bool, RuntimeError> {
+    match object {
+        Object::Boolean(b) => Ok(b),
+        object => Err(RuntimeError::type_mismatch(
+            position.clone(),
+            Type::Boolean,
+            object.get_type(),
+        )),
+    }
+}
+
+pub(crate) fn unary_operation(
+    operator: &BoundUnaryOperationKind,
+    right: Object,
+    position: &Position,
+) -> Result<Object, RuntimeError> {
+    match operator {
+        BoundUnaryOperationKind::Identity => Ok(Object::Number(expect_number(right, position)?)),
+        BoundUnaryOperationKind::Negation => negate(expect_number(right, position)?)
+            .map(Object::Number)
+            .map_err(|error| {
+                RuntimeError::arithmetic(position.clone(), error, &operator.to_string())
+            }),
+        BoundUnaryOperationKind::LogicalNegation => {
+            Ok(Object::Boolean(!expect_boolean(right, position)?))
+        }
+    }
+}
+
+/// Errors point at the whole operation, except division by zero, which points at the divisor.
+pub(crate) fn binary_operation(
+    operator: &BoundBinaryOperationKind,
+    left: Object,
+    right: Object,
+    position: &Position,
+    right_position: &Position,
+) -> Result<Object, RuntimeError> {
+    let positions = (position, right_position);
+    match operator {
+        BoundBinaryOperationKind::Addition => match (left, right) {
+            (Object::String(left), Object::String(right)) => {
+                Ok(Object::String(Rc::from([&*left, &*right].concat())))
+            }
+            (left, right) => arithmetic(left, right, positions, operator, add),
+        },
+        BoundBinaryOperationKind::Subtraction => {
+            arithmetic(left, right, positions, operator, subtract)
+        }
+        BoundBinaryOperationKind::Multiplication => {
+            arithmetic(left, right, positions, operator, multiply)
+        }
+        BoundBinaryOperationKind::Division => arithmetic(left, right, positions, operator, divide),
+
+        BoundBinaryOperationKind::Greater => Ok(Object::Boolean(
+            expect_number(left, position)? > expect_number(right, position)?,
+        )),
+        BoundBinaryOperationKind::Lesser => Ok(Object::Boolean(
+            expect_number(left, position)? < expect_number(right, position)?,
+        )),
+        BoundBinaryOperationKind::GreaterEqual => Ok(Object::Boolean(
+            expect_number(left, position)? >= expect_number(right, position)?,
+        )),
+        BoundBinaryOperationKind::LesserEqual => Ok(Object::Boolean(
+            expect_number(left, position)? <= expect_number(right, position)?,
+        )),
+
+        BoundBinaryOperationKind::LogicalAnd => Ok(Object::Boolean(
+            expect_boolean(left, position)? && expect_boolean(right, position)?,
+        )),
+        BoundBinaryOperationKind::LogicalOr => Ok(Object::Boolean(
+            expect_boolean(left, position)? || expect_boolean(right, position)?,
+        )),
+
+        BoundBinaryOperationKind::NotEqual => Ok(Object::Boolean(left != right)),
+        BoundBinaryOperationKind::Equal => Ok(Object::Boolean(left == right)),
+    }
+}
+
+fn arithmetic(
+    left: Object,
+    right: Object,
+    (position, right_position): (&Position, &Position),
+    operator: &BoundBinaryOperationKind,
+    operation: fn(i32, i32) -> Result<i32, ArithmeticError>,
+) -> Result<Object, RuntimeError> {
+    let left = expect_number(left, position)?;
+    let right = expect_number(right, right_position)?;
+    operation(left, right).map(Object::Number).map_err(|error| {
+        let position = match error {
+            ArithmeticError::Overflow => position,
+            ArithmeticError::DivisionByZero => right_position,
+        };
+        RuntimeError::arithmetic(position.clone(), error, &operator.to_string())
+    })
+}
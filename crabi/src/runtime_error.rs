@@ -0,0 +1,28 @@
+use crab::common::{arithmetic::ArithmeticError, diagnostic::Position, types::Type};
+
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub position: Position,
+    pub message: String,
+}
+
+impl RuntimeError {
+    fn new(position: Position, message: String) -> Self {
+        Self { position, message }
+    }
+
+    pub(crate) fn arithmetic(position: Position, error: ArithmeticError, operator: &str) -> Self {
+        Self::new(position, error.message(operator))
+    }
+
+    pub(crate) fn type_mismatch(position: Position, expected_type: Type, given_type: Type) -> Self {
+        Self::new(
+            position,
+            format!("Expected '{expected_type}' value, got '{given_type}'"),
+        )
+    }
+
+    pub(crate) fn undefined_name(position: Position, name: &str) -> Self {
+        Self::new(position, format!("Name '{name}' is not defined"))
+    }
+}
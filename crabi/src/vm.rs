@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use crab::common::{
+    intern::{Interner, Symbol},
+    types::Object,
+};
+
+use super::{
+    bytecode::{Chunk, Instruction},
+    operations::{binary_operation, expect_boolean, unary_operation},
+    runtime_error::RuntimeError,
+};
+
+#[derive(Default)]
+pub struct VirtualMachine {
+    stack: Vec<Object>,
+    globals: HashMap<Symbol, Object>,
+}
+
+impl VirtualMachine {
+    /// The interner is only needed to name an undefined global in a runtime error.
+    pub fn run(&mut self, chunk: &Chunk, interner: &Interner) -> Result<Object, RuntimeError> {
+        self.stack.clear();
+        let mut ip = 0;
+        loop {
+            let position = &chunk.positions[ip];
+            match &chunk.instructions[ip] {
+                Instruction::Constant(index) => self.stack.push(chunk.constants[*index].clone()),
+                Instruction::Pop => {
+                    self.pop();
+                }
+
+                Instruction::DefineGlobal(name) => {
+                    let object = self.pop();
+                    self.globals.insert(*name, object);
+                }
+                Instruction::GetGlobal(name) => {
+                    let object = self.globals.get(name).cloned().ok_or_else(|| {
+                        RuntimeError::undefined_name(position.clone(), interner.resolve(*name))
+                    })?;
+                    self.stack.push(object);
+                }
+                Instruction::SetGlobal(name) => {
+                    let object = self.peek().clone();
+                    let global = self.globals.get_mut(name).ok_or_else(|| {
+                        RuntimeError::undefined_name(position.clone(), interner.resolve(*name))
+                    })?;
+                    *global = object;
+                }
+                Instruction::GetLocal(slot) => self.stack.push(self.stack[*slot].clone()),
+                Instruction::SetLocal(slot) => self.stack[*slot] = self.peek().clone(),
+
+                Instruction::Unary(operator) => {
+                    let right = self.pop();
+                    self.stack.push(unary_operation(operator, right, position)?);
+                }
+                Instruction::Binary(operator) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    // The right operand's code ends just before, with its position.
+                    let right_position = &chunk.positions[ip - 1];
+                    self.stack.push(binary_operation(
+                        operator,
+                        left,
+                        right,
+                        position,
+                        right_position,
+                    )?);
+                }
+
+                Instruction::Print => println!("{}", self.pop()),
+                Instruction::Jump(target) => {
+                    ip = *target;
+                    continue;
+                }
+                Instruction::JumpIfFalse(target) => {
+                    if !expect_boolean(self.pop(), position)? {
+                        ip = *target;
+                        continue;
+                    }
+                }
+                Instruction::Return => return Ok(self.pop()),
+            }
+            ip += 1;
+        }
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().expect("Value stack underflow.")
+    }
+
+    fn peek(&self) -> &Object {
+        self.stack.last().expect("Value stack underflow.")
+    }
+}
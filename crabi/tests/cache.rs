@@ -0,0 +1,131 @@
+//! Checks that the virtual machine caches compiled programs beside their source, reuses them
+//! only while the source is unchanged, recovers from a damaged cache, and can do without one.
+
+use std::{
+    env::temp_dir,
+    fs::{metadata, read, read_dir, remove_file, write},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    time::SystemTime,
+};
+
+fn crabi(args: &[&str], path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_crabi"))
+        .args(args)
+        .arg(path)
+        .output()
+        .expect("Could not run crabi.")
+}
+
+fn program(name: &str, source: &str) -> (PathBuf, PathBuf) {
+    let path = temp_dir().join(format!("crabi_cache_{name}.crab"));
+    let cache = path.with_extension("crabc");
+    write(&path, source).unwrap();
+    let _ = remove_file(&cache);
+    (path, cache)
+}
+
+fn modified(path: &Path) -> SystemTime {
+    metadata(path).unwrap().modified().unwrap()
+}
+
+#[test]
+fn unchanged_sources_run_from_the_cache() {
+    let (path, cache) = program("unchanged", "var a = 20\nprint a + 1\n");
+    let first = crabi(&["--engine=vm"], &path);
+    assert_eq!(String::from_utf8_lossy(&first.stdout), "21\n");
+    let written = modified(&cache);
+
+    let second = crabi(&["--engine=vm"], &path);
+    assert_eq!(String::from_utf8_lossy(&second.stdout), "21\n");
+    assert_eq!(modified(&cache), written, "The cache was compiled again.");
+
+    write(&path, "var a = 20\nprint a + 2\n").unwrap();
+    let changed = crabi(&["--engine=vm"], &path);
+    assert_eq!(String::from_utf8_lossy(&changed.stdout), "22\n");
+}
+
+#[test]
+fn damaged_caches_are_compiled_again() {
+    let (path, cache) = program("damaged", "print \"hi\"\n");
+    crabi(&["--engine=vm"], &path);
+    let mut bytes = read(&cache).unwrap();
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0xff;
+    write(&cache, &bytes).unwrap();
+
+    let disassembly = crabi(&["--disassemble"], &cache);
+    assert_eq!(disassembly.status.code(), Some(65));
+
+    let output = crabi(&["--engine=vm"], &path);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi\n");
+    assert!(crabi(&["--disassemble"], &cache).status.success());
+}
+
+#[test]
+fn disassembly_lists_the_instructions() {
+    let (path, cache) = program("disassembly", "var greeting = \"hello\"\nprint greeting\n");
+    crabi(&["--engine=vm", "-O"], &path);
+    let output = crabi(&["--disassemble"], &cache);
+    let listing = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        listing.starts_with(&format!(
+            "; crabc version 2 by crabi {}",
+            env!("CARGO_PKG_VERSION")
+        )),
+        "{listing}"
+    );
+    assert!(listing.contains(", optimized"), "{listing}");
+    assert!(listing.contains("\"hello\""), "{listing}");
+    assert!(listing.contains("define_global greeting"), "{listing}");
+    assert!(listing.contains("print"), "{listing}");
+}
+
+#[test]
+fn programs_with_warnings_are_not_cached() {
+    let (path, cache) = program("warnings", "var unused = 1\n");
+    let output = crabi(&["--engine=vm"], &path);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warning"));
+    assert!(!cache.exists());
+}
+
+#[test]
+fn no_cache_neither_writes_nor_reads_one() {
+    let (path, cache) = program("disabled", "print 3\n");
+    let output = crabi(&["--engine=vm", "--no-cache"], &path);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+    assert!(!cache.exists());
+
+    // A damaged cache would be replaced if it were read.
+    write(&cache, b"CRBC damaged").unwrap();
+    let output = crabi(&["--engine=vm", "--no-cache"], &path);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+    assert_eq!(read(&cache).unwrap(), b"CRBC damaged");
+}
+
+#[test]
+fn cached_corpus_programs_run_like_compiled_ones() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus");
+    for entry in read_dir(corpus).unwrap() {
+        let source = entry.unwrap().path();
+        if source
+            .extension()
+            .is_none_or(|extension| extension != "crab")
+        {
+            continue;
+        }
+        let name = source.file_stem().unwrap().to_string_lossy();
+        let (path, cache) = program(
+            &format!("corpus_{name}"),
+            &std::fs::read_to_string(&source).unwrap(),
+        );
+        let compiled = crabi(&["--engine=vm"], &path);
+        if !cache.exists() {
+            continue;
+        }
+        assert!(crabi(&["--disassemble"], &cache).status.success(), "{name}");
+        let cached = crabi(&["--engine=vm"], &path);
+        assert_eq!(cached.stdout, compiled.stdout, "{name}");
+        assert_eq!(cached.status.code(), compiled.status.code(), "{name}");
+    }
+}
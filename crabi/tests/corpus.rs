@@ -0,0 +1,71 @@
+//! Runs the differential corpus shared with crabtgo and checks that every program prints its
+//! expected output. The programs in `failures/` must also stop with the runtime error in their
+//! `.err` file. crabtgo's tests hold the generated Go code to the same files.
+
+use std::{
+    fs::{read_dir, read_to_string},
+    path::Path,
+    process::Command,
+};
+
+#[test]
+fn corpus_prints_the_expected_output() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus");
+    for entry in read_dir(corpus).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "crab") {
+            continue;
+        }
+        let expected = read_to_string(path.with_extension("out")).unwrap();
+        for engine in ["tree", "vm", "ir"] {
+            let output = Command::new(env!("CARGO_BIN_EXE_crabi"))
+                .arg(format!("--engine={engine}"))
+                .arg(&path)
+                .output()
+                .expect("Could not run crabi.");
+            assert!(output.status.success());
+            assert_eq!(
+                String::from_utf8_lossy(&output.stdout),
+                expected,
+                "{} prints something else on the {engine} engine",
+                path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn failures_stop_with_the_expected_runtime_error() {
+    let failures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus/failures");
+    for entry in read_dir(failures).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "crab") {
+            continue;
+        }
+        let expected = read_to_string(path.with_extension("out")).unwrap();
+        let error = read_to_string(path.with_extension("err")).unwrap();
+        for engine in ["tree", "vm", "ir"] {
+            for args in [&[][..], &["-O"]] {
+                let output = Command::new(env!("CARGO_BIN_EXE_crabi"))
+                    .arg(format!("--engine={engine}"))
+                    .args(args)
+                    .arg(&path)
+                    .output()
+                    .expect("Could not run crabi.");
+                assert_eq!(output.status.code(), Some(70));
+                assert_eq!(
+                    String::from_utf8_lossy(&output.stdout),
+                    expected,
+                    "{} {args:?} prints something else on the {engine} engine",
+                    path.display()
+                );
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                assert!(
+                    stderr.contains(&format!("Runtime error: {}.", error.trim_end())),
+                    "{} {args:?} fails differently on the {engine} engine:\n{stderr}",
+                    path.display()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Checks how crabi reports diagnostics: after the program has run, in source order, and with
+//! every line of a position underlined, where runtime errors point, and that programs nested
+//! too deeply are reported rather than overflowing the stack.
+
+use std::{
+    env::temp_dir,
+    fs::write,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn program(name: &str, source: &str) -> PathBuf {
+    let path = temp_dir().join(format!("crabi_diagnostics_{name}.crab"));
+    write(&path, source).unwrap();
+    path
+}
+
+fn crabi(path: &PathBuf) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_crabi"))
+        .arg(path)
+        .output()
+        .expect("Could not run crabi.")
+}
+
+#[test]
+fn warnings_are_reported_after_the_program_ran() {
+    let path = program("after", "var unused = 1\nvar zero = 0\nprint 1 / zero\n");
+    let output = crabi(&path);
+    assert_eq!(output.status.code(), Some(70));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let error = stderr.find("Runtime error: Division by zero.").unwrap();
+    let warning = stderr.find("Variable 'unused' is never read").unwrap();
+    assert!(error < warning, "{stderr}");
+}
+
+#[test]
+fn positions_over_several_lines_are_underlined_line_by_line() {
+    let path = program("lines", "while false {\n\tprint 1\n    print 22\n}\n");
+    let output = crabi(&path);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "Warning: Statement is unreachable.\n\t{\n\t^\n\t\tprint 1\n\t\t^^^^^^^\n\t    print 22\n\t    ^^^^^^^^\n\t}\n\t^ --- here\n"
+        ),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn division_by_zero_points_at_the_divisor() {
+    let path = program("divisor", "var zero = 0\nprint 100 / (zero * 2)\n");
+    let overflow = program(
+        "division_overflow",
+        "var smallest = -2147483647 - 1\nvar minus = -1\nprint smallest / minus\n",
+    );
+    for engine in ["tree", "vm", "ir"] {
+        for optimize in [false, true] {
+            let mut command = Command::new(env!("CARGO_BIN_EXE_crabi"));
+            command.arg(format!("--engine={engine}"));
+            if optimize {
+                command.arg("-O");
+            }
+            let output = command.arg(&path).output().unwrap();
+            assert_eq!(output.status.code(), Some(70));
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(
+                stderr.contains("Division by zero.\n\tzero * 2\n\t^^^^^^^^ --- here\n"),
+                "{engine} -O={optimize}: {stderr}"
+            );
+        }
+        let output = Command::new(env!("CARGO_BIN_EXE_crabi"))
+            .arg(format!("--engine={engine}"))
+            .arg(&overflow)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("\tsmallest / minus\n\t^^^^^^^^^^^^^^^^ --- here\n"),
+            "{engine}: {stderr}"
+        );
+    }
+}
+
+#[test]
+fn programs_nested_too_deeply_are_reported() {
+    let depth = 50_000;
+    let sources = [
+        (
+            "parentheses",
+            format!("print {}1{}\n", "(".repeat(depth), ")".repeat(depth)),
+        ),
+        (
+            "braces",
+            format!("{}{}\n", "{".repeat(depth), "}".repeat(depth)),
+        ),
+        ("negations", format!("print {}1\n", "-".repeat(depth))),
+        ("chain", format!("print 1{}\n", " + 1".repeat(depth))),
+    ];
+    for (name, source) in sources {
+        let output = crabi(&program(&format!("nested_{name}"), &source));
+        assert_eq!(output.status.code(), Some(65), "{name}");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(
+            stderr
+                .matches("Error: Nesting is deeper than 1000 levels.")
+                .count(),
+            1,
+            "{name}: {stderr}"
+        );
+        assert_eq!(stderr.matches("Error").count(), 1, "{name}: {stderr}");
+    }
+}
+
+#[test]
+fn programs_nested_up_to_the_limit_run() {
+    let depth = 990;
+    let sources = [
+        format!("print {}1{}\n", "(".repeat(depth), ")".repeat(depth)),
+        format!("{}print 1{}\n", "{".repeat(depth), "}".repeat(depth)),
+        format!("print {}1\n", "-".repeat(depth - 1)),
+        format!("print 0{}\n", " + 1".repeat(depth - 1)),
+    ];
+    for (index, source) in sources.iter().enumerate() {
+        let path = program(&format!("nested_limit_{index}"), source);
+        for engine in ["tree", "vm", "ir"] {
+            for optimize in [false, true] {
+                let mut command = Command::new(env!("CARGO_BIN_EXE_crabi"));
+                command.args([&format!("--engine={engine}"), "--no-cache"]);
+                if optimize {
+                    command.arg("-O");
+                }
+                let output = command.arg(&path).output().unwrap();
+                assert!(output.status.success(), "{index} {engine} -O={optimize}");
+            }
+        }
+    }
+}
+
+#[test]
+fn the_nesting_limit_is_an_option() {
+    let depth = 5000;
+    let path = program(
+        "nested_option",
+        &format!("{}print 1{}\n", "{".repeat(depth), "}".repeat(depth)),
+    );
+    for engine in ["tree", "vm", "ir"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_crabi"))
+            .args([
+                &format!("--engine={engine}"),
+                "--no-cache",
+                "--max-depth=6000",
+            ])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{engine}");
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n", "{engine}");
+    }
+
+    let path = program("nested_lowered", "print ((((1))))\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_crabi"))
+        .arg("--max-depth=3")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(65));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Error: Nesting is deeper than 3 levels.")
+    );
+
+    for invalid in ["0", "-1", "deep"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_crabi"))
+            .arg(format!("--max-depth={invalid}"))
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(65), "{invalid}");
+    }
+}
+
+#[test]
+fn literals_out_of_range_are_reported_for_every_target() {
+    let path = program(
+        "literal_out_of_range",
+        "print 2147483647\nprint 3000000000\n",
+    );
+    for target in ["interp", "go", "c"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_crabi"))
+            .arg(format!("--target={target}"))
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(65), "{target}");
+        assert!(
+            String::from_utf8_lossy(&output.stderr)
+                .contains("Error: Integer literal '3000000000' is out of range."),
+            "{target}"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Runs programs on the IR engine, with and without its passes, and checks that they print and
+//! exit like they do on the tree engine.
+
+use std::{
+    env::temp_dir,
+    fs::{read_dir, write},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn run(path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_crabi"))
+        .args(args)
+        .arg(path)
+        .output()
+        .expect("Could not run crabi.")
+}
+
+fn assert_equivalent(path: &Path) {
+    let expected = run(path, &["--engine=tree"]);
+    for args in [&["--engine=ir"][..], &["--engine=ir", "-O"]] {
+        let output = run(path, args);
+        assert_eq!(
+            String::from_utf8_lossy(&expected.stdout),
+            String::from_utf8_lossy(&output.stdout),
+            "{} prints something else with {args:?}",
+            path.display()
+        );
+        assert_eq!(
+            expected.status.code(),
+            output.status.code(),
+            "{} exits differently with {args:?}",
+            path.display()
+        );
+    }
+}
+
+fn program(name: &str, source: &str) -> PathBuf {
+    let path = temp_dir().join(format!("crabi_ir_{name}.crab"));
+    write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn samples_run_the_same_on_the_ir() {
+    let samples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests");
+    for entry in read_dir(samples).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "crab")
+        {
+            assert_equivalent(&path);
+        }
+    }
+}
+
+#[test]
+fn loops_and_scopes_run_the_same() {
+    assert_equivalent(&program(
+        "loops",
+        "var n = 10\nvar total = 0\nvar i = 0\nwhile i < n {\n    var step = n * 2 + 1\n    total = total + step\n    i = i + 1\n}\nprint total\nfor j = 0 to 5 {\n    j = j + 1\n    print j\n}\n{\n    var n = 7\n    print n\n}\nprint n\n",
+    ));
+}
+
+#[test]
+fn runtime_errors_are_not_moved() {
+    for (name, source) in [
+        (
+            "overflow",
+            "var x = 1\nvar i = 0\nwhile i < 40 {\n    print i\n    x = x * 2\n    i = i + 1\n}\n",
+        ),
+        (
+            "division",
+            "var d = 3\nwhile d > -1 {\n    print 6 / d\n    d = d - 1\n}\n",
+        ),
+        ("untaken", "var i = 0\nwhile i > 0 print 1 / 0\nprint i\n"),
+    ] {
+        assert_equivalent(&program(name, source));
+    }
+}
+
+#[test]
+fn invariant_code_is_hoisted_out_of_loops() {
+    let path = program(
+        "hoisting",
+        "var c = 0\nwhile c < 2 c = c + 1\nvar k = 1\nif c == 2 k = 5\nvar i = 0\nwhile i < 3 {\n    print k < 4\n    i = i + 1\n}\n",
+    );
+    assert_equivalent(&path);
+    let dump = String::from_utf8(run(&path, &["--dump-ir", "-O"]).stdout).unwrap();
+    let hoisted = dump.find("load k").expect("The load of 'k' was removed.");
+    let header = dump.find("load i").unwrap();
+    assert!(
+        hoisted < header,
+        "The load of 'k' stays in the loop:\n{dump}"
+    );
+}
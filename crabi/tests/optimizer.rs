@@ -0,0 +1,119 @@
+//! Runs programs with and without `-O` on both engines and checks that the optimizer never
+//! changes what a program prints or how it exits.
+
+use std::{
+    env::temp_dir,
+    fs::{read_dir, write},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+const ENGINES: [&str; 2] = ["tree", "vm"];
+
+fn run(path: &Path, engine: &str, optimize: bool) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_crabi"));
+    command.arg(format!("--engine={engine}"));
+    if optimize {
+        command.arg("-O");
+    }
+    command.arg(path).output().expect("Could not run crabi.")
+}
+
+fn assert_equivalent(path: &Path) {
+    for engine in ENGINES {
+        let plain = run(path, engine, false);
+        let optimized = run(path, engine, true);
+        assert_eq!(
+            String::from_utf8_lossy(&plain.stdout),
+            String::from_utf8_lossy(&optimized.stdout),
+            "{} prints something else with -O on the {engine} engine",
+            path.display()
+        );
+        assert_eq!(
+            plain.status.code(),
+            optimized.status.code(),
+            "{} exits differently with -O on the {engine} engine",
+            path.display()
+        );
+    }
+}
+
+fn program(name: &str, source: &str) -> PathBuf {
+    let path = temp_dir().join(format!("crabi_optimizer_{name}.crab"));
+    write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn samples_print_the_same_when_optimized() {
+    let samples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests");
+    for entry in read_dir(samples).unwrap() {
+        let path = entry.unwrap().path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "crab")
+        {
+            assert_equivalent(&path);
+        }
+    }
+}
+
+#[test]
+fn pruned_branches_keep_their_scope() {
+    assert_equivalent(&program(
+        "scopes",
+        "var x = 1\nif true var x = 2 print x\nif false print 0 else { var x = 3 print x }\nprint x\n",
+    ));
+}
+
+#[test]
+fn pruned_branches_in_nested_bodies_keep_their_scope() {
+    for (name, source) in [
+        (
+            "nested_if",
+            "var c = true\nif c if true var y = 1\nprint c\n",
+        ),
+        (
+            "nested_while",
+            "var c = 0\nwhile c < 2 if true var y = c = c + 1\nprint c\n",
+        ),
+        (
+            "nested_block",
+            "var c = true\nif c if true { var y = 1 print y }\nprint c\n",
+        ),
+        (
+            "else_if",
+            "var c = false\nif c print 1 else if true var y = 2\nprint c\n",
+        ),
+        ("for_body", "for i = 0 to 1 if true { var y = i print y }\n"),
+    ] {
+        let path = program(name, source);
+        assert_equivalent(&path);
+        for engine in ENGINES {
+            assert!(run(&path, engine, true).status.success(), "{name}");
+        }
+    }
+}
+
+#[test]
+fn simplifications_keep_side_effects() {
+    assert_equivalent(&program(
+        "side_effects",
+        "var a = 1\nprint (a = a + 4) * 1\nprint 0 + (a = a * 2)\nvar b = true\nprint !!(b = false)\nprint a\n",
+    ));
+}
+
+#[test]
+fn failing_constants_still_fail_at_runtime() {
+    for (name, source) in [
+        ("overflow", "print 1\nprint 2147483647 + 1\n"),
+        ("division", "print 1\nprint 1 / 0\n"),
+        ("negation", "print -(-2147483647 - 1)\n"),
+    ] {
+        let path = program(name, source);
+        assert_equivalent(&path);
+        let output = run(&path, "tree", true);
+        assert_eq!(output.status.code(), Some(70));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Warning: Expression always"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Checks that `--target` hands programs to the selected backend, and that the C it writes,
+//! built with the system's `cc` when there is one, does what the interpreter does.
+
+use std::{
+    env::temp_dir,
+    fs::{read_to_string, remove_file, write},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn crabi(args: &[&str], path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_crabi"))
+        .args(args)
+        .arg(path)
+        .output()
+        .expect("Could not run crabi.")
+}
+
+fn program(name: &str, source: &str) -> PathBuf {
+    let path = temp_dir().join(format!("crabi_target_{name}.crab"));
+    write(&path, source).unwrap();
+    let _ = remove_file(path.with_extension("go"));
+    let _ = remove_file(path.with_extension("c"));
+    path
+}
+
+fn cc_is_installed() -> bool {
+    Command::new("cc")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Writes a program as C with `--target=c`, builds it and runs it.
+fn run_as_c(path: &Path) -> Output {
+    let output = crabi(&["--target=c"], path);
+    assert!(output.status.success(), "{output:?}");
+    let executable = path.with_extension("out");
+    let build = Command::new("cc")
+        .args(["-std=c99", "-pedantic", "-Wall", "-Wextra", "-Werror", "-o"])
+        .arg(&executable)
+        .arg(path.with_extension("c"))
+        .output()
+        .unwrap();
+    assert!(
+        build.status.success(),
+        "{}",
+        String::from_utf8_lossy(&build.stderr)
+    );
+    Command::new(&executable).output().unwrap()
+}
+
+#[test]
+fn the_interpreter_is_the_default_target() {
+    let path = program("default", "print 1 + 2\n");
+    let implicit = crabi(&[], &path);
+    let explicit = crabi(&["--target=interp"], &path);
+    assert!(explicit.status.success());
+    assert_eq!(String::from_utf8_lossy(&implicit.stdout), "3\n");
+    assert_eq!(implicit.stdout, explicit.stdout);
+    assert!(!path.with_extension("go").exists());
+}
+
+#[test]
+fn the_go_target_writes_go_beside_the_source() {
+    let path = program("go", "var greeting = \"hi\"\nprint greeting\n");
+    let output = crabi(&["--target=go"], &path);
+    assert!(output.status.success(), "{output:?}");
+    assert!(output.stdout.is_empty(), "The program was run.");
+    let go = read_to_string(path.with_extension("go")).unwrap();
+    assert!(go.starts_with("package main"), "{go}");
+    assert!(go.contains("fmt.Println(greeting)"), "{go}");
+}
+
+#[test]
+fn programs_with_errors_reach_no_backend() {
+    let path = program("errors", "print missing\n");
+    let output = crabi(&["--target=go"], &path);
+    assert_eq!(output.status.code(), Some(65));
+    assert!(!path.with_extension("go").exists());
+}
+
+#[test]
+fn runtime_errors_exit_like_before() {
+    let path = program("runtime_error", "var zero = 0\nprint 1 / zero\n");
+    for engine in ["--engine=tree", "--engine=vm", "--engine=ir"] {
+        let output = crabi(&["--target=interp", engine], &path);
+        assert_eq!(output.status.code(), Some(70), "{engine}");
+    }
+}
+
+#[test]
+fn unknown_targets_are_rejected() {
+    let path = program("unknown", "print 1\n");
+    let output = crabi(&["--target=cobol"], &path);
+    assert_eq!(output.status.code(), Some(65));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unknown target 'cobol', expected 'interp', 'go', 'c'."),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn only_the_interpreter_dumps_ir() {
+    let path = program("dump_ir", "print 1\n");
+    let output = crabi(&["--target=go", "--dump-ir"], &path);
+    assert_eq!(output.status.code(), Some(65));
+}
+
+#[test]
+fn the_c_target_does_what_the_interpreter_does() {
+    if !cc_is_installed() {
+        eprintln!("No C compiler is installed; skipping.");
+        return;
+    }
+    let programs = [
+        (
+            "eager_logic",
+            "var a = 1\nvar b = false\nprint b && (a = 5) == 5\nprint a\nprint true || (a = 7) > 0\nprint a\n",
+        ),
+        (
+            "order",
+            "var a = 1\nprint (a = 2) + a\na = (a = 3) + 1\nprint a\n",
+        ),
+        (
+            "shadowing",
+            "var x = 10\n{\n    var x = x + 1\n    print x\n}\nprint x\nfor x = 0 to 2 print x\n",
+        ),
+        (
+            "strings",
+            "var s = \"a?b\\c\"\nvar i = 0\nwhile i < 3 {\n    s = s + \"??/\"\n    var copy = s + \"!\"\n    print copy\n    i = i + 1\n}\nvar r = s\nr = r + r\nprint r\nprint \"tab\tand \u{fc}\"\nvar q = (s = \"q\")\nprint q + s\n",
+        ),
+        (
+            "names",
+            "var int = 3\nvar NULL = 4\nvar __LINE__ = 5\nvar crab_add = int + NULL + __LINE__\nprint crab_add\n",
+        ),
+        (
+            "failure_order",
+            "var zero = 0\nvar big = 2147483647\nprint 1\nprint (big * big) + (1 / zero)\n",
+        ),
+        (
+            "eager_failure",
+            "var zero = 0\nprint false && (1 / zero) == 0\n",
+        ),
+    ];
+    for (name, source) in programs {
+        let path = program(&format!("c_{name}"), source);
+        let expected = crabi(&[], &path);
+        let actual = run_as_c(&path);
+        assert_eq!(actual.status.code(), expected.status.code(), "{name}");
+        assert_eq!(
+            String::from_utf8_lossy(&actual.stdout),
+            String::from_utf8_lossy(&expected.stdout),
+            "{name}"
+        );
+        if !expected.status.success() {
+            let stderr = String::from_utf8_lossy(&actual.stderr);
+            let message = stderr.lines().next().unwrap_or_default();
+            assert!(
+                String::from_utf8_lossy(&expected.stderr).contains(message),
+                "{name} fails with {stderr}"
+            );
+        }
+    }
+}
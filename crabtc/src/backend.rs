@@ -0,0 +1,61 @@
+use std::{env::var_os, ffi::OsString, path::Path};
+
+use crab::backend::{
+    driver::{write_beside, Toolchain, Translation, Translator},
+    Backend, BackendError, Input,
+};
+
+use super::compiler::Compiler;
+
+/// Compiles programs to C99. As a backend, it writes the code beside the source, in a file
+/// with the extension `.c`, and on the command line, the compiler named by `CC`, or `cc`,
+/// builds it.
+///
+/// The C code nests as deeply as the program, which the compilation's nesting limit bounds, and
+/// it never recurses, since Crab has no functions. C99 only promises 127 levels of nested blocks
+/// and 63 of nested parentheses, so a program near a high limit may need a compiler that allows
+/// more, like clang with `-fbracket-depth`.
+#[derive(Default)]
+pub struct CBackend;
+
+impl Translator for CBackend {
+    fn extension(&self) -> &'static str {
+        "c"
+    }
+
+    fn translate(&self, input: &Input, _output: &Path) -> Translation {
+        let source_name = input.source_name();
+        let compilation = input.compilation;
+        let interner = compilation.interner.borrow();
+        let code = Compiler::new(
+            &compilation.bound_program,
+            &interner,
+            input.source,
+            compilation.unbound_program.comments(),
+            &source_name,
+        )
+        .compile();
+        Translation {
+            code,
+            companions: vec![],
+        }
+    }
+
+    fn toolchain(&self) -> Toolchain {
+        Toolchain {
+            program: var_os("CC").unwrap_or_else(|| OsString::from("cc")),
+            arguments: vec!["-std=c99", "-O2"],
+            description: "The C compiler",
+        }
+    }
+}
+
+impl Backend for CBackend {
+    fn name(&self) -> &'static str {
+        "c"
+    }
+
+    fn run(&self, input: &Input) -> Result<(), BackendError> {
+        write_beside(self, input)
+    }
+}
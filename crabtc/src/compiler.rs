@@ -0,0 +1,546 @@
+use std::{collections::BTreeSet, mem::discriminant};
+
+use crab::{
+    analysis::effects::{is_inert, reads, writes},
+    backend::{
+        layout::{IfChain, Layout},
+        names::Names,
+        usage::Usage,
+        writer::Writer,
+    },
+    binding::{
+        bound_tree::{
+            BoundBinaryExpression, BoundBinaryOperationKind, BoundBlockStatement, BoundExpression,
+            BoundExpressionId, BoundExpressionStatement, BoundForStatement, BoundIfStatement,
+            BoundPrintStatement, BoundProgram, BoundStatement, BoundStatementId,
+            BoundUnaryOperationKind, BoundVarStatement, BoundWhileStatement,
+        },
+        visitor::BoundTreeVisitor,
+    },
+    common::{
+        intern::Interner,
+        types::{Object, Type},
+    },
+    syntax::token::Comment,
+};
+
+use super::{names::names, runtime::Routine};
+
+const INDENT: &str = "    ";
+
+const HEADERS: [&str; 5] = ["stdbool.h", "stdint.h", "stdio.h", "stdlib.h", "string.h"];
+
+/// Compiles a bound program to a single C99 file: the runtime routines the program uses, the
+/// strings it writes, and a `main` with its statements. Comments of the source are carried
+/// over in front of the statement that follows them, or at the end of the line of a one-line
+/// statement they trail, and statements the source separates by an empty line stay separated.
+///
+/// Numbers are `int32_t` and arithmetic goes through routines that fail the way crabi does.
+/// Strings are counted, and every string variable is released at the end of its scope.
+///
+/// C leaves the order in which most operands are evaluated to the compiler, and `&&` and `||`
+/// skip their right operand, where crabi evaluates everything from left to right. When the
+/// order could show, through an assignment or the error an operand stops the program with,
+/// the left operand is evaluated into a temporary first, and both operands of `&&` and `||`
+/// are.
+pub(crate) struct Compiler<'a> {
+    program: &'a BoundProgram,
+    names: Names<'a>,
+    usage: Usage,
+    source_name: &'a str,
+    layout: Layout<'a>,
+    writer: Writer,
+    routines: BTreeSet<Routine>,
+    /// The strings the program writes, as the names of their constants and their text.
+    literals: Vec<(String, String)>,
+    /// The temporaries `main` declares up front, and their types.
+    temporaries: Vec<(String, Type)>,
+    /// The string variables of every scope that is open, to release when it closes.
+    scopes: Vec<Vec<String>>,
+}
+
+impl<'a> Compiler<'a> {
+    pub(crate) fn new(
+        program: &'a BoundProgram,
+        interner: &'a Interner,
+        source: &'a str,
+        comments: &'a [Comment<'a>],
+        source_name: &'a str,
+    ) -> Self {
+        Self {
+            program,
+            names: names(interner),
+            usage: Usage::of(program),
+            source_name,
+            layout: Layout::new(source, comments, comment_text),
+            writer: Writer::new(INDENT).indented(1),
+            routines: BTreeSet::new(),
+            literals: vec![],
+            temporaries: vec![],
+            scopes: vec![],
+        }
+    }
+
+    pub(crate) fn compile(mut self) -> String {
+        self.scopes.push(vec![]);
+        self.compile_statements(self.program.root());
+        self.layout.remaining_comments(&mut self.writer);
+        self.close_scope();
+        let (body, _) = self.writer.finish();
+
+        let mut writer = Writer::new(INDENT);
+        writer.line(&format!(
+            "// Code generated by crabtc from {}. DO NOT EDIT.",
+            self.source_name
+        ));
+        writer.blank_line();
+        for header in HEADERS {
+            writer.line(&format!("#include <{header}>"));
+        }
+        for routine in &self.routines {
+            writer.blank_line();
+            routine.write(&mut writer);
+        }
+        writer.blank_line();
+        for (name, text) in &self.literals {
+            writer.line(&format!(
+                "static crab_string {name} = {{0, {}, {}}};",
+                text.len(),
+                string_literal(text)
+            ));
+        }
+        writer.blank_line();
+        writer.open("int main(void) {");
+        for (name, typ) in &self.temporaries {
+            writer.line(&format!("{};", declaration(typ, name)));
+        }
+        let (mut code, _) = writer.finish();
+        if !self.temporaries.is_empty() && !body.is_empty() {
+            code.push('\n');
+        }
+        code.push_str(&body);
+        code.push_str("}\n");
+        code
+    }
+
+    fn compile_statements(&mut self, statements: &[BoundStatementId]) {
+        for &statement in statements {
+            let position = self.program.statement(statement).get_position();
+            self.layout.comments(&mut self.writer, position.start);
+            self.layout.separate(&mut self.writer, position.start);
+            self.visit_statement(self.program, statement);
+        }
+    }
+
+    /// Compiles the body of an `if`, `while` or `for` into a scope of its own. The braces come
+    /// from the statement, so a block body only contributes its statements.
+    fn compile_body(&mut self, statement: BoundStatementId) {
+        self.scopes.push(vec![]);
+        match self.program.statement(statement) {
+            BoundStatement::Block(block) => self.compile_block_body(block),
+            _ => self.compile_statements(&[statement]),
+        }
+        self.close_scope();
+    }
+
+    fn compile_block_body(&mut self, block: &BoundBlockStatement) {
+        self.compile_statements(&block.statements);
+        self.layout
+            .comments(&mut self.writer, block.get_position().end);
+    }
+
+    /// Releases the strings of the innermost scope, the last declared first.
+    fn close_scope(&mut self) {
+        let strings = self.scopes.pop().unwrap_or_default();
+        for name in strings.iter().rev() {
+            self.writer
+                .line(&format!("{}({name});", Routine::Release.name()));
+        }
+    }
+
+    /// Compiles an `if` and the `else if` chain after it as one statement.
+    fn compile_if_statement(&mut self, statement: &BoundIfStatement) {
+        let chain = IfChain::of(self.program, statement);
+        for (index, branch) in chain.branches.iter().enumerate() {
+            let condition = self.compile_condition(branch.condition);
+            if index == 0 {
+                self.writer.open(&format!("if ({condition}) {{"));
+            } else {
+                self.writer.reopen(&format!("}} else if ({condition}) {{"));
+            }
+            self.compile_body(branch.consequence);
+        }
+        if let Some(alternative) = chain.alternative {
+            self.writer.reopen("} else {");
+            self.compile_body(alternative);
+        }
+        self.writer.close("}");
+    }
+
+    /// Writes a statement that fits on one line, followed by the comment that trails it in the
+    /// source, if any.
+    fn simple_statement(&mut self, line: String, start: usize) {
+        self.layout.simple_statement(&mut self.writer, line, start);
+    }
+
+    /// Adds a routine, and the routines it depends on, to the runtime written with the program.
+    fn use_routine(&mut self, routine: Routine) -> &'static str {
+        if self.routines.insert(routine) {
+            for &dependency in routine.dependencies() {
+                self.use_routine(dependency);
+            }
+        }
+        routine.name()
+    }
+
+    /// The constant that holds a string of the program, shared by all its occurrences.
+    fn literal(&mut self, text: &str) -> String {
+        self.use_routine(Routine::String);
+        if let Some((name, _)) = self.literals.iter().find(|(_, other)| other == text) {
+            return name.clone();
+        }
+        let name = self
+            .names
+            .fresh(&format!("literal{}", self.literals.len() + 1));
+        self.literals.push((name.clone(), text.to_string()));
+        name
+    }
+
+    fn temporary(&mut self, typ: Type) -> String {
+        let name = self
+            .names
+            .fresh(&format!("temporary{}", self.temporaries.len() + 1));
+        self.temporaries.push((name.clone(), typ));
+        name
+    }
+
+    /// Compiles the condition of an `if` or `while`. An assignment gets parentheses of its own,
+    /// which tell C compilers that it is not a mistyped comparison.
+    fn compile_condition(&mut self, expression: BoundExpressionId) -> String {
+        let compiled = self.compile_expression(expression);
+        if matches!(
+            self.program.expression(expression),
+            BoundExpression::Assignment(_)
+        ) && self.is_infix(expression)
+        {
+            format!("({compiled})")
+        } else {
+            compiled
+        }
+    }
+
+    fn compile_expression(&mut self, expression: BoundExpressionId) -> String {
+        let program = self.program;
+        match program.expression(expression) {
+            BoundExpression::Literal(literal) => match &literal.value {
+                Object::Number(i32::MIN) => "INT32_MIN".to_string(),
+                Object::Number(value) => format!("{value}"),
+                Object::Boolean(value) => format!("{value}"),
+                Object::String(value) => format!("&{}", self.literal(value)),
+                Object::Unit => unreachable!("Programs cannot write the unit value."),
+            },
+            BoundExpression::Variable(variable) => {
+                let name = self.names.variable(&variable.variable);
+                match variable.variable.typ {
+                    Type::String => format!("{}({name})", self.use_routine(Routine::Retain)),
+                    _ => name,
+                }
+            }
+            BoundExpression::Unary(unary) => match unary.operator.operation_kind {
+                BoundUnaryOperationKind::Identity => self.compile_expression(unary.right),
+                BoundUnaryOperationKind::LogicalNegation => {
+                    format!("!{}", self.compile_operand(unary.right, None))
+                }
+                BoundUnaryOperationKind::Negation => {
+                    let right = self.compile_expression(unary.right);
+                    match Routine::arithmetic(program, expression) {
+                        Some(routine) => format!("{}({right})", self.use_routine(routine)),
+                        None if right.starts_with('-') => format!("-({right})"),
+                        None => format!("-{right}"),
+                    }
+                }
+            },
+            BoundExpression::Binary(binary) => self.compile_binary_expression(expression, binary),
+            BoundExpression::Assignment(assignment) => {
+                let name = self.names.variable(&assignment.variable);
+                let value = self.compile_expression(assignment.expression);
+                if assignment.variable.typ == Type::String {
+                    format!("{}(&{name}, {value})", self.use_routine(Routine::Assign))
+                } else if self.is_sequenced(expression) {
+                    // C would not say which of the two assignments to the variable wins.
+                    let temporary = self.temporary(assignment.variable.typ.clone());
+                    format!("({temporary} = {value}, {name} = {temporary})")
+                } else {
+                    format!("{name} = {value}")
+                }
+            }
+        }
+    }
+
+    fn compile_binary_expression(
+        &mut self,
+        expression: BoundExpressionId,
+        binary: &BoundBinaryExpression,
+    ) -> String {
+        let program = self.program;
+        let operation = &binary.operator.operation_kind;
+        let routine = match operation {
+            BoundBinaryOperationKind::Addition
+                if program.expression(expression).get_type() == Type::String =>
+            {
+                Some(Routine::Concatenate)
+            }
+            _ => Routine::arithmetic(program, expression),
+        };
+        let logical = is_logical(operation);
+        let sequenced = self.is_sequenced(expression);
+
+        // The operands evaluated up front, as the assignments to their temporaries.
+        let mut sequence = vec![];
+        let left = if sequenced {
+            let value = self.compile_expression(binary.left);
+            let temporary = self.temporary(program.expression(binary.left).get_type());
+            sequence.push(format!("{temporary} = {value}"));
+            temporary
+        } else if routine.is_none() {
+            self.compile_operand(binary.left, Some(operation))
+        } else {
+            self.compile_expression(binary.left)
+        };
+        let right = if sequenced && logical {
+            let value = self.compile_expression(binary.right);
+            let temporary = self.temporary(program.expression(binary.right).get_type());
+            sequence.push(format!("{temporary} = {value}"));
+            temporary
+        } else if routine.is_none() {
+            self.compile_operand(binary.right, None)
+        } else {
+            self.compile_expression(binary.right)
+        };
+
+        let compiled = match routine {
+            Some(routine) => format!("{}({left}, {right})", self.use_routine(routine)),
+            None => format!("{left} {operation} {right}"),
+        };
+        if sequence.is_empty() {
+            compiled
+        } else {
+            format!("({}, {compiled})", sequence.join(", "))
+        }
+    }
+
+    /// Compiles the operand of a unary or infix operator, in parentheses when it is itself
+    /// written with an infix operator, unless it is the left operand of the same operator:
+    /// `a && b && c` reads fine, `a < b == c` does not.
+    fn compile_operand(
+        &mut self,
+        expression: BoundExpressionId,
+        operation: Option<&BoundBinaryOperationKind>,
+    ) -> String {
+        let compiled = self.compile_expression(expression);
+        let chained = match (operation, self.program.expression(expression)) {
+            (Some(operation), BoundExpression::Binary(binary)) => {
+                discriminant(operation) == discriminant(&binary.operator.operation_kind)
+            }
+            _ => false,
+        };
+        if self.is_infix(expression) && !chained {
+            format!("({compiled})")
+        } else {
+            compiled
+        }
+    }
+
+    /// Whether the C code for an expression is written with an infix operator, as opposed to
+    /// a call, a name, a literal or something in parentheses.
+    fn is_infix(&self, expression: BoundExpressionId) -> bool {
+        let program = self.program;
+        match program.expression(expression) {
+            BoundExpression::Unary(unary) => {
+                matches!(
+                    unary.operator.operation_kind,
+                    BoundUnaryOperationKind::Identity
+                ) && self.is_infix(unary.right)
+            }
+            BoundExpression::Binary(binary) => {
+                !self.is_sequenced(expression)
+                    && Routine::arithmetic(program, expression).is_none()
+                    && !(matches!(
+                        binary.operator.operation_kind,
+                        BoundBinaryOperationKind::Addition
+                    ) && program.expression(expression).get_type() == Type::String)
+            }
+            BoundExpression::Assignment(assignment) => {
+                assignment.variable.typ != Type::String && !self.is_sequenced(expression)
+            }
+            BoundExpression::Literal(_) | BoundExpression::Variable(_) => false,
+        }
+    }
+
+    /// Whether an expression must evaluate an operand into a temporary to keep crabi's order.
+    /// The order of two operands shows when one can assign a variable the other reads, or when
+    /// both can stop the program, with different errors. `&&` and `||` evaluate their right
+    /// operand into a temporary unless skipping it would go unnoticed.
+    fn is_sequenced(&self, expression: BoundExpressionId) -> bool {
+        let program = self.program;
+        match program.expression(expression) {
+            BoundExpression::Binary(binary) if is_logical(&binary.operator.operation_kind) => {
+                !is_inert(program, binary.right)
+            }
+            BoundExpression::Binary(binary) => {
+                let (left, right) = (binary.left, binary.right);
+                let unordered = is_inert(program, right)
+                    && (!writes(program, left, None) || !reads(program, right))
+                    || is_inert(program, left)
+                        && (!writes(program, right, None) || !reads(program, left));
+                !unordered
+            }
+            BoundExpression::Assignment(assignment) => {
+                assignment.variable.typ != Type::String
+                    && writes(program, assignment.expression, Some(assignment.variable.id))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Statements are compiled as the visitor reaches them. Expressions are not visited: they are
+/// compiled to strings, with the surrounding expression in mind.
+impl BoundTreeVisitor for Compiler<'_> {
+    fn visit_expression_statement(
+        &mut self,
+        program: &BoundProgram,
+        statement: &BoundExpressionStatement,
+    ) {
+        let expression = statement.expression;
+        let line = match program.expression(expression) {
+            BoundExpression::Assignment(assignment) if assignment.variable.typ == Type::String => {
+                let name = self.names.variable(&assignment.variable);
+                let value = self.compile_expression(assignment.expression);
+                format!("{}(&{name}, {value});", self.use_routine(Routine::Set))
+            }
+            BoundExpression::Assignment(_) => format!("{};", self.compile_expression(expression)),
+            value if value.get_type() == Type::String => {
+                let value = self.compile_expression(expression);
+                format!("{}({value});", self.use_routine(Routine::Release))
+            }
+            _ => format!("(void){};", self.compile_operand(expression, None)),
+        };
+        self.simple_statement(line, statement.get_position().start);
+    }
+
+    fn visit_print_statement(&mut self, program: &BoundProgram, statement: &BoundPrintStatement) {
+        let routine = match program.expression(statement.expression).get_type() {
+            Type::Number => Routine::PrintNumber,
+            Type::Boolean => Routine::PrintBoolean,
+            Type::String => Routine::PrintString,
+            Type::Unit => unreachable!("Programs cannot print the unit value."),
+        };
+        let line = format!(
+            "{}({});",
+            self.use_routine(routine),
+            self.compile_expression(statement.expression)
+        );
+        self.simple_statement(line, statement.get_position().start);
+    }
+
+    fn visit_var_statement(&mut self, _program: &BoundProgram, statement: &BoundVarStatement) {
+        let value = self.compile_expression(statement.expression);
+        let name = self.names.variable(&statement.variable);
+        let line = format!("{} = {value};", declaration(&statement.variable.typ, &name));
+        self.simple_statement(line, statement.get_position().start);
+        if statement.variable.typ == Type::String {
+            self.use_routine(Routine::Release);
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.push(name);
+            }
+        } else if !self.usage.reads.contains(&statement.variable.id) {
+            self.writer.line(&format!("(void){name};"));
+        }
+    }
+
+    fn visit_block_statement(&mut self, _program: &BoundProgram, statement: &BoundBlockStatement) {
+        self.writer.open("{");
+        self.scopes.push(vec![]);
+        self.compile_block_body(statement);
+        self.close_scope();
+        self.writer.close("}");
+    }
+
+    fn visit_if_statement(&mut self, _program: &BoundProgram, statement: &BoundIfStatement) {
+        self.compile_if_statement(statement);
+    }
+
+    fn visit_while_statement(&mut self, _program: &BoundProgram, statement: &BoundWhileStatement) {
+        let condition = self.compile_condition(statement.condition);
+        self.writer.open(&format!("while ({condition}) {{"));
+        self.compile_body(statement.body);
+        self.writer.close("}");
+    }
+
+    fn visit_for_statement(&mut self, _program: &BoundProgram, statement: &BoundForStatement) {
+        // Like crabi, evaluate both bounds once, and after every iteration test the value the
+        // body left in the variable before incrementing it.
+        let lower_bound = self.compile_expression(statement.lower_bound);
+        let upper_bound = self.compile_expression(statement.upper_bound);
+        let variable = self.names.variable(&statement.variable);
+        let counter = self.names.fresh(&format!("{variable}_counter"));
+        let limit = self.names.fresh(&format!("{variable}_limit"));
+        let add = self.use_routine(Routine::Add);
+        self.writer.open("{");
+        self.writer
+            .line(&format!("int32_t {variable} = {lower_bound};"));
+        self.writer.open(&format!(
+            "for (int32_t {counter} = {variable}, {limit} = {upper_bound}; {counter} < {limit}; {counter} = {variable}, {variable} = {add}({variable}, 1)) {{"
+        ));
+        self.compile_body(statement.body);
+        self.writer.close("}");
+        self.writer.close("}");
+    }
+}
+
+fn is_logical(operation: &BoundBinaryOperationKind) -> bool {
+    matches!(
+        operation,
+        BoundBinaryOperationKind::LogicalAnd | BoundBinaryOperationKind::LogicalOr
+    )
+}
+
+/// Declares a variable of a crab type.
+fn declaration(typ: &Type, name: &str) -> String {
+    match typ {
+        Type::Number => format!("int32_t {name}"),
+        Type::Boolean => format!("bool {name}"),
+        Type::String => format!("crab_string *{name}"),
+        Type::Unit => unreachable!("Programs cannot declare unit variables."),
+    }
+}
+
+/// Writes text as a C string literal. Everything but printable ASCII is escaped, so the file
+/// reads the same in any character set, and so is `?`, which could start a trigraph.
+pub(crate) fn string_literal(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in text.bytes() {
+        match byte {
+            b'\\' => literal.push_str("\\\\"),
+            b'"' => literal.push_str("\\\""),
+            b'?' => literal.push_str("\\?"),
+            b'\n' => literal.push_str("\\n"),
+            b'\t' => literal.push_str("\\t"),
+            b' '..=b'~' => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// The text of a comment, without the backslashes at its end that would carry it over to the
+/// next line of the C code.
+fn comment_text(text: &str) -> &str {
+    let mut text = text.trim_end();
+    while let Some(rest) = text.strip_suffix('\\').or_else(|| text.strip_suffix("??/")) {
+        text = rest.trim_end();
+    }
+    text
+}
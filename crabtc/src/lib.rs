@@ -0,0 +1,6 @@
+mod backend;
+mod compiler;
+mod names;
+mod runtime;
+
+pub use backend::CBackend;
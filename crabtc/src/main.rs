@@ -0,0 +1,6 @@
+use crab::backend::driver::drive;
+use crabtc::CBackend;
+
+fn main() {
+    drive("crabtc", CBackend);
+}
@@ -0,0 +1,79 @@
+use crab::{
+    backend::names::{Names, Scoping},
+    common::intern::Interner,
+};
+
+use super::runtime::Routine;
+
+/// C99's keywords, which can never name anything.
+const KEYWORDS: [&str; 37] = [
+    "auto",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "_Bool",
+    "_Complex",
+    "_Imaginary",
+];
+
+/// Names the headers define as macros or types that `main` relies on, so a variable must not
+/// hide them. Macros written in capitals are caught by their spelling instead.
+const RESERVED: [&str; 13] = [
+    "bool", "errno", "false", "int32_t", "int64_t", "main", "size_t", "stderr", "stdin", "stdout",
+    "true", "FILE", "NULL",
+];
+
+/// Turns the variables of a program into C identifiers. C has no shadowing within an
+/// initializer, `var x = x + 1` would read the new `x`, so every variable gets an identifier
+/// of its own.
+pub(crate) fn names(interner: &Interner) -> Names<'_> {
+    Names::new(interner, &KEYWORDS, is_reserved, Scoping::Distinct)
+}
+
+/// Whether a name other than a keyword could be mistaken for something else: a name from the
+/// headers or the runtime, or one the C standard keeps for the compiler, which starts with two
+/// underscores or an underscore and a capital.
+fn is_reserved(name: &str) -> bool {
+    RESERVED.contains(&name)
+        || Routine::ALL.iter().any(|routine| routine.name() == name)
+        || name.starts_with("__")
+        || name.starts_with("_") && name[1..].starts_with(|first: char| first.is_ascii_uppercase())
+        || is_macro_like(name)
+}
+
+/// Whether a name is spelled like the macros of the standard headers, like `EOF` or
+/// `INT32_MAX`.
+fn is_macro_like(name: &str) -> bool {
+    name.chars().any(|character| character.is_ascii_uppercase())
+        && !name.chars().any(|character| character.is_ascii_lowercase())
+}
@@ -0,0 +1,239 @@
+use crab::{
+    backend::writer::Writer,
+    binding::bound_tree::{BoundExpressionId, BoundProgram},
+    common::arithmetic::{ArithmeticError, CheckedOperation},
+};
+
+use super::compiler::string_literal;
+
+/// The runtime of the generated code: a type for strings and the functions the program calls.
+/// Only the routines a program uses are written, in the order of this enum, which puts every
+/// routine after the ones it calls.
+///
+/// Arithmetic is checked, since C's wraps around or is undefined where crab reports a runtime
+/// error and exits with 70. Strings are immutable and counted: every string expression hands
+/// its value over with a reference of its own, which whatever consumes it releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Routine {
+    String,
+    Fail,
+    Negate,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    PrintNumber,
+    PrintBoolean,
+    Retain,
+    Release,
+    Concatenate,
+    PrintString,
+    Set,
+    Assign,
+}
+
+impl Routine {
+    pub(crate) const ALL: [Routine; 15] = [
+        Routine::String,
+        Routine::Fail,
+        Routine::Negate,
+        Routine::Add,
+        Routine::Subtract,
+        Routine::Multiply,
+        Routine::Divide,
+        Routine::PrintNumber,
+        Routine::PrintBoolean,
+        Routine::Retain,
+        Routine::Release,
+        Routine::Concatenate,
+        Routine::PrintString,
+        Routine::Set,
+        Routine::Assign,
+    ];
+
+    /// The checked routine that computes an arithmetic expression, if C's operator would not do.
+    pub(crate) fn arithmetic(
+        program: &BoundProgram,
+        expression: BoundExpressionId,
+    ) -> Option<Routine> {
+        CheckedOperation::of(program, expression).map(Routine::from)
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Routine::String => "crab_string",
+            Routine::Fail => "crab_fail",
+            Routine::Negate => "crab_negate",
+            Routine::Add => "crab_add",
+            Routine::Subtract => "crab_subtract",
+            Routine::Multiply => "crab_multiply",
+            Routine::Divide => "crab_divide",
+            Routine::PrintNumber => "crab_print_number",
+            Routine::PrintBoolean => "crab_print_boolean",
+            Routine::Retain => "crab_retain",
+            Routine::Release => "crab_release",
+            Routine::Concatenate => "crab_concatenate",
+            Routine::PrintString => "crab_print_string",
+            Routine::Set => "crab_set",
+            Routine::Assign => "crab_assign",
+        }
+    }
+
+    /// The routines this one calls, or whose type it uses.
+    pub(crate) fn dependencies(self) -> &'static [Routine] {
+        match self {
+            Routine::String | Routine::Fail | Routine::PrintNumber | Routine::PrintBoolean => &[],
+            Routine::Negate
+            | Routine::Add
+            | Routine::Subtract
+            | Routine::Multiply
+            | Routine::Divide => &[Routine::Fail],
+            Routine::Retain | Routine::Release => &[Routine::String],
+            Routine::Concatenate => &[Routine::Fail, Routine::Release],
+            Routine::PrintString | Routine::Set => &[Routine::Release],
+            Routine::Assign => &[Routine::Set, Routine::Retain],
+        }
+    }
+
+    fn operator(self) -> &'static str {
+        match self {
+            Routine::Add => "+",
+            Routine::Multiply => "*",
+            Routine::Divide => "/",
+            _ => "-",
+        }
+    }
+
+    pub(crate) fn write(self, writer: &mut Writer) {
+        let overflow = fail(ArithmeticError::Overflow, self.operator());
+        match self {
+            Routine::String => {
+                writer.line("// Strings written in the program are never freed and have no count.");
+                writer.open("typedef struct crab_string {");
+                writer.line("size_t references;");
+                writer.line("size_t length;");
+                writer.line("const char *bytes;");
+                writer.close("} crab_string;");
+                return;
+            }
+            Routine::Fail => {
+                writer.open("static void crab_fail(const char *message) {");
+                writer.line("fprintf(stderr, \"Runtime error: %s.\\n\", message);");
+                writer.line("exit(70);");
+            }
+            Routine::Negate => {
+                writer.open("static int32_t crab_negate(int32_t right) {");
+                writer.open("if (right == INT32_MIN) {");
+                writer.line(&overflow);
+                writer.close("}");
+                writer.line("return -right;");
+            }
+            // Sums, differences and products are computed in 64 bits, where they cannot
+            // overflow, and checked against the range of `int32_t`.
+            Routine::Add | Routine::Subtract | Routine::Multiply => {
+                writer.open(&format!(
+                    "static int32_t {}(int32_t left, int32_t right) {{",
+                    self.name()
+                ));
+                writer.line(&format!(
+                    "int64_t result = (int64_t)left {} right;",
+                    self.operator()
+                ));
+                writer.open("if (result < INT32_MIN || result > INT32_MAX) {");
+                writer.line(&overflow);
+                writer.close("}");
+                writer.line("return (int32_t)result;");
+            }
+            Routine::Divide => {
+                writer.open("static int32_t crab_divide(int32_t left, int32_t right) {");
+                writer.open("if (right == 0) {");
+                writer.line(&fail(ArithmeticError::DivisionByZero, self.operator()));
+                writer.close("}");
+                writer.open("if (left == INT32_MIN && right == -1) {");
+                writer.line(&overflow);
+                writer.close("}");
+                writer.line("return left / right;");
+            }
+            Routine::PrintNumber => {
+                writer.open("static void crab_print_number(int32_t number) {");
+                writer.line("printf(\"%ld\\n\", (long)number);");
+            }
+            Routine::PrintBoolean => {
+                writer.open("static void crab_print_boolean(bool boolean) {");
+                writer.line("puts(boolean ? \"true\" : \"false\");");
+            }
+            Routine::Retain => {
+                writer.open("static crab_string *crab_retain(crab_string *string) {");
+                writer.open("if (string->references > 0) {");
+                writer.line("string->references++;");
+                writer.close("}");
+                writer.line("return string;");
+            }
+            Routine::Release => {
+                writer.open("static void crab_release(crab_string *string) {");
+                writer.open("if (string->references > 0 && --string->references == 0) {");
+                writer.line("free(string);");
+                writer.close("}");
+            }
+            Routine::Concatenate => {
+                writer.open(
+                    "static crab_string *crab_concatenate(crab_string *left, crab_string *right) {",
+                );
+                writer.line("size_t length = left->length + right->length;");
+                writer.line("crab_string *result = malloc(sizeof(crab_string) + length);");
+                writer.line("char *bytes;");
+                writer.open("if (result == NULL) {");
+                writer.line("crab_fail(\"Out of memory\");");
+                writer.close("}");
+                writer.line("bytes = (char *)(result + 1);");
+                writer.line("memcpy(bytes, left->bytes, left->length);");
+                writer.line("memcpy(bytes + left->length, right->bytes, right->length);");
+                writer.line("result->references = 1;");
+                writer.line("result->length = length;");
+                writer.line("result->bytes = bytes;");
+                writer.line("crab_release(left);");
+                writer.line("crab_release(right);");
+                writer.line("return result;");
+            }
+            Routine::PrintString => {
+                writer.open("static void crab_print_string(crab_string *string) {");
+                writer.line("fwrite(string->bytes, 1, string->length, stdout);");
+                writer.line("putchar('\\n');");
+                writer.line("crab_release(string);");
+            }
+            Routine::Set => {
+                writer.open("static void crab_set(crab_string **variable, crab_string *value) {");
+                writer.line("crab_release(*variable);");
+                writer.line("*variable = value;");
+            }
+            Routine::Assign => {
+                writer.open(
+                    "static crab_string *crab_assign(crab_string **variable, crab_string *value) {",
+                );
+                writer.line("crab_set(variable, value);");
+                writer.line("return crab_retain(value);");
+            }
+        }
+        writer.close("}");
+    }
+}
+
+impl From<CheckedOperation> for Routine {
+    fn from(operation: CheckedOperation) -> Self {
+        match operation {
+            CheckedOperation::Negation => Routine::Negate,
+            CheckedOperation::Addition => Routine::Add,
+            CheckedOperation::Subtraction => Routine::Subtract,
+            CheckedOperation::Multiplication => Routine::Multiply,
+            CheckedOperation::Division => Routine::Divide,
+        }
+    }
+}
+
+fn fail(error: ArithmeticError, operator: &str) -> String {
+    format!(
+        "{}({});",
+        Routine::Fail.name(),
+        string_literal(&error.message(operator))
+    )
+}
@@ -0,0 +1,138 @@
+//! Checks crabtc's command line: where the C code goes, checking without writing, exit codes,
+//! and handing the code to the C compiler named by `CC`.
+
+use std::{
+    env::temp_dir,
+    fs::{read_to_string, remove_file, write},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn crabtc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_crabtc"))
+        .args(args)
+        .output()
+        .expect("Could not run crabtc.")
+}
+
+fn program(name: &str, source: &str) -> PathBuf {
+    let path = temp_dir().join(format!("crabtc_cli_{name}.crab"));
+    write(&path, source).unwrap();
+    let _ = remove_file(path.with_extension("c"));
+    path
+}
+
+fn text(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn output_path_and_stdout() {
+    let path = program("output", "print 1\n");
+    assert!(crabtc(&[text(&path)]).status.success());
+    let beside = read_to_string(path.with_extension("c")).unwrap();
+    assert!(beside.starts_with("// Code generated by crabtc from crabtc_cli_output.crab."));
+
+    let output = temp_dir().join("crabtc_cli_elsewhere.c");
+    let _ = remove_file(&output);
+    assert!(crabtc(&["-o", text(&output), text(&path)]).status.success());
+    assert_eq!(read_to_string(&output).unwrap(), beside);
+
+    let printed = crabtc(&["--stdout", text(&path)]);
+    assert!(printed.status.success());
+    assert_eq!(String::from_utf8_lossy(&printed.stdout), beside);
+}
+
+#[test]
+fn check_writes_nothing() {
+    let path = program("check", "print 1\n");
+    assert!(crabtc(&["check", text(&path)]).status.success());
+    assert!(!path.with_extension("c").exists());
+
+    let path = program("check_errors", "print missing\n");
+    assert_eq!(crabtc(&["check", text(&path)]).status.code(), Some(65));
+}
+
+#[test]
+fn invalid_arguments_exit_with_65() {
+    let path = program("arguments", "print 1\n");
+    for args in [
+        &[][..],
+        &["--unknown", text(&path)],
+        &["-o"],
+        &["--stdout", "-o", "out.c", text(&path)],
+        &["run", "-o", "out", text(&path)],
+        &["build", "--stdout", text(&path)],
+    ] {
+        let output = crabtc(args);
+        assert_eq!(output.status.code(), Some(65), "{args:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Usage: crabtc"));
+    }
+    assert_eq!(
+        crabtc(&[text(&temp_dir().join("crabtc_cli_missing.crab"))])
+            .status
+            .code(),
+        Some(66)
+    );
+}
+
+#[test]
+fn a_missing_compiler_exits_with_69() {
+    let path = program("no_compiler", "print 1\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_crabtc"))
+        .env("CC", "crabtc-no-such-compiler")
+        .arg("build")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(69));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'crabtc-no-such-compiler'"));
+}
+
+#[test]
+fn run_exits_like_the_program() {
+    if !Command::new("cc")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+    {
+        eprintln!("No C compiler is installed; skipping.");
+        return;
+    }
+    let path = program("run", "print \"hi\"\n");
+    let output = crabtc(&["run", text(&path)]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi\n");
+
+    let path = program("run_failure", "var zero = 0\nprint 1\nprint 1 / zero\n");
+    let output = crabtc(&["run", text(&path)]);
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Runtime error: Division by zero."));
+}
+
+#[test]
+fn the_nesting_limit_is_an_option() {
+    let path = program("nesting", "print ((((1))))\n");
+    assert!(crabtc(&["--stdout", text(&path)]).status.success());
+    let output = crabtc(&["--stdout", "--max-depth", "3", text(&path)]);
+    assert_eq!(output.status.code(), Some(65));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Error: Nesting is deeper than 3 levels.")
+    );
+    for invalid in ["0", "deep"] {
+        let output = crabtc(&["--stdout", "--max-depth", invalid, text(&path)]);
+        assert_eq!(output.status.code(), Some(65), "{invalid}");
+    }
+    assert_eq!(crabtc(&["--max-depth"]).status.code(), Some(65));
+}
+
+#[test]
+fn literals_out_of_range_are_rejected() {
+    let path = program("out_of_range", "print 3000000000\n");
+    let output = crabtc(&["--stdout", text(&path)]);
+    assert_eq!(output.status.code(), Some(65));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Error: Integer literal '3000000000' is out of range."));
+}
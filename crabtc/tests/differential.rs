@@ -0,0 +1,209 @@
+//! Transpiles the differential corpus and, when a C compiler is installed, builds the generated
+//! code as strict C99, runs it and compares what it prints with the expected output that crabi
+//! is held to. Programs in `failures/` must exit like crabi does on a runtime error, with the
+//! same message.
+
+use std::{
+    env::temp_dir,
+    fs::{copy, read_dir, read_to_string, write},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn transpile(source: &Path, args: &[&str]) -> PathBuf {
+    let path = temp_dir().join(format!(
+        "crabtc_differential_{}",
+        source.file_name().unwrap().to_string_lossy()
+    ));
+    copy(source, &path).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_crabtc"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .expect("Could not run crabtc.");
+    assert!(output.status.success(), "{} failed", source.display());
+    path.with_extension("c")
+}
+
+fn cc_is_installed() -> bool {
+    Command::new("cc")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Builds the C code with every warning an error, so that it stays portable, and runs it.
+fn build_and_run(c: &Path) -> Output {
+    let executable = c.with_extension("out");
+    let build = Command::new("cc")
+        .args(["-std=c99", "-pedantic", "-Wall", "-Wextra", "-Werror", "-o"])
+        .arg(&executable)
+        .arg(c)
+        .output()
+        .unwrap();
+    assert!(
+        build.status.success(),
+        "{} does not compile:\n{}",
+        c.display(),
+        String::from_utf8_lossy(&build.stderr)
+    );
+    Command::new(&executable).output().unwrap()
+}
+
+fn corpus(directory: &str) -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join(directory);
+    read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "crab")
+        })
+        .collect()
+}
+
+#[test]
+fn generated_c_prints_the_expected_output() {
+    let run = cc_is_installed();
+    if !run {
+        eprintln!("No C compiler is installed; only checking that the corpus transpiles.");
+    }
+    for path in corpus("../tests/corpus") {
+        for args in [&[][..], &["-O"]] {
+            let c = transpile(&path, args);
+            if run {
+                let output = build_and_run(&c);
+                assert!(output.status.success(), "{}", path.display());
+                assert_eq!(
+                    String::from_utf8_lossy(&output.stdout),
+                    read_to_string(path.with_extension("out")).unwrap(),
+                    "The C code for {} {args:?} prints something else",
+                    path.display()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn generated_c_fails_like_crabi() {
+    let run = cc_is_installed();
+    for path in corpus("../tests/corpus/failures") {
+        for args in [&[][..], &["-O"]] {
+            let c = transpile(&path, args);
+            if run {
+                let output = build_and_run(&c);
+                assert_eq!(
+                    output.status.code(),
+                    Some(70),
+                    "{} {args:?}",
+                    path.display()
+                );
+                assert_eq!(
+                    String::from_utf8_lossy(&output.stdout),
+                    read_to_string(path.with_extension("out")).unwrap(),
+                    "The C code for {} {args:?} prints something else",
+                    path.display()
+                );
+                let error = read_to_string(path.with_extension("err")).unwrap();
+                assert!(String::from_utf8_lossy(&output.stderr)
+                    .contains(&format!("Runtime error: {}.", error.trim_end())));
+            }
+        }
+    }
+}
+
+#[test]
+fn arithmetic_is_checked() {
+    let c = read_to_string(transpile(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus/arithmetic.crab"),
+        &[],
+    ))
+    .unwrap();
+    for expected in [
+        "    int32_t largest = 2147483647;\n",
+        "    int32_t smallest = crab_subtract(crab_negate(largest), 1);\n",
+        "    crab_print_number(crab_multiply(largest, -1));\n",
+        "static int32_t crab_negate(int32_t right) {",
+        "        crab_fail(\"Division by zero\");\n",
+        "    exit(70);\n",
+    ] {
+        assert!(c.contains(expected), "Expected {expected:?} in:\n{c}");
+    }
+}
+
+#[test]
+fn operands_are_evaluated_from_left_to_right() {
+    let path = temp_dir().join("crabtc_differential_order.crab");
+    write(
+        &path,
+        "var a = 1\nvar b = false\nprint b && (a = 5) == 5\nprint (a = 2) + a\nprint a + 1 < 3 && b\nprint (a < 2) == b\n",
+    )
+    .unwrap();
+    let c = read_to_string(transpile(&path, &[])).unwrap();
+    for expected in [
+        "    bool temporary1;\n    bool temporary2;\n    int32_t temporary3;\n\n",
+        "crab_print_boolean((temporary1 = b, temporary2 = (a = 5) == 5, temporary1 && temporary2));",
+        "crab_print_number((temporary3 = a = 2, crab_add(temporary3, a)));",
+        "crab_print_boolean((crab_add(a, 1) < 3) && b);",
+        "crab_print_boolean((a < 2) == b);",
+    ] {
+        assert!(c.contains(expected), "Expected {expected} in:\n{c}");
+    }
+}
+
+#[test]
+fn strings_are_released_at_the_end_of_their_scope() {
+    let path = temp_dir().join("crabtc_differential_strings.crab");
+    write(
+        &path,
+        "var s = \"a\"\n{\n    var t = s + \"b\"\n    s = t\n}\nprint s\n",
+    )
+    .unwrap();
+    let c = read_to_string(transpile(&path, &[])).unwrap();
+    assert!(
+        c.contains("static crab_string literal1 = {0, 1, \"a\"};\n"),
+        "{c}"
+    );
+    assert!(
+        c.contains(
+            "    {\n        crab_string *t = crab_concatenate(crab_retain(s), &literal2);\n        crab_set(&s, crab_retain(t));\n        crab_release(t);\n    }\n"
+        ),
+        "{c}"
+    );
+    assert!(
+        c.ends_with("    crab_print_string(crab_retain(s));\n    crab_release(s);\n}\n"),
+        "{c}"
+    );
+}
+
+#[test]
+fn c_keywords_and_shadowed_names_are_mangled() {
+    let path = temp_dir().join("crabtc_differential_names.crab");
+    write(
+        &path,
+        "var int = 1\nvar EOF = 2\nvar x = int + EOF\n{\n    var x = x + 1\n    print x\n}\nprint x\n",
+    )
+    .unwrap();
+    let c = read_to_string(transpile(&path, &[])).unwrap();
+    for expected in [
+        "    int32_t int_ = 1;\n",
+        "    int32_t EOF_ = 2;\n",
+        "    int32_t x = crab_add(int_, EOF_);\n",
+        "        int32_t x_ = crab_add(x, 1);\n",
+        "        crab_print_number(x_);\n",
+    ] {
+        assert!(c.contains(expected), "Expected {expected:?} in:\n{c}");
+    }
+}
+
+#[test]
+fn string_literals_are_escaped() {
+    let path = temp_dir().join("crabtc_differential_escapes.crab");
+    write(&path, "print \"a\\b??/\n\u{fc}\"\n").unwrap();
+    let c = read_to_string(transpile(&path, &[])).unwrap();
+    assert!(
+        c.contains("static crab_string literal1 = {0, 9, \"a\\\\b\\?\\?/\\n\\303\\274\"};\n"),
+        "{c}"
+    );
+}
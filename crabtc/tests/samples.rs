@@ -0,0 +1,36 @@
+//! Transpiles the samples in `tests/` that have a C twin and checks that the output is exactly
+//! the committed file.
+
+use std::{
+    fs::{read_dir, read_to_string},
+    path::Path,
+    process::Command,
+};
+
+#[test]
+fn samples_match_their_c_twins() {
+    let samples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests");
+    for entry in read_dir(samples).unwrap() {
+        let path = entry.unwrap().path();
+        let twin = path.with_extension("c");
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "crab")
+            && twin.exists()
+        {
+            let output = Command::new(env!("CARGO_BIN_EXE_crabtc"))
+                .arg("--stdout")
+                .arg(&path)
+                .output()
+                .expect("Could not run crabtc.");
+            assert!(output.status.success(), "{} failed", path.display());
+            assert_eq!(
+                String::from_utf8_lossy(&output.stdout),
+                read_to_string(&twin).unwrap(),
+                "{} does not transpile to {}",
+                path.display(),
+                twin.display()
+            );
+        }
+    }
+}
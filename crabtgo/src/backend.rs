@@ -0,0 +1,143 @@
+use std::{ffi::OsString, path::Path};
+
+use crab::backend::{
+    driver::{write_beside, Mode, Toolchain, Translation, Translator},
+    file_name, Backend, BackendError, Input,
+};
+
+use super::{
+    compiler::{Compiler, Options},
+    names::is_identifier,
+    source_map::source_map,
+};
+
+/// What the Go backend writes besides the program itself.
+pub struct GoOptions {
+    /// The name in the package clause.
+    pub package: String,
+    /// Start with a comment saying the code is generated.
+    pub header: bool,
+    /// Precede every statement with a `//line` directive pointing into the source.
+    pub line_directives: bool,
+    /// Write a source map beside the Go code.
+    pub source_map: bool,
+}
+
+impl Default for GoOptions {
+    fn default() -> Self {
+        Self {
+            package: "main".to_string(),
+            header: false,
+            line_directives: false,
+            source_map: false,
+        }
+    }
+}
+
+/// Compiles programs to Go. As a backend, it writes the code beside the source, in a file with
+/// the extension `.go`, and on the command line, `go build` builds it.
+///
+/// The Go code nests as deeply as the program, which the compilation's nesting limit bounds, and
+/// it never recurses: Crab has no functions, so the only calls are to the arithmetic helpers and
+/// to the function literals that assignments used as values become, which nest no deeper than
+/// the expression they are in. Goroutine stacks grow as they need to, so the generated program
+/// cannot overflow its stack, whatever limit it was compiled with.
+pub struct GoBackend {
+    options: GoOptions,
+}
+
+impl GoBackend {
+    pub fn new(options: GoOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Translator for GoBackend {
+    fn extension(&self) -> &'static str {
+        "go"
+    }
+
+    fn usage(&self) -> &'static str {
+        "[--package NAME] [--header] [--line-directives] [--source-map]"
+    }
+
+    fn parse_option(
+        &mut self,
+        option: &str,
+        value: &mut dyn FnMut() -> Option<String>,
+    ) -> Result<bool, String> {
+        match option {
+            "--header" => self.options.header = true,
+            "--line-directives" => self.options.line_directives = true,
+            "--source-map" => self.options.source_map = true,
+            "--package" => {
+                self.options.package = value().ok_or("'--package' needs a value.")?;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn check_options(&self, mode: Mode, stdout: bool) -> Result<(), String> {
+        let package = &self.options.package;
+        if stdout && self.options.source_map {
+            return Err("'--stdout' writes no files, so it takes no '--source-map'.".to_string());
+        }
+        if !is_package_name(package) {
+            return Err(format!("'{package}' is not a valid Go package name."));
+        }
+        if matches!(mode, Mode::Build | Mode::Run) && package != "main" {
+            return Err("Only package 'main' can be built or run.".to_string());
+        }
+        Ok(())
+    }
+
+    /// Compiles a program to Go and, with `source_map`, maps the code back to the source.
+    fn translate(&self, input: &Input, output: &Path) -> Translation {
+        let source_name = input.source_name();
+        let compilation = input.compilation;
+        let interner = compilation.interner.borrow();
+        let (code, mappings) = Compiler::new(
+            &compilation.bound_program,
+            &interner,
+            input.source,
+            compilation.unbound_program.comments(),
+            Options {
+                source_name: &source_name,
+                package: &self.options.package,
+                header: self.options.header,
+                line_directives: self.options.line_directives,
+            },
+        )
+        .compile();
+        let mut companions = vec![];
+        if self.options.source_map {
+            let map = source_map(&file_name(output), &source_name, &mappings);
+            companions.push((".map", map));
+        }
+        Translation { code, companions }
+    }
+
+    fn toolchain(&self) -> Toolchain {
+        Toolchain {
+            program: OsString::from("go"),
+            arguments: vec!["build"],
+            description: "The Go toolchain",
+        }
+    }
+}
+
+impl Backend for GoBackend {
+    fn name(&self) -> &'static str {
+        "go"
+    }
+
+    fn run(&self, input: &Input) -> Result<(), BackendError> {
+        write_beside(self, input)
+    }
+}
+
+/// Whether Go accepts a name in a package clause.
+pub fn is_package_name(name: &str) -> bool {
+    is_identifier(name) && name != "_"
+}
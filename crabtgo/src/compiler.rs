@@ -1,124 +1,569 @@
 use crab::{
-    binding::bound_tree::{BoundExpression, BoundStatement},
-    common::types::Object,
+    analysis::effects::{is_inert, reads, writes},
+    backend::{
+        layout::{IfChain, Layout},
+        names::Names,
+        usage::Usage,
+        writer::{Mapping, Writer},
+    },
+    binding::{
+        bound_tree::{
+            BoundAssignmentExpression, BoundBinaryExpression, BoundBinaryOperationKind,
+            BoundBlockStatement, BoundExpression, BoundExpressionId, BoundExpressionStatement,
+            BoundForStatement, BoundIfStatement, BoundPrintStatement, BoundProgram, BoundStatement,
+            BoundStatementId, BoundVarStatement, BoundWhileStatement,
+        },
+        visitor::BoundTreeVisitor,
+    },
+    common::{
+        diagnostic::Position,
+        intern::Interner,
+        types::{Object, Type},
+    },
+    syntax::token::Comment,
 };
 
-pub(crate) struct Compiler {
-    bound_statements: Vec<BoundStatement>,
+use super::{
+    names::names,
+    runtime::{write_fail, Helper},
+};
+
+/// Compiles a bound program to a Go `main` package, laid out as gofmt would lay it out.
+/// Comments of the source are carried over in front of the statement that follows them, or at
+/// the end of the line of a one-line statement they trail, and statements the source separates
+/// by an empty line stay separated.
+///
+/// Go rejects some programs that crab accepts: expressions that are not assignments cannot
+/// stand alone, and variables must be read. Such expressions are assigned to the blank
+/// identifier, which still evaluates them, and so are variables that are only ever written.
+/// `fmt` is only imported by programs that print.
+///
+/// Numbers are `int32`, like crab's, and arithmetic that Go would let wrap around goes through
+/// helpers that fail the way crabi does.
+///
+/// Go skips the right operand of `&&` and `||` when the left one decides the result, and only
+/// orders the calls in an expression, not when it reads a variable, where crabi evaluates every
+/// operand from left to right. When that could show, through an assignment or the error an
+/// operand stops the program with, the operands are stored in variables of a function literal
+/// one after the other before they are combined.
+///
+/// Every statement is mapped back to the line and column it starts at in the source, for the
+/// `//line` directives and source maps.
+pub(crate) struct Compiler<'a> {
+    program: &'a BoundProgram,
+    names: Names<'a>,
+    usage: Usage,
+    source: &'a str,
+    layout: Layout<'a>,
+    options: Options<'a>,
+    writer: Writer,
+}
+
+/// What the compiler writes besides the program itself.
+pub(crate) struct Options<'a> {
+    /// The name of the source file, as the generated code refers to it.
+    pub(crate) source_name: &'a str,
+    /// The name in the package clause.
+    pub(crate) package: &'a str,
+    /// Start with a comment saying the code is generated.
+    pub(crate) header: bool,
+    /// Precede every statement with a `//line` directive pointing into the source.
+    pub(crate) line_directives: bool,
 }
 
-impl Compiler {
-    pub(crate) fn new(bound_statements: Vec<BoundStatement>) -> Self {
-        Self { bound_statements }
+impl<'a> Compiler<'a> {
+    pub(crate) fn new(
+        program: &'a BoundProgram,
+        interner: &'a Interner,
+        source: &'a str,
+        comments: &'a [Comment<'a>],
+        options: Options<'a>,
+    ) -> Self {
+        let mut writer = Writer::new("\t");
+        if options.line_directives {
+            let file = options.source_name.to_string();
+            writer = writer.with_directives(Box::new(move |line, column| {
+                format!("//line {file}:{line}:{column}")
+            }));
+        }
+        Self {
+            program,
+            names: names(interner),
+            usage: Usage::of(program),
+            source,
+            layout: Layout::new(source, comments, str::trim_end),
+            options,
+            writer,
+        }
     }
 
-    pub(crate) fn compile(&mut self) -> String {
-        let mut compiled_code = String::from("package main\nimport (\"fmt\")\n");
-        compiled_code.push_str(&format!(
-            "func main() {{\n{}}}\n",
-            self.compile_statements(&self.bound_statements.clone())
-        ));
-        compiled_code
-    }
-
-    fn compile_statements(&mut self, statements: &[BoundStatement]) -> String {
-        let mut compiled_code = String::new();
-        for statement in statements {
-            compiled_code.push_str(&self.compile_statement(statement));
-        }
-        compiled_code
-    }
-
-    fn compile_statement(&mut self, statement: &BoundStatement) -> String {
-        let mut compiled_statement = String::new();
-        match statement {
-            BoundStatement::Expression(statement) => compiled_statement.push_str(&format!(
-                "{}\n",
-                self.compile_expression(&statement.expression)
-            )),
-            BoundStatement::Print(statement) => compiled_statement.push_str(&format!(
-                "fmt.Println({})\n",
-                self.compile_expression(&statement.expression)
-            )),
-            BoundStatement::Var(statement) => {
-                compiled_statement.push_str(&format!(
-                    "{}:={}\n",
-                    statement.name,
-                    self.compile_expression(&statement.expression)
-                ));
-            }
-            BoundStatement::Block(statement) => compiled_statement.push_str(&format!(
-                "{{{}}}",
-                self.compile_statements(&statement.statements)
-            )),
-            BoundStatement::If(statement) => {
-                compiled_statement.push_str(&format!(
-                    "if {} {{\n {} }}\n",
-                    self.compile_expression(&statement.condition),
-                    self.compile_statement(&statement.consequence)
-                ));
-                if let Some(alternative) = *statement.else_clause.clone() {
-                    compiled_statement.push_str(&format!(
-                        "else {{\n {} }}\n",
-                        self.compile_statement(&alternative)
-                    ));
+    /// Returns the Go code and where its statements come from in the source.
+    pub(crate) fn compile(mut self) -> (String, Vec<Mapping>) {
+        if self.options.header {
+            self.writer.line(&format!(
+                "// Code generated by crabtgo from {}. DO NOT EDIT.",
+                self.options.source_name
+            ));
+            self.writer.blank_line();
+        }
+        self.writer
+            .line(&format!("package {}", self.options.package));
+        self.writer.blank_line();
+        let checks = !self.usage.operations.is_empty();
+        if self.usage.prints || checks {
+            self.writer.open("import (");
+            self.writer.line("\"fmt\"");
+            if checks {
+                self.writer.line("\"os\"");
+                if self.options.line_directives {
+                    self.writer.line("\"runtime\"");
                 }
             }
-            BoundStatement::While(statement) => {
-                compiled_statement.push_str(&format!(
-                    "for {} {{\n {} }}\n",
-                    self.compile_expression(&statement.condition),
-                    self.compile_statement(&statement.body)
-                ));
-            }
-            BoundStatement::For(statement) => {
-                compiled_statement.push_str(&format!(
-                    "for {} := {}; {} < {}; {}++ {{\n {} }}\n",
-                    &statement.identifier,
-                    self.compile_expression(&statement.lower_bound),
-                    self.compile_expression(&statement.lower_bound),
-                    self.compile_expression(&statement.upper_bound),
-                    &statement.identifier,
-                    self.compile_statement(&statement.body)
-                ));
+            self.writer.close(")");
+            self.writer.blank_line();
+        }
+        self.writer.open("func main() {");
+        self.compile_statements(self.program.root());
+        self.layout.remaining_comments(&mut self.writer);
+        self.writer.close("}");
+        for &operation in &self.usage.operations {
+            self.writer.blank_line();
+            Helper::from(operation).write(&mut self.writer);
+        }
+        if checks {
+            self.writer.blank_line();
+            write_fail(&mut self.writer, self.options.line_directives);
+        }
+        self.writer.finish()
+    }
+
+    fn compile_statements(&mut self, statements: &[BoundStatementId]) {
+        for &statement in statements {
+            let position = self.program.statement(statement).get_position();
+            self.layout.comments(&mut self.writer, position.start);
+            self.layout.separate(&mut self.writer, position.start);
+            self.mark(&position);
+            self.visit_statement(self.program, statement);
+        }
+    }
+
+    /// Compiles the body of an `if`, `while` or `for`. The braces come from the statement, so
+    /// a block body only contributes its statements.
+    fn compile_body(&mut self, statement: BoundStatementId) {
+        match self.program.statement(statement) {
+            BoundStatement::Block(block) => self.compile_block_body(block),
+            _ => self.compile_statements(&[statement]),
+        }
+    }
+
+    fn compile_block_body(&mut self, block: &BoundBlockStatement) {
+        self.compile_statements(&block.statements);
+        self.layout
+            .comments(&mut self.writer, block.get_position().end);
+    }
+
+    /// Compiles an `if` and the `else if` chain after it as one statement.
+    fn compile_if_statement(&mut self, statement: &BoundIfStatement) {
+        let chain = IfChain::of(self.program, statement);
+        for (index, branch) in chain.branches.iter().enumerate() {
+            let condition = self.compile_expression(branch.condition);
+            if index == 0 {
+                self.writer.open(&format!("if {condition} {{"));
+            } else {
+                self.mark(&branch.get_position());
+                self.writer.reopen(&format!("}} else if {condition} {{"));
             }
+            self.compile_body(branch.consequence);
+        }
+        if let Some(alternative) = chain.alternative {
+            self.writer.reopen("} else {");
+            self.compile_body(alternative);
         }
-        compiled_statement
+        self.writer.close("}");
     }
 
-    fn compile_expression(&mut self, bound_expression: &BoundExpression) -> String {
-        let mut compiled_expression = String::new();
-        match bound_expression {
-            BoundExpression::Literal(expression) => {
-                let value = match &expression.value {
-                    Object::Unit => "nil".to_string(),
-                    Object::Number(v) => format!("{v}"),
-                    Object::Boolean(v) => format!("{v}"),
-                    Object::String(v) => format!("\"{v}\""),
+    /// Writes a statement that fits on one line, followed by the comment that trails it in the
+    /// source, if any.
+    fn simple_statement(&mut self, line: String, start: usize) {
+        self.layout.simple_statement(&mut self.writer, line, start);
+    }
+
+    /// Maps the next line written to where `position` starts in the source.
+    fn mark(&mut self, position: &Position) {
+        self.writer.mark(
+            position.get_line(self.source),
+            position.get_column(self.source),
+        );
+    }
+
+    /// Compiles an expression that stands on its own, like a statement or a single argument.
+    fn compile_expression(&self, expression: BoundExpressionId) -> String {
+        self.compile_expression_at(expression, 1)
+    }
+
+    /// Compiles a bound of a `for` loop. Go would make a constant bound an `int`, which cannot
+    /// be compared with the `int32` variable.
+    fn compile_bound(&self, bound: BoundExpressionId) -> String {
+        if self.is_untyped_constant(bound) {
+            format!("int32({})", self.compile_expression(bound))
+        } else {
+            self.compile_expression(bound)
+        }
+    }
+
+    /// Compiles an expression `depth` levels into the surrounding one. Like gofmt, binary
+    /// operators are spaced out at the top level and written tight deeper down, so that
+    /// `a + b*c` shows how it groups.
+    fn compile_expression_at(&self, expression: BoundExpressionId, depth: usize) -> String {
+        match self.program.expression(expression) {
+            BoundExpression::Literal(expression) => match &expression.value {
+                Object::Unit => "nil".to_string(),
+                Object::Number(v) => format!("{v}"),
+                Object::Boolean(v) => format!("{v}"),
+                Object::String(v) => string_literal(v),
+            },
+            BoundExpression::Variable(expression) => self.names.variable(&expression.variable),
+            BoundExpression::Unary(unary) => {
+                if let Some(helper) = Helper::of(self.program, expression) {
+                    return format!(
+                        "{}({})",
+                        helper.name(),
+                        self.compile_expression(unary.right)
+                    );
+                }
+                let operator = unary.operator.operation_kind.to_string();
+                let right = self.compile_operand(unary.right, &operator, UNARY_PRECEDENCE, depth);
+                format!("{operator}{right}")
+            }
+            BoundExpression::Binary(binary) => {
+                if self.is_sequenced(expression) {
+                    return self.compile_sequenced(expression, binary);
+                }
+                if let Some(helper) = Helper::of(self.program, expression) {
+                    return format!(
+                        "{}({}, {})",
+                        helper.name(),
+                        self.compile_expression(binary.left),
+                        self.compile_expression(binary.right)
+                    );
+                }
+                let operation = &binary.operator.operation_kind;
+                let operator = operation.to_string();
+                let precedence = binary_precedence(operation);
+                let left_depth = match self.program.expression(binary.left) {
+                    BoundExpression::Binary(left)
+                        if binary_precedence(&left.operator.operation_kind) == precedence =>
+                    {
+                        depth
+                    }
+                    _ => depth + 1,
                 };
-                compiled_expression.push_str(&format!("{}", value));
+                let left = self.compile_operand(binary.left, "", precedence, left_depth);
+                // Go's binary operators associate to the left, so an operand on the right
+                // needs parentheses even when it binds as tightly as the operator.
+                let right =
+                    self.compile_operand(binary.right, &operator, precedence + 1, depth + 1);
+                if precedence < self.cutoff(expression, depth) {
+                    format!("{left} {operator} {right}")
+                } else {
+                    format!("{left}{operator}{right}")
+                }
             }
-            BoundExpression::Variable(expression) => {
-                compiled_expression.push_str(&format!("{}", expression.name))
+            // An assignment is a statement in Go. Where crab uses its value, it is made in a
+            // function literal that returns the variable, which keeps it where it is evaluated.
+            BoundExpression::Assignment(expression) => {
+                let name = self.names.variable(&expression.variable);
+                format!(
+                    "func() {} {{ {name} = {}; return {name} }}()",
+                    go_type(&expression.variable.typ),
+                    self.compile_expression(expression.expression)
+                )
             }
-            BoundExpression::Unary(expression) => {
-                compiled_expression.push_str(&format!("{}", expression.operator.operation_kind));
-                compiled_expression
-                    .push_str(&format!("{}", self.compile_expression(&expression.right)));
+        }
+    }
+
+    /// Compiles a binary expression whose operands must be evaluated in order as a function
+    /// literal that stores them before combining them.
+    fn compile_sequenced(
+        &self,
+        expression: BoundExpressionId,
+        binary: &BoundBinaryExpression,
+    ) -> String {
+        let program = self.program;
+        let left = self.names.fresh("left");
+        let right = self.names.fresh("right");
+        let value = match Helper::of(program, expression) {
+            Some(helper) => format!("{}({left}, {right})", helper.name()),
+            None => format!("{left} {} {right}", binary.operator.operation_kind),
+        };
+        format!(
+            "func() {} {{ var {left} {} = {}; var {right} {} = {}; return {value} }}()",
+            go_type(&program.expression(expression).get_type()),
+            go_type(&program.expression(binary.left).get_type()),
+            self.compile_expression(binary.left),
+            go_type(&program.expression(binary.right).get_type()),
+            self.compile_expression(binary.right),
+        )
+    }
+
+    /// Whether a binary expression must evaluate its operands in order, because `&&` or `||`
+    /// could skip the right one unnoticed, or because Go could evaluate them the other way.
+    fn is_sequenced(&self, expression: BoundExpressionId) -> bool {
+        match self.program.expression(expression) {
+            BoundExpression::Binary(binary) if is_logical(&binary.operator.operation_kind) => {
+                !is_inert(self.program, binary.right)
             }
+            BoundExpression::Binary(binary) => self.interfere(binary.left, binary.right),
+            _ => false,
+        }
+    }
+
+    /// Whether Go could evaluate two expressions in one statement in an order that shows: when
+    /// one assigns a variable, Go may read the variables of the other before or after it does.
+    /// Calls are the only thing Go evaluates from left to right, so the checks of the helpers
+    /// fail in order.
+    fn interfere(&self, first: BoundExpressionId, second: BoundExpressionId) -> bool {
+        let program = self.program;
+        writes(program, first, None) && reads(program, second)
+            || writes(program, second, None) && reads(program, first)
+    }
+
+    /// Compiles an assignment that stands on its own as a Go assignment statement.
+    fn compile_assignment(&self, assignment: &BoundAssignmentExpression) -> String {
+        format!(
+            "{} = {}",
+            self.names.variable(&assignment.variable),
+            self.compile_expression(assignment.expression)
+        )
+    }
+
+    /// Compiles an operand, in parentheses when it binds more loosely than `precedence`, or
+    /// when its sign would merge with the operator before it: `a - -b` must not become `a--b`.
+    fn compile_operand(
+        &self,
+        expression: BoundExpressionId,
+        operator: &str,
+        precedence: u8,
+        depth: usize,
+    ) -> String {
+        let compiled = self.compile_expression_at(expression, depth);
+        let merges = ["+", "-"]
+            .iter()
+            .any(|sign| operator.ends_with(sign) && compiled.starts_with(sign));
+        if self.precedence(expression) < precedence || merges {
+            // Parentheses start a new expression one level up.
+            let depth = depth.saturating_sub(1).max(1);
+            format!("({})", self.compile_expression_at(expression, depth))
+        } else {
+            compiled
+        }
+    }
+
+    /// The precedence below which gofmt puts spaces around the binary operators of an
+    /// expression: at the top level, only around the loosest operators when additions and
+    /// multiplications mix, and everywhere otherwise; deeper down, only below additions.
+    fn cutoff(&self, expression: BoundExpressionId, depth: usize) -> u8 {
+        let (additive, multiplicative) = self.operator_levels(expression);
+        match (additive && multiplicative, depth) {
+            (true, 1) => 5,
+            (false, 1) => 6,
+            _ => 4,
+        }
+    }
+
+    /// Whether a binary expression uses additive and multiplicative operators, looking through
+    /// the operands that are written without parentheses.
+    fn operator_levels(&self, expression: BoundExpressionId) -> (bool, bool) {
+        let BoundExpression::Binary(binary) = self.program.expression(expression) else {
+            return (false, false);
+        };
+        let precedence = binary_precedence(&binary.operator.operation_kind);
+        let mut levels = (precedence == 4, precedence == 5);
+        for (operand, minimum) in [(binary.left, precedence), (binary.right, precedence + 1)] {
+            if matches!(self.program.expression(operand), BoundExpression::Binary(_))
+                && Helper::of(self.program, operand).is_none()
+                && !self.is_sequenced(operand)
+                && self.precedence(operand) >= minimum
+            {
+                let (additive, multiplicative) = self.operator_levels(operand);
+                levels.0 |= additive;
+                levels.1 |= multiplicative;
+            }
+        }
+        levels
+    }
+
+    /// Whether Go would give an expression a type of its own choosing, rather than `int32`:
+    /// numbers written without a variable or a helper in them.
+    fn is_untyped_constant(&self, expression: BoundExpressionId) -> bool {
+        if Helper::of(self.program, expression).is_some() {
+            return false;
+        }
+        match self.program.expression(expression) {
+            BoundExpression::Literal(_) => true,
+            BoundExpression::Unary(unary) => self.is_untyped_constant(unary.right),
+            _ => false,
+        }
+    }
+
+    /// How tightly the Go code for an expression binds, using Go's precedence levels. Calls to
+    /// helpers and function literals bind like any other operand.
+    fn precedence(&self, expression: BoundExpressionId) -> u8 {
+        if Helper::of(self.program, expression).is_some() || self.is_sequenced(expression) {
+            return PRIMARY_PRECEDENCE;
+        }
+        match self.program.expression(expression) {
+            BoundExpression::Literal(_) | BoundExpression::Variable(_) => PRIMARY_PRECEDENCE,
+            BoundExpression::Unary(_) => UNARY_PRECEDENCE,
             BoundExpression::Binary(expression) => {
-                compiled_expression
-                    .push_str(&format!("{}", self.compile_expression(&expression.left)));
-                compiled_expression.push_str(&format!("{}", expression.operator.operation_kind));
-                compiled_expression
-                    .push_str(&format!("{}", self.compile_expression(&expression.right)));
+                binary_precedence(&expression.operator.operation_kind)
+            }
+            BoundExpression::Assignment(_) => PRIMARY_PRECEDENCE,
+        }
+    }
+}
+
+/// Statements are compiled as the visitor reaches them. Expressions are not visited: they are
+/// compiled to strings, with the precedence of the surrounding expression in mind.
+impl BoundTreeVisitor for Compiler<'_> {
+    fn visit_expression_statement(
+        &mut self,
+        program: &BoundProgram,
+        statement: &BoundExpressionStatement,
+    ) {
+        let line = match program.expression(statement.expression) {
+            BoundExpression::Assignment(assignment) => self.compile_assignment(assignment),
+            _ => format!("_ = {}", self.compile_expression(statement.expression)),
+        };
+        self.simple_statement(line, statement.get_position().start);
+    }
+
+    fn visit_print_statement(&mut self, _program: &BoundProgram, statement: &BoundPrintStatement) {
+        let line = format!(
+            "fmt.Println({})",
+            self.compile_expression(statement.expression)
+        );
+        self.simple_statement(line, statement.get_position().start);
+    }
+
+    fn visit_var_statement(&mut self, _program: &BoundProgram, statement: &BoundVarStatement) {
+        let position = statement.get_position();
+        let name = self.names.variable(&statement.variable);
+        let expression = self.compile_expression(statement.expression);
+        let line = match statement.variable.typ {
+            Type::Number => format!("var {name} int32 = {expression}"),
+            _ => format!("{name} := {expression}"),
+        };
+        self.simple_statement(line, position.start);
+        if !self.usage.reads.contains(&statement.variable.id) {
+            self.mark(&position);
+            self.writer.line(&format!("_ = {name}"));
+        }
+    }
+
+    fn visit_block_statement(&mut self, _program: &BoundProgram, statement: &BoundBlockStatement) {
+        self.writer.open("{");
+        self.compile_block_body(statement);
+        self.writer.close("}");
+    }
+
+    fn visit_if_statement(&mut self, _program: &BoundProgram, statement: &BoundIfStatement) {
+        self.compile_if_statement(statement);
+    }
+
+    fn visit_while_statement(&mut self, _program: &BoundProgram, statement: &BoundWhileStatement) {
+        let condition = self.compile_expression(statement.condition);
+        self.writer.open(&format!("for {condition} {{"));
+        self.compile_body(statement.body);
+        self.writer.close("}");
+    }
+
+    fn visit_for_statement(&mut self, _program: &BoundProgram, statement: &BoundForStatement) {
+        // Like crabi, evaluate both bounds once, and after every iteration test the value the
+        // body left in the variable before incrementing it. The bounds are evaluated before the
+        // variable is declared, since it may shadow a variable they read.
+        let position = statement.get_position();
+        let variable = self.names.variable(&statement.variable);
+        let counter = self.names.fresh(&format!("{variable}_counter"));
+        let limit = self.names.fresh(&format!("{variable}_limit"));
+        let lower_bound = self.compile_bound(statement.lower_bound);
+        let upper_bound = self.compile_bound(statement.upper_bound);
+        self.writer.open("{");
+        self.mark(&position);
+        if self.interfere(statement.lower_bound, statement.upper_bound) {
+            self.writer.line(&format!("{counter} := {lower_bound}"));
+            self.mark(&position);
+            self.writer.line(&format!("{limit} := {upper_bound}"));
+        } else {
+            self.writer.line(&format!(
+                "{counter}, {limit} := {lower_bound}, {upper_bound}"
+            ));
+        }
+        self.mark(&position);
+        self.writer
+            .line(&format!("var {variable} int32 = {counter}"));
+        self.mark(&position);
+        self.writer.open(&format!(
+            "for ; {counter} < {limit}; {counter}, {variable} = {variable}, {}({variable}, 1) {{",
+            Helper::Add.name()
+        ));
+        self.compile_body(statement.body);
+        self.writer.close("}");
+        self.writer.close("}");
+    }
+}
+
+const UNARY_PRECEDENCE: u8 = 6;
+const PRIMARY_PRECEDENCE: u8 = 7;
+
+fn binary_precedence(operation: &BoundBinaryOperationKind) -> u8 {
+    match operation {
+        BoundBinaryOperationKind::Multiplication | BoundBinaryOperationKind::Division => 5,
+        BoundBinaryOperationKind::Addition | BoundBinaryOperationKind::Subtraction => 4,
+        BoundBinaryOperationKind::Greater
+        | BoundBinaryOperationKind::Lesser
+        | BoundBinaryOperationKind::GreaterEqual
+        | BoundBinaryOperationKind::LesserEqual
+        | BoundBinaryOperationKind::NotEqual
+        | BoundBinaryOperationKind::Equal => 3,
+        BoundBinaryOperationKind::LogicalAnd => 2,
+        BoundBinaryOperationKind::LogicalOr => 1,
+    }
+}
+
+fn is_logical(operation: &BoundBinaryOperationKind) -> bool {
+    matches!(
+        operation,
+        BoundBinaryOperationKind::LogicalAnd | BoundBinaryOperationKind::LogicalOr
+    )
+}
+
+fn go_type(typ: &Type) -> &'static str {
+    match typ {
+        Type::Unit => "any",
+        Type::Number => "int32",
+        Type::Boolean => "bool",
+        Type::String => "string",
+    }
+}
+
+/// Writes text as a Go string literal. Control characters are escaped, so that the literal
+/// stays on one line; everything else is written as it is, since Go source is UTF-8 too.
+fn string_literal(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for character in text.chars() {
+        match character {
+            '\\' => literal.push_str("\\\\"),
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            '\r' => literal.push_str("\\r"),
+            character if character.is_control() => {
+                literal.push_str(&format!("\\u{:04x}", character as u32))
             }
-            BoundExpression::Assignment(expression) => compiled_expression.push_str(&format!(
-                "{}={}",
-                expression.name,
-                self.compile_expression(&expression.expression)
-            )),
+            character => literal.push(character),
         }
-        compiled_expression
     }
+    literal.push('"');
+    literal
 }
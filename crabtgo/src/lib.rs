@@ -0,0 +1,7 @@
+mod backend;
+mod compiler;
+mod names;
+mod runtime;
+mod source_map;
+
+pub use backend::{is_package_name, GoBackend, GoOptions};
@@ -0,0 +1,115 @@
+use crab::{
+    backend::names::{Names, Scoping},
+    common::intern::Interner,
+};
+
+use super::runtime::{Helper, FAIL};
+
+/// Go's keywords, which can never name anything.
+const KEYWORDS: [&str; 25] = [
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "type",
+    "var",
+];
+
+/// Names that Go lets a program shadow but that the generated code relies on or that would
+/// confuse a reader: the predeclared identifiers, the imported package and the functions the
+/// runtime calls itself. `_` is here too, since Go never lets it be read. So are the functions
+/// the generated code defines.
+const RESERVED: [&str; 50] = [
+    "_",
+    "any",
+    "append",
+    "bool",
+    "byte",
+    "cap",
+    "clear",
+    "close",
+    "comparable",
+    "complex",
+    "complex64",
+    "complex128",
+    "copy",
+    "delete",
+    "error",
+    "false",
+    "float32",
+    "float64",
+    "fmt",
+    "imag",
+    "init",
+    "int",
+    "int8",
+    "int16",
+    "int32",
+    "int64",
+    "iota",
+    "len",
+    "main",
+    "make",
+    "max",
+    "min",
+    "new",
+    "nil",
+    "os",
+    "panic",
+    "print",
+    "println",
+    "real",
+    "recover",
+    "rune",
+    "runtime",
+    "string",
+    "true",
+    "uint",
+    "uint8",
+    "uint16",
+    "uint32",
+    "uint64",
+    "uintptr",
+];
+
+/// Turns the names of a program into Go identifiers. Go shadows names the way crab does, so a
+/// name always maps to the same identifier, whatever declares it.
+pub(crate) fn names(interner: &Interner) -> Names<'_> {
+    Names::new(interner, &KEYWORDS, is_reserved, Scoping::Shadowing)
+}
+
+/// Whether Go would take a name as an identifier, as opposed to a keyword or anything else.
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut characters = name.chars();
+    characters
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && characters.all(|character| character.is_ascii_alphanumeric() || character == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+/// Whether Go would misread a name other than a keyword, or the generated code relies on it.
+fn is_reserved(name: &str) -> bool {
+    RESERVED.contains(&name)
+        || name == FAIL
+        || Helper::ALL.iter().any(|helper| helper.name() == name)
+}
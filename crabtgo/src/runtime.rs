@@ -0,0 +1,120 @@
+use crab::{
+    backend::writer::Writer,
+    binding::bound_tree::{BoundExpressionId, BoundProgram},
+    common::arithmetic::{ArithmeticError, CheckedOperation},
+};
+
+/// The function that stops a program on a runtime error, like crabi does.
+pub(crate) const FAIL: &str = "fail";
+
+/// Functions the generated code calls for the arithmetic that crab checks and Go does not: Go's
+/// `int32` wraps around on overflow and panics on division by zero, where every crab backend
+/// reports a runtime error and exits with 70.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Helper {
+    Negate,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Helper {
+    pub(crate) const ALL: [Helper; 5] = [
+        Helper::Negate,
+        Helper::Add,
+        Helper::Subtract,
+        Helper::Multiply,
+        Helper::Divide,
+    ];
+
+    /// The helper that computes an expression, if Go's operator would not do.
+    pub(crate) fn of(program: &BoundProgram, expression: BoundExpressionId) -> Option<Helper> {
+        CheckedOperation::of(program, expression).map(Helper::from)
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Helper::Negate => "checkedNeg",
+            Helper::Add => "checkedAdd",
+            Helper::Subtract => "checkedSub",
+            Helper::Multiply => "checkedMul",
+            Helper::Divide => "checkedDiv",
+        }
+    }
+
+    fn operator(self) -> &'static str {
+        match self {
+            Helper::Negate | Helper::Subtract => "-",
+            Helper::Add => "+",
+            Helper::Multiply => "*",
+            Helper::Divide => "/",
+        }
+    }
+
+    /// Writes the definition of the helper. Sums, differences and products are computed in 64
+    /// bits, where they cannot overflow, and checked against the range of `int32`.
+    pub(crate) fn write(self, writer: &mut Writer) {
+        let overflow = fail(ArithmeticError::Overflow, self.operator());
+        match self {
+            Helper::Negate => {
+                writer.open(&format!("func {}(right int32) int32 {{", self.name()));
+                writer.open("if right == -1<<31 {");
+                writer.line(&overflow);
+                writer.close("}");
+                writer.line("return -right");
+            }
+            Helper::Divide => {
+                writer.open(&format!("func {}(left, right int32) int32 {{", self.name()));
+                writer.open("if right == 0 {");
+                writer.line(&fail(ArithmeticError::DivisionByZero, self.operator()));
+                writer.close("}");
+                writer.open("if left == -1<<31 && right == -1 {");
+                writer.line(&overflow);
+                writer.close("}");
+                writer.line("return left / right");
+            }
+            _ => {
+                writer.open(&format!("func {}(left, right int32) int32 {{", self.name()));
+                writer.line(&format!(
+                    "result := int64(left) {} int64(right)",
+                    self.operator()
+                ));
+                writer.open("if result != int64(int32(result)) {");
+                writer.line(&overflow);
+                writer.close("}");
+                writer.line("return int32(result)");
+            }
+        }
+        writer.close("}");
+    }
+}
+
+impl From<CheckedOperation> for Helper {
+    fn from(operation: CheckedOperation) -> Self {
+        match operation {
+            CheckedOperation::Negation => Helper::Negate,
+            CheckedOperation::Addition => Helper::Add,
+            CheckedOperation::Subtraction => Helper::Subtract,
+            CheckedOperation::Multiplication => Helper::Multiply,
+            CheckedOperation::Division => Helper::Divide,
+        }
+    }
+}
+
+/// Writes the function the helpers call when an operation fails. With `//line` directives, the
+/// line of the statement that called the helper is a line of the source, so it is reported too.
+pub(crate) fn write_fail(writer: &mut Writer, line_directives: bool) {
+    writer.open(&format!("func {FAIL}(message string) {{"));
+    if line_directives {
+        writer.line("_, _, line, _ := runtime.Caller(2)");
+        writer.line("fmt.Fprintf(os.Stderr, \"[runtime error in line: %d]\\n\", line)");
+    }
+    writer.line("fmt.Fprintf(os.Stderr, \"Runtime error: %s.\\n\", message)");
+    writer.line("os.Exit(70)");
+    writer.close("}");
+}
+
+fn fail(error: ArithmeticError, operator: &str) -> String {
+    format!("{FAIL}(\"{}\")", error.message(operator))
+}
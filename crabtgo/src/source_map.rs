@@ -0,0 +1,44 @@
+use crab::backend::writer::Mapping;
+
+/// Writes where the lines of a generated Go file come from as JSON, for tools that read Go
+/// positions and want to show the `.crab` ones instead:
+///
+/// `{"version": 1, "file": "a.go", "source": "a.crab", "mappings": [{"generated_line": 9, "line": 1, "column": 1}]}`
+pub(crate) fn source_map(file: &str, source: &str, mappings: &[Mapping]) -> String {
+    let mut output = String::from("{\n");
+    output.push_str("  \"version\": 1,\n");
+    output.push_str(&format!("  \"file\": {},\n", json_string(file)));
+    output.push_str(&format!("  \"source\": {},\n", json_string(source)));
+    output.push_str("  \"mappings\": [");
+    for (index, mapping) in mappings.iter().enumerate() {
+        if index > 0 {
+            output.push(',');
+        }
+        output.push_str(&format!(
+            "\n    {{\"generated_line\": {}, \"line\": {}, \"column\": {}}}",
+            mapping.generated_line, mapping.line, mapping.column
+        ));
+    }
+    if !mappings.is_empty() {
+        output.push_str("\n  ");
+    }
+    output.push_str("]\n}\n");
+    output
+}
+
+fn json_string(text: &str) -> String {
+    let mut output = String::from("\"");
+    for character in text.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            character if character.is_control() => {
+                output.push_str(&format!("\\u{:04x}", character as u32));
+            }
+            character => output.push(character),
+        }
+    }
+    output.push('"');
+    output
+}
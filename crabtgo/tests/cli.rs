@@ -0,0 +1,222 @@
+//! Checks crabtgo's command line: where the Go code goes, the package it declares, checking
+//! without writing, several inputs at once, exit codes, and handing the code to the Go
+//! toolchain, which a stand-in `go` script plays here so that Go need not be installed.
+
+use std::{
+    env::{join_paths, split_paths, temp_dir, var_os},
+    fs::{create_dir_all, read_to_string, remove_file, write},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn crabtgo(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_crabtgo"))
+        .args(args)
+        .output()
+        .expect("Could not run crabtgo.")
+}
+
+fn program(name: &str, source: &str) -> PathBuf {
+    let path = temp_dir().join(format!("crabtgo_cli_{name}.crab"));
+    write(&path, source).unwrap();
+    let _ = remove_file(path.with_extension("go"));
+    path
+}
+
+fn text(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn output_path_and_stdout() {
+    let path = program("output", "print 1\n");
+    let output = temp_dir().join("crabtgo_cli_elsewhere.go");
+    assert!(crabtgo(&["-o", text(&output), text(&path)])
+        .status
+        .success());
+    assert!(read_to_string(&output)
+        .unwrap()
+        .starts_with("package main\n"));
+    assert!(!path.with_extension("go").exists());
+
+    let printed = crabtgo(&["--stdout", text(&path)]);
+    assert!(printed.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&printed.stdout),
+        read_to_string(&output).unwrap()
+    );
+    assert!(!path.with_extension("go").exists());
+}
+
+#[test]
+fn package_name() {
+    let path = program("package", "print 1\n");
+    let printed = crabtgo(&["--stdout", "--package", "samples", text(&path)]);
+    assert!(String::from_utf8_lossy(&printed.stdout).starts_with("package samples\n"));
+    for invalid in ["1st", "func", "_", "a-b"] {
+        let output = crabtgo(&["--stdout", "--package", invalid, text(&path)]);
+        assert_eq!(output.status.code(), Some(65), "{invalid}");
+    }
+    let output = crabtgo(&["run", "--package", "samples", text(&path)]);
+    assert_eq!(output.status.code(), Some(65));
+}
+
+#[test]
+fn several_inputs_are_transpiled_side_by_side() {
+    let first = program("first", "print 1\n");
+    let second = program("second", "print 2\n");
+    assert!(crabtgo(&[text(&first), text(&second)]).status.success());
+    assert!(read_to_string(first.with_extension("go"))
+        .unwrap()
+        .contains("fmt.Println(1)"));
+    assert!(read_to_string(second.with_extension("go"))
+        .unwrap()
+        .contains("fmt.Println(2)"));
+}
+
+#[test]
+fn diagnostics_fail_the_run() {
+    let good = program("good", "print 1\n");
+    let bad = program("bad", "print missing\n");
+    let output = crabtgo(&[text(&good), text(&bad)]);
+    assert_eq!(output.status.code(), Some(65));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("In {}:", text(&bad))), "{stderr}");
+    assert!(good.with_extension("go").exists());
+    assert!(!bad.with_extension("go").exists());
+}
+
+#[test]
+fn check_writes_nothing() {
+    let good = program("check_good", "var unused = 1\n");
+    let output = crabtgo(&["check", text(&good)]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warning"));
+    assert!(!good.with_extension("go").exists());
+
+    let bad = program("check_bad", "print 1 +\n");
+    assert_eq!(crabtgo(&["check", text(&bad)]).status.code(), Some(65));
+}
+
+#[test]
+fn diagnostics_are_reported_in_source_order() {
+    // The unused variable is found before the constant condition, which comes first.
+    let path = program("order", "while false print 1\nvar unused = 2\n");
+    let output = crabtgo(&["check", text(&path)]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let condition = stderr.find("Condition is always 'false'").unwrap();
+    let unused = stderr.find("Variable 'unused' is never read").unwrap();
+    assert!(condition < unused, "{stderr}");
+}
+
+#[test]
+fn invalid_arguments() {
+    let path = program("arguments", "print 1\n");
+    for args in [
+        &[][..],
+        &["--unknown", text(&path)],
+        &["-o"],
+        &["-o", "a.go", text(&path), text(&path)],
+        &["--stdout", "--source-map", text(&path)],
+        &["run", "-o", "program", text(&path)],
+    ] {
+        let output = crabtgo(args);
+        assert_eq!(output.status.code(), Some(65), "{args:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Usage:"));
+    }
+    assert_eq!(
+        crabtgo(&["crabtgo_cli_missing.crab"]).status.code(),
+        Some(66)
+    );
+}
+
+/// Runs crabtgo with a `go` on the path that records its arguments and "builds" a shell script
+/// that prints a line and exits with 3.
+fn with_stand_in_go(name: &str, args: &[&str]) -> (Output, String) {
+    let bin = temp_dir().join(format!("crabtgo_cli_go_{name}"));
+    create_dir_all(&bin).unwrap();
+    let log = bin.join("arguments");
+    let _ = remove_file(&log);
+    let go = bin.join("go");
+    write(
+        &go,
+        format!(
+            "#!/bin/sh\necho \"$@\" > '{}'\nprintf '#!/bin/sh\\necho ran\\nexit 3\\n' > \"$3\"\nchmod +x \"$3\"\n",
+            log.display()
+        ),
+    )
+    .unwrap();
+    Command::new("chmod").arg("+x").arg(&go).status().unwrap();
+    let path = join_paths(
+        [bin.clone()]
+            .into_iter()
+            .chain(split_paths(&var_os("PATH").unwrap_or_default())),
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_crabtgo"))
+        .args(args)
+        .env("PATH", path)
+        .output()
+        .expect("Could not run crabtgo.");
+    (output, read_to_string(&log).unwrap_or_default())
+}
+
+#[cfg(unix)]
+#[test]
+fn build_and_run_hand_the_code_to_go() {
+    let path = program("build", "print 1\n");
+    let executable = temp_dir().join("crabtgo_cli_built");
+    let (output, arguments) =
+        with_stand_in_go("build", &["build", "-o", text(&executable), text(&path)]);
+    assert!(output.status.success());
+    assert_eq!(
+        arguments.trim_end(),
+        format!(
+            "build -o {} {}",
+            executable.display(),
+            path.with_extension("go").display()
+        )
+    );
+    assert!(executable.exists());
+
+    let (output, _) = with_stand_in_go("run", &["run", text(&path)]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ran\n");
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn build_without_go_fails() {
+    let path = program("no_go", "print 1\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_crabtgo"))
+        .args(["build", text(&path)])
+        .env("PATH", "")
+        .output()
+        .expect("Could not run crabtgo.");
+    assert_eq!(output.status.code(), Some(69));
+}
+
+#[test]
+fn the_nesting_limit_is_an_option() {
+    let path = program("nesting", "print ((((1))))\n");
+    assert!(crabtgo(&["--stdout", text(&path)]).status.success());
+    let output = crabtgo(&["--stdout", "--max-depth", "3", text(&path)]);
+    assert_eq!(output.status.code(), Some(65));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Error: Nesting is deeper than 3 levels.")
+    );
+    for invalid in ["0", "deep"] {
+        let output = crabtgo(&["--stdout", "--max-depth", invalid, text(&path)]);
+        assert_eq!(output.status.code(), Some(65), "{invalid}");
+    }
+    assert_eq!(crabtgo(&["--max-depth"]).status.code(), Some(65));
+}
+
+#[test]
+fn literals_out_of_range_are_rejected() {
+    let path = program("out_of_range", "print 3000000000\n");
+    let output = crabtgo(&["--stdout", text(&path)]);
+    assert_eq!(output.status.code(), Some(65));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Error: Integer literal '3000000000' is out of range."));
+}
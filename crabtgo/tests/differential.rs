@@ -0,0 +1,304 @@
+//! Transpiles the differential corpus and, when a Go toolchain is installed, runs the generated
+//! code and compares what it prints with the expected output that crabi is held to. Programs
+//! in `failures/` must exit like crabi does on a runtime error, with the same message. Set
+//! `CRAB_REQUIRE_GO` to make a missing toolchain fail the tests instead.
+
+use std::{
+    env::{temp_dir, var_os},
+    fs::{copy, read_dir, read_to_string, write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+fn transpile(source: &Path, args: &[&str]) -> PathBuf {
+    let path = temp_dir().join(format!(
+        "crabtgo_differential_{}",
+        source.file_name().unwrap().to_string_lossy()
+    ));
+    copy(source, &path).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_crabtgo"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .expect("Could not run crabtgo.");
+    assert!(output.status.success(), "{} failed", source.display());
+    path.with_extension("go")
+}
+
+fn go_is_installed() -> bool {
+    let installed = Command::new("go")
+        .arg("version")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    assert!(
+        installed || var_os("CRAB_REQUIRE_GO").is_none(),
+        "CRAB_REQUIRE_GO is set, but Go is not installed."
+    );
+    installed
+}
+
+#[test]
+fn generated_go_prints_the_expected_output() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus");
+    let run = go_is_installed();
+    if !run {
+        eprintln!("Go is not installed; only checking that the corpus transpiles.");
+    }
+    for entry in read_dir(corpus).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "crab") {
+            continue;
+        }
+        for args in [&[][..], &["-O"]] {
+            let go = transpile(&path, args);
+            if run {
+                let output = Command::new("go").arg("run").arg(&go).output().unwrap();
+                assert_eq!(
+                    String::from_utf8_lossy(&output.stdout),
+                    read_to_string(path.with_extension("out")).unwrap(),
+                    "The Go code for {} {args:?} prints something else",
+                    path.display()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn generated_go_fails_like_crabi() {
+    let failures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus/failures");
+    let run = go_is_installed();
+    for entry in read_dir(failures).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "crab") {
+            continue;
+        }
+        for args in [&[][..], &["-O"]] {
+            let go = transpile(&path, args);
+            if run {
+                let output = Command::new("go").arg("run").arg(&go).output().unwrap();
+                assert_eq!(
+                    output.status.code(),
+                    Some(70),
+                    "{} {args:?}",
+                    path.display()
+                );
+                assert_eq!(
+                    String::from_utf8_lossy(&output.stdout),
+                    read_to_string(path.with_extension("out")).unwrap(),
+                    "The Go code for {} {args:?} prints something else",
+                    path.display()
+                );
+                let error = read_to_string(path.with_extension("err")).unwrap();
+                assert!(String::from_utf8_lossy(&output.stderr)
+                    .contains(&format!("Runtime error: {}.", error.trim_end())));
+            }
+        }
+    }
+}
+
+#[test]
+fn numbers_are_declared_as_int32() {
+    let go = read_to_string(transpile(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus/arithmetic.crab"),
+        &[],
+    ))
+    .unwrap();
+    for expected in [
+        "var largest int32 = 2147483647\n",
+        "var smallest int32 = checkedSub(checkedNeg(largest), 1)\n",
+        "fmt.Println(checkedMul(largest, -1))",
+        "fmt.Println(checkedDiv(-7, 2))",
+        "func checkedNeg(right int32) int32 {",
+        "\t\tfail(\"Division by zero\")\n",
+        "\tos.Exit(70)\n",
+    ] {
+        assert!(go.contains(expected), "Expected {expected:?} in:\n{go}");
+    }
+}
+
+#[test]
+fn folded_literals_below_zero_are_negated_with_a_check() {
+    let go = read_to_string(transpile(
+        &Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../tests/corpus/failures/folded_negation_overflow.crab"),
+        &["-O"],
+    ))
+    .unwrap();
+    assert!(
+        go.contains("fmt.Println(checkedNeg(-2147483648))\n"),
+        "{go}"
+    );
+}
+
+#[test]
+fn parentheses_follow_go_precedence() {
+    let path = temp_dir().join("crabtgo_differential_parentheses.crab");
+    write(
+        &path,
+        "var a = 1\nvar s = \"b\"\nprint !(a < 2 && a > 0)\nprint (a < 2 || a > 5) && a != 3\nprint \"a\" + (s + \"c\")\nprint -(a + 1) < -5\nprint (a + 2) * 3\n",
+    )
+    .unwrap();
+    let go = read_to_string(transpile(&path, &[])).unwrap();
+    for expected in [
+        "fmt.Println(!(a < 2 && a > 0))",
+        "fmt.Println((a < 2 || a > 5) && a != 3)",
+        "fmt.Println(\"a\" + (s + \"c\"))",
+        "fmt.Println(checkedNeg(checkedAdd(a, 1)) < -5)",
+        "fmt.Println(checkedMul(checkedAdd(a, 2), 3))",
+    ] {
+        assert!(go.contains(expected), "Expected {expected} in:\n{go}");
+    }
+}
+
+#[test]
+fn for_loops_test_the_variable_against_bounds_evaluated_once() {
+    let path = temp_dir().join("crabtgo_differential_for.crab");
+    write(
+        &path,
+        "var low = 1\nvar high = 3\nfor i = low + 1 to high * 2 high = high + 1\nprint high\n",
+    )
+    .unwrap();
+    let go = read_to_string(transpile(&path, &[])).unwrap();
+    assert!(
+        go.contains(
+            "i_counter, i_limit := checkedAdd(low, 1), checkedMul(high, 2)\n\t\tvar i int32 = i_counter\n"
+        ),
+        "{go}"
+    );
+    assert!(
+        go.contains("for ; i_counter < i_limit; i_counter, i = i, checkedAdd(i, 1) {"),
+        "{go}"
+    );
+    assert_eq!(go.matches("checkedAdd(low, 1)").count(), 1, "{go}");
+    assert_eq!(go.matches("checkedMul(high, 2)").count(), 1, "{go}");
+}
+
+#[test]
+fn for_loop_bounds_are_evaluated_before_the_variable_shadows_them() {
+    let go = read_to_string(transpile(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus/for_loops.crab"),
+        &[],
+    ))
+    .unwrap();
+    assert!(
+        go.contains(
+            "\t\tk_counter, k_limit := int32(0), checkedAdd(k, 2)\n\t\tvar k int32 = k_counter\n"
+        ),
+        "{go}"
+    );
+}
+
+#[test]
+fn assignments_used_as_values_are_made_in_function_literals() {
+    let go = read_to_string(transpile(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus/assignment_values.crab"),
+        &[],
+    ))
+    .unwrap();
+    for expected in [
+        "var y int32 = func() int32 { c = checkedAdd(c, 1); return c }()\n",
+        "fmt.Println(checkedAdd(func() int32 { c = 5; return c }(), 1))\n",
+        "fmt.Println(func() string { s = \"crab\"; return s }() + \"s\")\n",
+        "for func() int32 { n = checkedSub(n, 1); return n }() > 0 {\n",
+    ] {
+        assert!(go.contains(expected), "Expected {expected:?} in:\n{go}");
+    }
+}
+
+#[test]
+fn operands_go_would_skip_or_reorder_are_stored_in_order() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus");
+    let go = read_to_string(transpile(&corpus.join("eager_logic.crab"), &[])).unwrap();
+    assert!(
+        go.contains("fmt.Println(func() bool { var left bool = b; var right bool = func() int32 { a = checkedAdd(a, 5); return a }() == 5; return left && right }())\n"),
+        "{go}"
+    );
+    let go = read_to_string(transpile(&corpus.join("operand_order.crab"), &[])).unwrap();
+    for expected in [
+        "fmt.Println(func() int32 { var left int32 = a; var right int32 = func() int32 { a = 5; return a }(); return checkedAdd(left, right) }())\n",
+        "\t\ti_counter := a\n\t\ti_limit := func() int32 { a = 7; return a }()\n",
+    ] {
+        assert!(go.contains(expected), "Expected {expected:?} in:\n{go}");
+    }
+    let go = read_to_string(transpile(
+        &corpus.join("failures/eager_logic_division.crab"),
+        &[],
+    ))
+    .unwrap();
+    assert!(
+        go.contains("var right bool = checkedDiv(1, zero) == 0; return left && right"),
+        "{go}"
+    );
+}
+
+#[test]
+fn go_keywords_and_predeclared_names_are_mangled() {
+    let go = read_to_string(transpile(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus/go_names.crab"),
+        &[],
+    ))
+    .unwrap();
+    for expected in [
+        "var func_ int32 = 1\n",
+        "var type_1 int32 = 2\n",
+        "var type_ int32 = 3\n",
+        "var range_ int32 = checkedAdd(checkedAdd(func_, type_1), type_)\n",
+        "fmt_ := \"fmt\"\n",
+        "main_ := fmt_ + \"!\"\n",
+        "var __ int32 = checkedMul(len_, 2)\n",
+        "map__counter, map__limit := int32(0), int32(2)\n",
+        "var map_ int32 = map__counter\n",
+        "fmt.Println(checkedAdd(map_, range_))",
+        "fmt.Println(__)",
+    ] {
+        assert!(go.contains(expected), "Expected {expected} in:\n{go}");
+    }
+}
+
+#[test]
+fn go_accepts_bare_expressions_and_unread_variables() {
+    let go = read_to_string(transpile(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus/unused.crab"),
+        &[],
+    ))
+    .unwrap();
+    for expected in [
+        "\t_ = checkedMul(a, b)\n",
+        "\t_ = a\n",
+        "\tvar written int32 = 0\n\t_ = written\n",
+        "\t\twritten = b\n",
+        "\t\t\tvar scratch int32 = checkedMul(i, 2)\n\t\t\t_ = scratch\n",
+    ] {
+        assert!(go.contains(expected), "Expected {expected:?} in:\n{go}");
+    }
+    assert!(!go.contains("\tvar a int32 = 6\n\t_ = a\n"), "{go}");
+}
+
+#[test]
+fn fmt_is_only_imported_by_programs_that_print() {
+    let path = temp_dir().join("crabtgo_differential_silent.crab");
+    write(&path, "var a = 1\na = 2\n").unwrap();
+    let go = read_to_string(transpile(&path, &[])).unwrap();
+    assert_eq!(
+        go,
+        "package main\n\nfunc main() {\n\tvar a int32 = 1\n\t_ = a\n\ta = 2\n}\n"
+    );
+}
+
+#[test]
+fn strings_are_quoted_for_go() {
+    let go = read_to_string(transpile(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/corpus/strings.crab"),
+        &[],
+    ))
+    .unwrap();
+    for expected in [
+        "path := \"C:\\\\new\\\\table\"\n",
+        "fmt.Println(path + \"\\\\\")\n",
+        "fmt.Println(\"one\\ntwo\")\n",
+        "fmt.Println(\"tab:\\tend\")\n",
+    ] {
+        assert!(go.contains(expected), "Expected {expected:?} in:\n{go}");
+    }
+}
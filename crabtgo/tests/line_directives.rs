@@ -0,0 +1,113 @@
+//! Checks that `--line-directives` points every statement of the generated code back into the
+//! source, that `--source-map` records the same places, and, when a Go toolchain is installed,
+//! that a runtime error is reported at the line of the `.crab` file. That last check fails
+//! instead of being skipped when `CRAB_REQUIRE_GO` is set.
+
+use std::{
+    env::{temp_dir, var_os},
+    fs::{read_to_string, write},
+    path::PathBuf,
+    process::Command,
+};
+
+const SOURCE: &str =
+    "var d = 2\n\nwhile d > -1 {\n    print 6 / d\n    d = d - 1\n}\nfor i = 0 to 2 print i\n";
+
+fn transpile(name: &str, args: &[&str]) -> PathBuf {
+    let path = temp_dir().join(format!("crabtgo_lines_{name}.crab"));
+    write(&path, SOURCE).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_crabtgo"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .expect("Could not run crabtgo.");
+    assert!(output.status.success());
+    path.with_extension("go")
+}
+
+#[test]
+fn statements_are_preceded_by_directives() {
+    let go = read_to_string(transpile("directives", &["--line-directives"])).unwrap();
+    let lines: Vec<&str> = go.lines().collect();
+    let after = |directive: &str| {
+        let index = lines
+            .iter()
+            .position(|line| *line == directive)
+            .unwrap_or_else(|| panic!("{directive} is missing:\n{go}"));
+        lines[index + 1]
+    };
+    assert_eq!(
+        after("//line crabtgo_lines_directives.crab:1:4"),
+        "\tvar d int32 = 2"
+    );
+    assert_eq!(
+        after("//line crabtgo_lines_directives.crab:3:6"),
+        "\tfor d > -1 {"
+    );
+    assert_eq!(
+        after("//line crabtgo_lines_directives.crab:4:9"),
+        "\t\tfmt.Println(checkedDiv(6, d))"
+    );
+    assert_eq!(
+        after("//line crabtgo_lines_directives.crab:5:3"),
+        "\t\td = checkedSub(d, 1)"
+    );
+    assert!(lines
+        .iter()
+        .filter(|line| line.starts_with("//line"))
+        .all(|line| line.starts_with("//line crabtgo_lines_directives.crab:")));
+}
+
+#[test]
+fn directives_are_opt_in() {
+    let go = read_to_string(transpile("plain", &[])).unwrap();
+    assert!(!go.contains("//line"), "{go}");
+}
+
+#[test]
+fn source_maps_point_at_the_statements() {
+    let go = transpile("map", &["--source-map"]);
+    let code = read_to_string(&go).unwrap();
+    let map = read_to_string(go.with_extension("go.map")).unwrap();
+    assert!(map.contains("\"file\": \"crabtgo_lines_map.go\""), "{map}");
+    assert!(
+        map.contains("\"source\": \"crabtgo_lines_map.crab\""),
+        "{map}"
+    );
+    let print = code
+        .lines()
+        .position(|line| line.contains("fmt.Println(checkedDiv(6, d))"))
+        .unwrap()
+        + 1;
+    assert!(
+        map.contains(&format!(
+            "{{\"generated_line\": {print}, \"line\": 4, \"column\": 11}}"
+        )),
+        "{map}"
+    );
+}
+
+#[test]
+fn runtime_errors_are_reported_in_the_source() {
+    let installed = Command::new("go")
+        .arg("version")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    assert!(
+        installed || var_os("CRAB_REQUIRE_GO").is_none(),
+        "CRAB_REQUIRE_GO is set, but Go is not installed."
+    );
+    if !installed {
+        eprintln!("Go is not installed; skipping.");
+        return;
+    }
+    let go = transpile("error", &["--line-directives"]);
+    let output = Command::new("go").arg("run").arg(&go).output().unwrap();
+    assert_eq!(output.status.code(), Some(70));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[runtime error in line: 4]"), "{stderr}");
+    assert!(
+        stderr.contains("Runtime error: Division by zero."),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,39 @@
+//! Checks that `-O` reaches the generated Go code.
+
+use std::{
+    env::temp_dir,
+    fs::{read_to_string, write},
+    process::Command,
+};
+
+fn transpile(name: &str, source: &str, optimize: bool) -> String {
+    let path = temp_dir().join(format!("crabtgo_optimizer_{name}.crab"));
+    write(&path, source).unwrap();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_crabtgo"));
+    if optimize {
+        command.arg("-O");
+    }
+    let output = command.arg(&path).output().expect("Could not run crabtgo.");
+    assert!(output.status.success());
+    read_to_string(path.with_extension("go")).unwrap()
+}
+
+#[test]
+fn constants_are_folded() {
+    let source = "print 2 * 3 + 4\n";
+    assert!(
+        transpile("plain", source, false).contains("fmt.Println(checkedAdd(checkedMul(2, 3), 4))")
+    );
+    assert!(transpile("folded", source, true).contains("fmt.Println(10)"));
+}
+
+#[test]
+fn constant_branches_are_pruned() {
+    let go = transpile(
+        "pruned",
+        "if 1 < 2 print \"taken\" else print \"pruned\"\n",
+        true,
+    );
+    assert!(go.contains("\"taken\""));
+    assert!(!go.contains("\"pruned\""));
+}
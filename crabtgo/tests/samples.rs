@@ -0,0 +1,57 @@
+//! Transpiles the samples in `tests/` that have a Go twin and checks that the output is exactly
+//! the committed file, which is laid out the way gofmt lays it out.
+
+use std::{
+    env::temp_dir,
+    fs::{copy, read_dir, read_to_string, write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+fn transpile(source: &Path, args: &[&str]) -> String {
+    let path: PathBuf = temp_dir().join(format!(
+        "crabtgo_samples_{}",
+        source.file_name().unwrap().to_string_lossy()
+    ));
+    copy(source, &path).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_crabtgo"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .expect("Could not run crabtgo.");
+    assert!(output.status.success(), "{} failed", source.display());
+    read_to_string(path.with_extension("go")).unwrap()
+}
+
+#[test]
+fn samples_match_their_go_twins() {
+    let samples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests");
+    for entry in read_dir(samples).unwrap() {
+        let path = entry.unwrap().path();
+        let twin = path.with_extension("go");
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "crab")
+            && twin.exists()
+        {
+            assert_eq!(
+                transpile(&path, &[]),
+                read_to_string(&twin).unwrap(),
+                "{} does not transpile to {}",
+                path.display(),
+                twin.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn header_names_the_source_file() {
+    let path = temp_dir().join("header.crab");
+    write(&path, "print 1\n").unwrap();
+    let go = transpile(&path, &["--header"]);
+    assert!(go.starts_with(
+        "// Code generated by crabtgo from crabtgo_samples_header.crab. DO NOT EDIT.\n\npackage main\n"
+    ));
+    assert!(transpile(&path, &[]).starts_with("package main\n"));
+}
@@ -0,0 +1,30 @@
+// Code generated by crabtc from add.crab. DO NOT EDIT.
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static void crab_fail(const char *message) {
+    fprintf(stderr, "Runtime error: %s.\n", message);
+    exit(70);
+}
+
+static int32_t crab_add(int32_t left, int32_t right) {
+    int64_t result = (int64_t)left + right;
+    if (result < INT32_MIN || result > INT32_MAX) {
+        crab_fail("Arithmetic overflow in '+' operation");
+    }
+    return (int32_t)result;
+}
+
+static void crab_print_number(int32_t number) {
+    printf("%ld\n", (long)number);
+}
+
+int main(void) {
+    int32_t a = 5;
+    int32_t b = 5;
+    crab_print_number(crab_add(a, b));
+}
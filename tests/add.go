@@ -2,10 +2,24 @@ package main
 
 import (
 	"fmt"
+	"os"
 )
 
 func main() {
-	a := 5
-	b := 5
-	fmt.Println(a + b)
+	var a int32 = 5
+	var b int32 = 5
+	fmt.Println(checkedAdd(a, b))
+}
+
+func checkedAdd(left, right int32) int32 {
+	result := int64(left) + int64(right)
+	if result != int64(int32(result)) {
+		fail("Arithmetic overflow in '+' operation")
+	}
+	return int32(result)
+}
+
+func fail(message string) {
+	fmt.Fprintf(os.Stderr, "Runtime error: %s.\n", message)
+	os.Exit(70)
 }
@@ -0,0 +1,106 @@
+// Code generated by crabtc from countdown.crab. DO NOT EDIT.
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+// Strings written in the program are never freed and have no count.
+typedef struct crab_string {
+    size_t references;
+    size_t length;
+    const char *bytes;
+} crab_string;
+
+static void crab_fail(const char *message) {
+    fprintf(stderr, "Runtime error: %s.\n", message);
+    exit(70);
+}
+
+static int32_t crab_negate(int32_t right) {
+    if (right == INT32_MIN) {
+        crab_fail("Arithmetic overflow in '-' operation");
+    }
+    return -right;
+}
+
+static int32_t crab_add(int32_t left, int32_t right) {
+    int64_t result = (int64_t)left + right;
+    if (result < INT32_MIN || result > INT32_MAX) {
+        crab_fail("Arithmetic overflow in '+' operation");
+    }
+    return (int32_t)result;
+}
+
+static int32_t crab_subtract(int32_t left, int32_t right) {
+    int64_t result = (int64_t)left - right;
+    if (result < INT32_MIN || result > INT32_MAX) {
+        crab_fail("Arithmetic overflow in '-' operation");
+    }
+    return (int32_t)result;
+}
+
+static int32_t crab_multiply(int32_t left, int32_t right) {
+    int64_t result = (int64_t)left * right;
+    if (result < INT32_MIN || result > INT32_MAX) {
+        crab_fail("Arithmetic overflow in '*' operation");
+    }
+    return (int32_t)result;
+}
+
+static int32_t crab_divide(int32_t left, int32_t right) {
+    if (right == 0) {
+        crab_fail("Division by zero");
+    }
+    if (left == INT32_MIN && right == -1) {
+        crab_fail("Arithmetic overflow in '/' operation");
+    }
+    return left / right;
+}
+
+static void crab_print_number(int32_t number) {
+    printf("%ld\n", (long)number);
+}
+
+static void crab_release(crab_string *string) {
+    if (string->references > 0 && --string->references == 0) {
+        free(string);
+    }
+}
+
+static void crab_print_string(crab_string *string) {
+    fwrite(string->bytes, 1, string->length, stdout);
+    putchar('\n');
+    crab_release(string);
+}
+
+static crab_string literal1 = {0, 5, "three"};
+static crab_string literal2 = {0, 3, "two"};
+static crab_string literal3 = {0, 3, "one"};
+
+int main(void) {
+    int32_t temporary1;
+    int32_t temporary2;
+
+    // Counts down and classifies.
+    int32_t n = 3; // start here
+
+    // The loop.
+    while (n > 0) {
+        // inside
+        if (n == 3) {
+            crab_print_string(&literal1);
+        } else if (n == 2) {
+            crab_print_string(&literal2); // trailing
+        } else {
+            crab_print_string(&literal3);
+            // last in block
+        }
+        n = crab_subtract(n, 1);
+    }
+    int32_t a = 1;
+    int32_t b = 2;
+    crab_print_number((temporary1 = crab_multiply(crab_add(a, b), 3), crab_subtract(temporary1, (temporary2 = crab_multiply(a, b), crab_divide(temporary2, crab_subtract(a, crab_negate(b)))))));
+    // the end
+}
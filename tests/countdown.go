@@ -0,0 +1,75 @@
+package main
+
+import (
+	"fmt"
+	"os"
+)
+
+func main() {
+	// Counts down and classifies.
+	var n int32 = 3 // start here
+
+	// The loop.
+	for n > 0 {
+		// inside
+		if n == 3 {
+			fmt.Println("three")
+		} else if n == 2 {
+			fmt.Println("two") // trailing
+		} else {
+			fmt.Println("one")
+			// last in block
+		}
+		n = checkedSub(n, 1)
+	}
+	var a int32 = 1
+	var b int32 = 2
+	fmt.Println(checkedSub(checkedMul(checkedAdd(a, b), 3), checkedDiv(checkedMul(a, b), checkedSub(a, checkedNeg(b)))))
+	// the end
+}
+
+func checkedNeg(right int32) int32 {
+	if right == -1<<31 {
+		fail("Arithmetic overflow in '-' operation")
+	}
+	return -right
+}
+
+func checkedAdd(left, right int32) int32 {
+	result := int64(left) + int64(right)
+	if result != int64(int32(result)) {
+		fail("Arithmetic overflow in '+' operation")
+	}
+	return int32(result)
+}
+
+func checkedSub(left, right int32) int32 {
+	result := int64(left) - int64(right)
+	if result != int64(int32(result)) {
+		fail("Arithmetic overflow in '-' operation")
+	}
+	return int32(result)
+}
+
+func checkedMul(left, right int32) int32 {
+	result := int64(left) * int64(right)
+	if result != int64(int32(result)) {
+		fail("Arithmetic overflow in '*' operation")
+	}
+	return int32(result)
+}
+
+func checkedDiv(left, right int32) int32 {
+	if right == 0 {
+		fail("Division by zero")
+	}
+	if left == -1<<31 && right == -1 {
+		fail("Arithmetic overflow in '/' operation")
+	}
+	return left / right
+}
+
+func fail(message string) {
+	fmt.Fprintf(os.Stderr, "Runtime error: %s.\n", message)
+	os.Exit(70)
+}
@@ -0,0 +1,45 @@
+// Code generated by crabtc from factorial.crab. DO NOT EDIT.
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static void crab_fail(const char *message) {
+    fprintf(stderr, "Runtime error: %s.\n", message);
+    exit(70);
+}
+
+static int32_t crab_add(int32_t left, int32_t right) {
+    int64_t result = (int64_t)left + right;
+    if (result < INT32_MIN || result > INT32_MAX) {
+        crab_fail("Arithmetic overflow in '+' operation");
+    }
+    return (int32_t)result;
+}
+
+static int32_t crab_multiply(int32_t left, int32_t right) {
+    int64_t result = (int64_t)left * right;
+    if (result < INT32_MIN || result > INT32_MAX) {
+        crab_fail("Arithmetic overflow in '*' operation");
+    }
+    return (int32_t)result;
+}
+
+static void crab_print_number(int32_t number) {
+    printf("%ld\n", (long)number);
+}
+
+int main(void) {
+    int32_t factorial = 1;
+
+    {
+        int32_t i = 1;
+        for (int32_t i_counter = i, i_limit = 5; i_counter < i_limit; i_counter = i, i = crab_add(i, 1)) {
+            factorial = crab_multiply(factorial, i);
+        }
+    }
+
+    crab_print_number(factorial);
+}
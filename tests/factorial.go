@@ -2,12 +2,40 @@ package main
 
 import (
 	"fmt"
+	"os"
 )
 
 func main() {
-	factorial := 1
-	for i := 1; 1 < 5; i++ {
-		factorial = factorial * i
+	var factorial int32 = 1
+
+	{
+		i_counter, i_limit := int32(1), int32(5)
+		var i int32 = i_counter
+		for ; i_counter < i_limit; i_counter, i = i, checkedAdd(i, 1) {
+			factorial = checkedMul(factorial, i)
+		}
 	}
+
 	fmt.Println(factorial)
 }
+
+func checkedAdd(left, right int32) int32 {
+	result := int64(left) + int64(right)
+	if result != int64(int32(result)) {
+		fail("Arithmetic overflow in '+' operation")
+	}
+	return int32(result)
+}
+
+func checkedMul(left, right int32) int32 {
+	result := int64(left) * int64(right)
+	if result != int64(int32(result)) {
+		fail("Arithmetic overflow in '*' operation")
+	}
+	return int32(result)
+}
+
+func fail(message string) {
+	fmt.Fprintf(os.Stderr, "Runtime error: %s.\n", message)
+	os.Exit(70)
+}
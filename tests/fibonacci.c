@@ -0,0 +1,47 @@
+// Code generated by crabtc from fibonacci.crab. DO NOT EDIT.
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static void crab_fail(const char *message) {
+    fprintf(stderr, "Runtime error: %s.\n", message);
+    exit(70);
+}
+
+static int32_t crab_add(int32_t left, int32_t right) {
+    int64_t result = (int64_t)left + right;
+    if (result < INT32_MIN || result > INT32_MAX) {
+        crab_fail("Arithmetic overflow in '+' operation");
+    }
+    return (int32_t)result;
+}
+
+static int32_t crab_subtract(int32_t left, int32_t right) {
+    int64_t result = (int64_t)left - right;
+    if (result < INT32_MIN || result > INT32_MAX) {
+        crab_fail("Arithmetic overflow in '-' operation");
+    }
+    return (int32_t)result;
+}
+
+static void crab_print_number(int32_t number) {
+    printf("%ld\n", (long)number);
+}
+
+int main(void) {
+    int32_t previous = 0;
+    int32_t current = 1;
+    int32_t n = 10;
+
+    while (n > 1) {
+        int32_t t = current;
+        current = crab_add(previous, current);
+        previous = t;
+        n = crab_subtract(n, 1);
+    }
+
+    crab_print_number(current);
+}
@@ -2,19 +2,41 @@ package main
 
 import (
 	"fmt"
+	"os"
 )
 
 func main() {
-	previous := 0
-	current := 1
-	n := 10
+	var previous int32 = 0
+	var current int32 = 1
+	var n int32 = 10
+
 	for n > 1 {
-		{
-			t := current
-			current = previous + current
-			previous = t
-			n = n - 1
-		}
+		var t int32 = current
+		current = checkedAdd(previous, current)
+		previous = t
+		n = checkedSub(n, 1)
 	}
+
 	fmt.Println(current)
 }
+
+func checkedAdd(left, right int32) int32 {
+	result := int64(left) + int64(right)
+	if result != int64(int32(result)) {
+		fail("Arithmetic overflow in '+' operation")
+	}
+	return int32(result)
+}
+
+func checkedSub(left, right int32) int32 {
+	result := int64(left) - int64(right)
+	if result != int64(int32(result)) {
+		fail("Arithmetic overflow in '-' operation")
+	}
+	return int32(result)
+}
+
+func fail(message string) {
+	fmt.Fprintf(os.Stderr, "Runtime error: %s.\n", message)
+	os.Exit(70)
+}
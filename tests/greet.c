@@ -0,0 +1,70 @@
+// Code generated by crabtc from greet.crab. DO NOT EDIT.
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+// Strings written in the program are never freed and have no count.
+typedef struct crab_string {
+    size_t references;
+    size_t length;
+    const char *bytes;
+} crab_string;
+
+static void crab_fail(const char *message) {
+    fprintf(stderr, "Runtime error: %s.\n", message);
+    exit(70);
+}
+
+static crab_string *crab_retain(crab_string *string) {
+    if (string->references > 0) {
+        string->references++;
+    }
+    return string;
+}
+
+static void crab_release(crab_string *string) {
+    if (string->references > 0 && --string->references == 0) {
+        free(string);
+    }
+}
+
+static crab_string *crab_concatenate(crab_string *left, crab_string *right) {
+    size_t length = left->length + right->length;
+    crab_string *result = malloc(sizeof(crab_string) + length);
+    char *bytes;
+    if (result == NULL) {
+        crab_fail("Out of memory");
+    }
+    bytes = (char *)(result + 1);
+    memcpy(bytes, left->bytes, left->length);
+    memcpy(bytes + left->length, right->bytes, right->length);
+    result->references = 1;
+    result->length = length;
+    result->bytes = bytes;
+    crab_release(left);
+    crab_release(right);
+    return result;
+}
+
+static void crab_print_string(crab_string *string) {
+    fwrite(string->bytes, 1, string->length, stdout);
+    putchar('\n');
+    crab_release(string);
+}
+
+static crab_string literal1 = {0, 5, "hello"};
+static crab_string literal2 = {0, 5, "utsho"};
+static crab_string literal3 = {0, 2, ", "};
+static crab_string literal4 = {0, 1, "!"};
+
+int main(void) {
+    crab_string *greet = &literal1;
+    crab_string *name = &literal2;
+
+    crab_print_string(crab_concatenate(crab_concatenate(crab_concatenate(crab_retain(greet), &literal3), crab_retain(name)), &literal4));
+    crab_release(name);
+    crab_release(greet);
+}
@@ -7,5 +7,6 @@ import (
 func main() {
 	greet := "hello"
 	name := "utsho"
+
 	fmt.Println(greet + ", " + name + "!")
 }
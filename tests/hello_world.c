@@ -0,0 +1,41 @@
+// Code generated by crabtc from hello_world.crab. DO NOT EDIT.
+
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+// Strings written in the program are never freed and have no count.
+typedef struct crab_string {
+    size_t references;
+    size_t length;
+    const char *bytes;
+} crab_string;
+
+static crab_string *crab_retain(crab_string *string) {
+    if (string->references > 0) {
+        string->references++;
+    }
+    return string;
+}
+
+static void crab_release(crab_string *string) {
+    if (string->references > 0 && --string->references == 0) {
+        free(string);
+    }
+}
+
+static void crab_print_string(crab_string *string) {
+    fwrite(string->bytes, 1, string->length, stdout);
+    putchar('\n');
+    crab_release(string);
+}
+
+static crab_string literal1 = {0, 13, "Hello, World!"};
+
+int main(void) {
+    crab_string *greet = &literal1;
+    crab_print_string(crab_retain(greet));
+    crab_release(greet);
+}